@@ -13,11 +13,16 @@
 // limitations under the License.
 
 #![deny(clippy::all)]
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsStr;
-use std::io::{BufRead, BufReader, ErrorKind};
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags};
 use nix::sys::statfs::{fstatfs, CGROUP2_SUPER_MAGIC};
 use openat::{AsPath, Dir, SimpleType};
 use thiserror::Error;
@@ -135,11 +140,11 @@ impl CgroupReader {
         self.read_singleline_stat_file("memory.current")
     }
 
-    /// Read memory.high - returning memory.high consumption in bytes
+    /// Read a limit from a file that has a single line
     /// Will return -1 if the content is max
     /// Will return None if the file is missing
-    pub fn read_memory_high(&self) -> Result<Option<i64>> {
-        match self.read_singleline_stat_file("memory.high") {
+    fn read_singleline_limit_file(&self, file_name: &str) -> Result<Option<i64>> {
+        match self.read_singleline_stat_file(file_name) {
             Ok(v) => Ok(Some(v as i64)),
             Err(Error::IoError(_, e)) if e.kind() == ErrorKind::NotFound => Ok(None),
             Err(Error::UnexpectedLine(_, line)) if line.starts_with("max") => Ok(Some(-1)),
@@ -147,6 +152,156 @@ impl CgroupReader {
         }
     }
 
+    /// Read memory.high - returning memory.high consumption in bytes
+    /// Will return -1 if the content is max
+    /// Will return None if the file is missing
+    pub fn read_memory_high(&self) -> Result<Option<i64>> {
+        self.read_singleline_limit_file("memory.high")
+    }
+
+    /// Read memory.max - returning the hard memory limit in bytes
+    /// Will return -1 if the content is max
+    /// Will return None if the file is missing
+    pub fn read_memory_max(&self) -> Result<Option<i64>> {
+        self.read_singleline_limit_file("memory.max")
+    }
+
+    /// Read memory.low - returning the best-effort memory protection in
+    /// bytes
+    /// Will return -1 if the content is max
+    /// Will return None if the file is missing
+    pub fn read_memory_low(&self) -> Result<Option<i64>> {
+        self.read_singleline_limit_file("memory.low")
+    }
+
+    /// Read memory.min - returning the hard memory protection in bytes
+    /// Will return -1 if the content is max
+    /// Will return None if the file is missing
+    pub fn read_memory_min(&self) -> Result<Option<i64>> {
+        self.read_singleline_limit_file("memory.min")
+    }
+
+    /// Read memory.swap.max - returning the swap limit in bytes
+    /// Will return -1 if the content is max
+    /// Will return None if the file is missing
+    pub fn read_memory_swap_max(&self) -> Result<Option<i64>> {
+        self.read_singleline_limit_file("memory.swap.max")
+    }
+
+    /// Read pids.max - returning the limit of the number of processes
+    /// Will return -1 if the content is max
+    /// Will return None if the file is missing
+    pub fn read_pids_max(&self) -> Result<Option<i64>> {
+        self.read_singleline_limit_file("pids.max")
+    }
+
+    /// Read cpu.max - returning the cpu bandwidth limit
+    pub fn read_cpu_max(&self) -> Result<CpuMax> {
+        let file_name = "cpu.max";
+        let file = self
+            .dir
+            .open_file(file_name)
+            .map_err(|e| self.io_error(file_name, e))?;
+        let buf_reader = BufReader::new(file);
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| self.io_error(file_name, e))?;
+            // e.g. "max 100000" or "50000 100000"
+            let items = line.split_whitespace().collect::<Vec<_>>();
+            if items.len() != 2 {
+                return Err(self.unexpected_line(file_name, line));
+            }
+            let max_usec = match items[0] {
+                "max" => -1,
+                v => v
+                    .parse::<i64>()
+                    .map_err(|_| self.unexpected_line(file_name, line.clone()))?,
+            };
+            let period_usec = items[1]
+                .parse::<u64>()
+                .map_err(|_| self.unexpected_line(file_name, line.clone()))?;
+            return Ok(CpuMax {
+                max_usec: Some(max_usec),
+                period_usec: Some(period_usec),
+            });
+        }
+        Err(self.invalid_file_format(file_name))
+    }
+
+    /// Read cpu.weight - returning the relative cpu weight
+    pub fn read_cpu_weight(&self) -> Result<u32> {
+        self.read_singleline_stat_file("cpu.weight")
+            .map(|v| v as u32)
+    }
+
+    /// Read io.max - returning the limits of each device that has any
+    pub fn read_io_max(&self) -> Result<BTreeMap<String, IoMax>> {
+        let file_name = "io.max";
+        let file = self
+            .dir
+            .open_file(file_name)
+            .map_err(|e| self.io_error(file_name, e))?;
+        let buf_reader = BufReader::new(file);
+        let mut map = BTreeMap::new();
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| self.io_error(file_name, e))?;
+            // e.g. "8:16 rbps=2097152 wbps=max riops=max wiops=120"
+            let items = line.split_whitespace().collect::<Vec<_>>();
+            if items.is_empty() {
+                continue;
+            }
+            let mut io_max = IoMax::default();
+            for item in items.iter().skip(1) {
+                let (key, val) = item
+                    .split_once('=')
+                    .ok_or_else(|| self.unexpected_line(file_name, line.clone()))?;
+                let val = match val {
+                    "max" => -1,
+                    v => v
+                        .parse::<i64>()
+                        .map_err(|_| self.unexpected_line(file_name, line.clone()))?,
+                };
+                match key {
+                    "rbps" => io_max.rbps = Some(val),
+                    "wbps" => io_max.wbps = Some(val),
+                    "riops" => io_max.riops = Some(val),
+                    "wiops" => io_max.wiops = Some(val),
+                    _ => {}
+                }
+            }
+            map.insert(items[0].to_owned(), io_max);
+        }
+        Ok(map)
+    }
+
+    /// Read io.weight - returning the relative io weight of each device
+    /// with a weight of its own, and the weight of all others under the
+    /// "default" key
+    pub fn read_io_weight(&self) -> Result<BTreeMap<String, u32>> {
+        let file_name = "io.weight";
+        let file = self
+            .dir
+            .open_file(file_name)
+            .map_err(|e| self.io_error(file_name, e))?;
+        let buf_reader = BufReader::new(file);
+        let mut map = BTreeMap::new();
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| self.io_error(file_name, e))?;
+            // e.g. "default 100" or "8:16 200"
+            let items = line.split_whitespace().collect::<Vec<_>>();
+            if items.len() != 2 {
+                return Err(self.unexpected_line(file_name, line));
+            }
+            let weight = items[1]
+                .parse::<u32>()
+                .map_err(|_| self.unexpected_line(file_name, line.clone()))?;
+            map.insert(items[0].to_owned(), weight);
+        }
+        if map.is_empty() {
+            return Err(self.invalid_file_format(file_name));
+        }
+        Ok(map)
+    }
+
     /// Read memory.swap.current - returning current cgroup memory
     /// swap consumption in bytes
     pub fn read_memory_swap_current(&self) -> Result<u64> {
@@ -173,6 +328,139 @@ impl CgroupReader {
         MemoryEvents::read(&self)
     }
 
+    /// Read memory.numa_stat - returning the memory.stat breakdown of
+    /// each NUMA node, keyed by node id
+    pub fn read_memory_numa_stat(&self) -> Result<BTreeMap<u32, MemoryNumaStat>> {
+        MemoryNumaStat::read(&self)
+    }
+
+    /// Read a cpuset formatted file, e.g. "0-3,8"
+    fn read_cpuset_file(&self, file_name: &str) -> Result<Cpuset> {
+        let file = self
+            .dir
+            .open_file(file_name)
+            .map_err(|e| self.io_error(file_name, e))?;
+        let buf_reader = BufReader::new(file);
+        let mut cpuset = BTreeSet::new();
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| self.io_error(file_name, e))?;
+            for range in line.split(',').filter(|range| !range.is_empty()) {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                let (start, end) = match (start.parse::<u32>(), end.parse::<u32>()) {
+                    (Ok(start), Ok(end)) if start <= end => (start, end),
+                    _ => return Err(self.unexpected_line(file_name, line.clone())),
+                };
+                cpuset.extend(start..=end);
+            }
+        }
+        Ok(Cpuset(cpuset))
+    }
+
+    /// Read cpuset.cpus.effective - returning the CPUs the cgroup may
+    /// run on
+    pub fn read_cpuset_cpus_effective(&self) -> Result<Cpuset> {
+        self.read_cpuset_file("cpuset.cpus.effective")
+    }
+
+    /// Read cpuset.mems.effective - returning the memory nodes the cgroup
+    /// may allocate from
+    pub fn read_cpuset_mems_effective(&self) -> Result<Cpuset> {
+        self.read_cpuset_file("cpuset.mems.effective")
+    }
+
+    /// Read a cgroup interface file that is not collected by default,
+    /// returning its raw values keyed by `<file>` for single value files,
+    /// `<file>.<key>` for flat keyed files and `<file>.<key>.<sub_key>` for
+    /// nested keyed files
+    pub fn read_extra_file(&self, extra_file: &ExtraFile) -> Result<BTreeMap<String, String>> {
+        let file_name = extra_file.name.as_str();
+        let file = self
+            .dir
+            .open_file(file_name)
+            .map_err(|e| self.io_error(file_name, e))?;
+        let mut map = BTreeMap::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| self.io_error(file_name, e))?;
+            let mut items = line.split_whitespace();
+            match extra_file.format {
+                ExtraFileFormat::SingleValue => {
+                    map.insert(file_name.to_owned(), line.trim().to_owned());
+                    break;
+                }
+                ExtraFileFormat::FlatKeyed => match (items.next(), items.next(), items.next()) {
+                    (Some(key), Some(val), None) => {
+                        map.insert(format!("{}.{}", file_name, key), val.to_owned());
+                    }
+                    _ => return Err(self.unexpected_line(file_name, line)),
+                },
+                ExtraFileFormat::NestedKeyed => {
+                    let key = match items.next() {
+                        Some(key) => key,
+                        None => return Err(self.unexpected_line(file_name, line)),
+                    };
+                    for item in items {
+                        let (sub_key, val) = match item.split_once('=') {
+                            Some(kv) => kv,
+                            None => return Err(self.unexpected_line(file_name, line)),
+                        };
+                        map.insert(format!("{}.{}.{}", file_name, key, sub_key), val.to_owned());
+                    }
+                }
+            }
+        }
+        if map.is_empty() {
+            return Err(self.invalid_file_format(file_name));
+        }
+        Ok(map)
+    }
+
+    /// Read hugetlb.<size>.current - returning the hugetlb usage in bytes
+    /// of each huge page size, keyed by size (e.g. 2MB). The map is empty
+    /// if the hugetlb controller is not enabled.
+    pub fn read_hugetlb_current(&self) -> Result<BTreeMap<String, u64>> {
+        let mut map = BTreeMap::new();
+        for entry in self.dir.list_dir(".").map_err(|e| self.io_error("", e))? {
+            let entry = entry.map_err(|e| self.io_error("", e))?;
+            let file_name = match entry.file_name().to_str() {
+                Some(file_name) => file_name,
+                None => continue,
+            };
+            // Sizes have no dot, unlike e.g. hugetlb.2MB.rsvd.current
+            if let Some(size) = file_name
+                .strip_prefix("hugetlb.")
+                .and_then(|rest| rest.strip_suffix(".current"))
+                .filter(|size| !size.contains('.'))
+            {
+                map.insert(size.to_owned(), self.read_singleline_stat_file(file_name)?);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Read pids.current - returning the number of tasks in the cgroup and
+    /// its descendants
+    pub fn read_pids_current(&self) -> Result<u64> {
+        self.read_singleline_stat_file("pids.current")
+    }
+
+    /// Read pids.events - returning how often forks failed because of
+    /// pids.max
+    pub fn read_pids_events(&self) -> Result<PidsEvents> {
+        PidsEvents::read(&self)
+    }
+
+    /// Read cgroup.stat - returning the number of live and dying
+    /// descendant cgroups
+    pub fn read_cgroup_stat(&self) -> Result<CgroupStat> {
+        CgroupStat::read(&self)
+    }
+
+    /// Read cgroup.events - returning whether the cgroup has live
+    /// processes and whether it is frozen
+    pub fn read_cgroup_events(&self) -> Result<CgroupEvents> {
+        CgroupEvents::read(&self)
+    }
+
     /// Read cpu.pressure
     pub fn read_cpu_pressure(&self) -> Result<CpuPressure> {
         let file_name = "cpu.pressure";
@@ -227,6 +515,38 @@ impl CgroupReader {
         })
     }
 
+    /// Register a PSI trigger on the <resource>.pressure file, which fires
+    /// when tasks of the cgroup were stalled on the resource for at least
+    /// `threshold` within a `window`. The kernel limits the window to
+    /// between 500ms and 10s and fires a trigger at most once per window.
+    pub fn register_pressure_trigger(
+        &self,
+        resource: PressureResource,
+        stall_type: StallType,
+        threshold: Duration,
+        window: Duration,
+    ) -> Result<PressureTrigger> {
+        let file_name = resource.file_name();
+        // Pressure files always exist, so this only opens them for writing
+        let mut file = self
+            .dir
+            .update_file(file_name, 0o644)
+            .map_err(|e| self.io_error(file_name, e))?;
+        // The kernel replaces the last byte written with a NUL, so the
+        // terminator must be included
+        let trigger = format!(
+            "{} {} {}\0",
+            stall_type.name(),
+            threshold.as_micros(),
+            window.as_micros()
+        );
+        file.write_all(trigger.as_bytes())
+            .map_err(|e| self.io_error(file_name, e))?;
+        let mut path = self.relative_path.clone();
+        path.push(file_name);
+        Ok(PressureTrigger { path, file })
+    }
+
     /// Return an iterator over child cgroups
     pub fn child_cgroup_iter(&self) -> Result<impl Iterator<Item = CgroupReader> + '_> {
         Ok(self
@@ -268,6 +588,102 @@ impl CgroupReader {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PressureResource {
+    Cpu,
+    Io,
+    Memory,
+}
+
+impl PressureResource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PressureResource::Cpu => "cpu",
+            PressureResource::Io => "io",
+            PressureResource::Memory => "memory",
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            PressureResource::Cpu => "cpu.pressure",
+            PressureResource::Io => "io.pressure",
+            PressureResource::Memory => "memory.pressure",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StallType {
+    /// At least one task stalled
+    Some,
+    /// All non-idle tasks stalled at the same time
+    Full,
+}
+
+impl StallType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StallType::Some => "some",
+            StallType::Full => "full",
+        }
+    }
+}
+
+/// A registered PSI trigger, which is removed by the kernel when dropped
+pub struct PressureTrigger {
+    path: PathBuf,
+    file: File,
+}
+
+impl PressureTrigger {
+    /// Path of the pressure file relative to the cgroup root
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Wait until at least one of `triggers` fires, or until `timeout` passed if
+/// given, and return the indices of the triggers that fired. A trigger whose
+/// cgroup was removed returns an error.
+pub fn wait_pressure_triggers(
+    triggers: &[PressureTrigger],
+    timeout: Option<Duration>,
+) -> Result<Vec<usize>> {
+    let mut fds: Vec<PollFd> = triggers
+        .iter()
+        .map(|t| PollFd::new(t.file.as_raw_fd(), PollFlags::POLLPRI))
+        .collect();
+    let timeout = timeout.map_or(-1, |t| t.as_millis().min(i32::MAX as u128) as i32);
+    loop {
+        match poll(&mut fds, timeout) {
+            Ok(_) => break,
+            // Interrupted by a signal handler
+            Err(Errno::EINTR) => continue,
+            Err(e) => {
+                return Err(Error::IoError(
+                    PathBuf::new(),
+                    std::io::Error::from_raw_os_error(e as i32),
+                ));
+            }
+        }
+    }
+    let mut fired = Vec::new();
+    for (idx, (fd, trigger)) in fds.iter().zip(triggers).enumerate() {
+        let revents = fd.revents().unwrap_or_else(PollFlags::empty);
+        if revents.contains(PollFlags::POLLERR) {
+            return Err(Error::IoError(
+                trigger.path.clone(),
+                std::io::Error::new(ErrorKind::Other, "PSI trigger failed, cgroup removed?"),
+            ));
+        }
+        if revents.contains(PollFlags::POLLPRI) {
+            fired.push(idx);
+        }
+    }
+    Ok(fired)
+}
+
 // Trait to add a read() method for `key value` formatted files
 trait KVRead: Sized {
     fn read(reader: &CgroupReader) -> Result<Self>;
@@ -277,8 +693,10 @@ trait KVRead: Sized {
 // name, and keys. If a line does not exist in the file then the
 // corresponding field is left as `None`. If lines include fields that
 // are not listed, they are ignored.
+/// Keys not in the field list are ignored, unless a map field is given after
+/// the list, in which case they are kept there.
 macro_rules! key_values_format {
-    ($struct:ident; $file:expr; [ $( $field:ident ),+ ]) => (
+    ($struct:ident; $file:expr; [ $( $field:ident ),+ ] $(; $other:ident)?) => (
         impl KVRead for $struct {
             fn read(r: &CgroupReader) -> Result<$struct> {
                 let mut s = $struct::default();
@@ -295,7 +713,9 @@ macro_rules! key_values_format {
                     let val = items[1].parse::<u64>().map_err(|_| r.unexpected_line(file_name, line.clone()))?;
                     match key.as_ref() {
                         $(stringify!($field) => s.$field = Some(val),)*
-                        _ => (),
+                        _ => {
+                            $(s.$other.insert(key.to_owned(), val);)?
+                        }
                     };
                 }
                 if s == $struct::default() {
@@ -335,13 +755,23 @@ key_values_format!(MemoryStat; memory.stat; [
     unevictable,
     slab_reclaimable,
     slab_unreclaimable,
+    percpu,
+    sec_pagetables,
+    zswap,
+    zswapped,
+    file_thp,
+    shmem_thp,
     pgfault,
     pgmajfault,
     workingset_refault,
+    workingset_refault_anon,
+    workingset_refault_file,
     workingset_activate,
     workingset_nodereclaim,
     pgrefill,
     pgscan,
+    pgscan_direct,
+    pgscan_kswapd,
     pgsteal,
     pgactivate,
     pgdeactivate,
@@ -349,7 +779,7 @@ key_values_format!(MemoryStat; memory.stat; [
     pglazyfreed,
     thp_fault_alloc,
     thp_collapse_alloc
-]);
+]; other);
 
 key_values_format!(MemoryEvents; memory.events; [
     low,
@@ -359,6 +789,86 @@ key_values_format!(MemoryEvents; memory.events; [
     oom_kill
 ]);
 
+key_values_format!(PidsEvents; pids.events; [
+    max
+]);
+
+key_values_format!(CgroupStat; cgroup.stat; [
+    nr_descendants,
+    nr_dying_descendants
+]);
+
+key_values_format!(CgroupEvents; cgroup.events; [
+    populated,
+    frozen
+]);
+
+// Trait to add a read() method for `key N<node>=value` formatted files
+trait NodeKVRead: Sized {
+    fn read(reader: &CgroupReader) -> Result<BTreeMap<u32, Self>>;
+}
+
+// This macro generates the read() method for the given struct, file
+// name, and keys, returning one struct per node. Keys that are not
+// listed are ignored.
+macro_rules! node_key_values_format {
+    ($struct:ident; $file:expr; [ $( $field:ident ),+ ]) => (
+        impl NodeKVRead for $struct {
+            fn read(r: &CgroupReader) -> Result<BTreeMap<u32, $struct>> {
+                let mut map = BTreeMap::new();
+                let file_name = stringify!($file);
+                let file = r.dir.open_file(file_name).map_err(|e| r.io_error(file_name, e))?;
+                let buf_reader = BufReader::new(file);
+                for line in buf_reader.lines() {
+                    let line = line.map_err(|e| r.io_error(file_name, e))?;
+                    // as an example, memory.numa_stat looks like:
+                    // anon N0=1654784 N1=2101248
+                    let items = line.split_whitespace().collect::<Vec<_>>();
+                    if items.is_empty() {
+                        continue;
+                    }
+                    for item in items.iter().skip(1) {
+                        let (node, val) = item
+                            .strip_prefix('N')
+                            .and_then(|item| item.split_once('='))
+                            .and_then(|(node, val)| Some((node.parse::<u32>().ok()?, val.parse::<u64>().ok()?)))
+                            .ok_or_else(|| r.unexpected_line(file_name, line.clone()))?;
+                        let s: &mut $struct = map.entry(node).or_default();
+                        match items[0] {
+                            $(stringify!($field) => s.$field = Some(val),)*
+                            _ => (),
+                        };
+                    }
+                }
+                if map.is_empty() {
+                    Err(r.invalid_file_format(file_name))
+                } else {
+                    Ok(map)
+                }
+            }
+        }
+    )
+}
+
+node_key_values_format!(MemoryNumaStat; memory.numa_stat; [
+    anon,
+    file,
+    kernel_stack,
+    pagetables,
+    shmem,
+    file_mapped,
+    file_dirty,
+    file_writeback,
+    anon_thp,
+    inactive_anon,
+    active_anon,
+    inactive_file,
+    active_file,
+    unevictable,
+    slab_reclaimable,
+    slab_unreclaimable
+]);
+
 // Trait to add a read() method for `<string> key=value` formatted files
 trait NameKVRead: Sized {
     fn read<P: AsRef<Path> + AsPath + Clone>(
@@ -17,11 +17,14 @@ use std::fs::File;
 use std::io::Write;
 use std::os::linux::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use tempfile::TempDir;
 
 use crate::CgroupReader;
 use crate::Error;
+use crate::{wait_pressure_triggers, PressureResource, StallType};
+use crate::{ExtraFile, ExtraFileFormat};
 
 struct TestCgroup {
     tempdir: TempDir,
@@ -174,6 +177,47 @@ fn test_memory_high_failure() {
     assert!(val.is_err());
 }
 
+#[test]
+fn test_memory_limits_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("memory.max", b"4096\n");
+    cgroup.create_file_with_content("memory.low", b"max\n");
+    cgroup.create_file_with_content("memory.min", b"0\n");
+    cgroup.create_file_with_content("pids.max", b"max\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    assert_eq!(
+        cgroup_reader
+            .read_memory_max()
+            .expect("Failed to read memory.max"),
+        Some(4096)
+    );
+    assert_eq!(
+        cgroup_reader
+            .read_memory_low()
+            .expect("Failed to read memory.low"),
+        Some(-1)
+    );
+    assert_eq!(
+        cgroup_reader
+            .read_memory_min()
+            .expect("Failed to read memory.min"),
+        Some(0)
+    );
+    assert_eq!(
+        cgroup_reader
+            .read_memory_swap_max()
+            .expect("Failed to read memory.swap.max"),
+        None
+    );
+    assert_eq!(
+        cgroup_reader
+            .read_pids_max()
+            .expect("Failed to read pids.max"),
+        Some(-1)
+    );
+}
+
 #[test]
 fn test_memory_stat_success() {
     let cgroup = TestCgroup::new();
@@ -186,6 +230,47 @@ fn test_memory_stat_success() {
     assert_eq!(val.slab.expect("Failed to populate slab field"), 1234);
 }
 
+#[test]
+fn test_memory_stat_extended_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content(
+        "memory.stat",
+        b"anon 1\npercpu 2\nzswap 3\nworkingset_refault_file 4\npgscan_kswapd 5\nnew_counter 6\n",
+    );
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_memory_stat()
+        .expect("Failed to read memory.stat");
+    assert_eq!(val.anon, Some(1));
+    assert_eq!(val.percpu, Some(2));
+    assert_eq!(val.zswap, Some(3));
+    assert_eq!(val.workingset_refault_file, Some(4));
+    assert_eq!(val.pgscan_kswapd, Some(5));
+    assert_eq!(
+        val.other.into_iter().collect::<Vec<_>>(),
+        vec![("new_counter".to_owned(), 6)]
+    );
+}
+
+#[test]
+fn test_hugetlb_current_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("hugetlb.2MB.current", b"4194304\n");
+    cgroup.create_file_with_content("hugetlb.1GB.current", b"0\n");
+    cgroup.create_file_with_content("hugetlb.2MB.max", b"max\n");
+    cgroup.create_file_with_content("hugetlb.2MB.rsvd.current", b"2097152\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_hugetlb_current()
+        .expect("Failed to read hugetlb.*.current");
+    assert_eq!(
+        val.into_iter().collect::<Vec<_>>(),
+        vec![("1GB".to_owned(), 0), ("2MB".to_owned(), 4194304)]
+    );
+}
+
 #[test]
 fn test_memory_stat_overflow() {
     let cgroup = TestCgroup::new();
@@ -276,6 +361,46 @@ fn test_cpu_stat_invalid_format() {
     }
 }
 
+#[test]
+fn test_cpu_max_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("cpu.max", b"max 100000\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_cpu_max()
+        .expect("Failed to read cpu.max");
+    assert_eq!(val.max_usec, Some(-1));
+    assert_eq!(val.period_usec, Some(100000));
+
+    cgroup.create_file_with_content("cpu.max", b"50000 100000\n");
+    let val = cgroup_reader
+        .read_cpu_max()
+        .expect("Failed to read cpu.max");
+    assert_eq!(val.max_usec, Some(50000));
+
+    cgroup.create_file_with_content("cpu.max", b"50000\n");
+    let err = cgroup_reader
+        .read_cpu_max()
+        .expect_err("Did not fail to read cpu.max");
+    match err {
+        Error::UnexpectedLine(_, _) => {}
+        _ => panic!("Got unexpected error type: {}", err),
+    }
+}
+
+#[test]
+fn test_cpu_weight_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("cpu.weight", b"100\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_cpu_weight()
+        .expect("Failed to read cpu.weight");
+    assert_eq!(val, 100);
+}
+
 #[test]
 fn test_io_stat_success() {
     let cgroup = TestCgroup::new();
@@ -324,6 +449,50 @@ fn test_io_stat_empty_file() {
     assert!(val.is_empty());
 }
 
+#[test]
+fn test_io_max_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content(
+        "io.max",
+        b"8:16 rbps=2097152 wbps=max riops=max wiops=120\n8:0 rbps=max wbps=max riops=max wiops=max\n",
+    );
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader.read_io_max().expect("Failed to read io.max");
+    assert_eq!(val.len(), 2);
+    let limits = val.get("8:16").expect("Failed to find 8:16 device");
+    assert_eq!(limits.rbps, Some(2097152));
+    assert_eq!(limits.wbps, Some(-1));
+    assert_eq!(limits.riops, Some(-1));
+    assert_eq!(limits.wiops, Some(120));
+
+    cgroup.create_file_with_content("io.max", b"");
+    let val = cgroup_reader.read_io_max().expect("Failed to read io.max");
+    assert!(val.is_empty());
+}
+
+#[test]
+fn test_io_weight_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("io.weight", b"default 100\n8:16 200\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_io_weight()
+        .expect("Failed to read io.weight");
+    assert_eq!(val.get("default"), Some(&100));
+    assert_eq!(val.get("8:16"), Some(&200));
+
+    cgroup.create_file_with_content("io.weight", b"");
+    let err = cgroup_reader
+        .read_io_weight()
+        .expect_err("Did not fail to read io.weight");
+    match err {
+        Error::InvalidFileFormat(_) => {}
+        _ => panic!("Got unexpected error type: {}", err),
+    }
+}
+
 #[test]
 fn test_cpu_pressure_success() {
     let cgroup = TestCgroup::new();
@@ -425,6 +594,139 @@ fn test_memory_pressure_empty_file() {
     }
 }
 
+#[test]
+fn test_register_pressure_trigger() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("memory.pressure", b"");
+
+    let cgroup_reader = cgroup.get_reader();
+    let trigger = cgroup_reader
+        .register_pressure_trigger(
+            PressureResource::Memory,
+            StallType::Full,
+            Duration::from_millis(150),
+            Duration::from_secs(1),
+        )
+        .expect("Failed to register trigger");
+    assert_eq!(trigger.path(), Path::new("memory.pressure"));
+    assert_eq!(
+        std::fs::read(cgroup.path().join("memory.pressure")).expect("Failed to read trigger"),
+        b"full 150000 1000000\0"
+    );
+    // Regular files never signal POLLPRI
+    let fired = wait_pressure_triggers(&[trigger], Some(Duration::from_millis(10)))
+        .expect("Failed to wait for trigger");
+    assert_eq!(fired, Vec::<usize>::new());
+}
+
+#[test]
+fn test_pids_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("pids.current", b"42\n");
+    cgroup.create_file_with_content("pids.events", b"max 3\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_pids_current()
+        .expect("Failed to read pids.current");
+    assert_eq!(val, 42);
+    let val = cgroup_reader
+        .read_pids_events()
+        .expect("Failed to read pids.events");
+    assert_eq!(val.max, Some(3));
+}
+
+#[test]
+fn test_cgroup_stat_and_events_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("cgroup.stat", b"nr_descendants 5\nnr_dying_descendants 2\n");
+    cgroup.create_file_with_content("cgroup.events", b"populated 1\nfrozen 0\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_cgroup_stat()
+        .expect("Failed to read cgroup.stat");
+    assert_eq!(val.nr_descendants, Some(5));
+    assert_eq!(val.nr_dying_descendants, Some(2));
+    let val = cgroup_reader
+        .read_cgroup_events()
+        .expect("Failed to read cgroup.events");
+    assert_eq!(val.populated, Some(1));
+    assert_eq!(val.frozen, Some(0));
+
+    cgroup.create_file_with_content("cgroup.events", b"");
+    let err = cgroup_reader
+        .read_cgroup_events()
+        .expect_err("Did not fail to read cgroup.events");
+    match err {
+        Error::InvalidFileFormat(_) => {}
+        _ => panic!("Got unexpected error type: {}", err),
+    }
+}
+
+#[test]
+fn test_memory_numa_stat_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content(
+        "memory.numa_stat",
+        b"anon N0=1654784 N1=2101248
+file N0=4096 N1=0
+shmem N0=0 N1=0
+workingset_refault_anon N0=0 N1=0
+",
+    );
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_memory_numa_stat()
+        .expect("Failed to read memory.numa_stat");
+    assert_eq!(val.len(), 2);
+    assert_eq!(val[&0].anon, Some(1654784));
+    assert_eq!(val[&0].file, Some(4096));
+    assert_eq!(val[&1].anon, Some(2101248));
+    assert_eq!(val[&1].shmem, Some(0));
+    assert_eq!(val[&1].kernel_stack, None);
+
+    cgroup.create_file_with_content("memory.numa_stat", b"anon 1654784\n");
+    let err = cgroup_reader
+        .read_memory_numa_stat()
+        .expect_err("Did not fail to read memory.numa_stat");
+    match err {
+        Error::UnexpectedLine(_, _) => {}
+        _ => panic!("Got unexpected error type: {}", err),
+    }
+}
+
+#[test]
+fn test_cpuset_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("cpuset.cpus.effective", b"0-3,8,10-11\n");
+    cgroup.create_file_with_content("cpuset.mems.effective", b"0\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let val = cgroup_reader
+        .read_cpuset_cpus_effective()
+        .expect("Failed to read cpuset.cpus.effective");
+    assert_eq!(
+        val.0.iter().cloned().collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 8, 10, 11]
+    );
+    assert_eq!(val.to_string(), "0-3,8,10-11");
+    let val = cgroup_reader
+        .read_cpuset_mems_effective()
+        .expect("Failed to read cpuset.mems.effective");
+    assert_eq!(val.to_string(), "0");
+
+    cgroup.create_file_with_content("cpuset.cpus.effective", b"3-1\n");
+    let err = cgroup_reader
+        .read_cpuset_cpus_effective()
+        .expect_err("Did not fail to read cpuset.cpus.effective");
+    match err {
+        Error::UnexpectedLine(_, _) => {}
+        _ => panic!("Got unexpected error type: {}", err),
+    }
+}
+
 #[test]
 fn test_child_cgroup_iter() {
     let root = TestCgroup::new();
@@ -513,3 +815,75 @@ fn test_validate_cgroup2_fs() {
     let root = TestCgroup::new();
     assert!(root.get_reader_validate().is_err());
 }
+
+#[test]
+fn test_extra_file_success() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("memory.oom.group", b"1\n");
+    cgroup.create_file_with_content("memory.events.local", b"low 0\nhigh 2\n");
+    cgroup.create_file_with_content("io.latency", b"8:0 target=75\n8:16 target=100 x=y\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let read = |name: &str, format| {
+        cgroup_reader
+            .read_extra_file(&ExtraFile {
+                name: name.to_owned(),
+                format,
+            })
+            .map(|map| map.into_iter().collect::<Vec<_>>())
+    };
+    let pairs = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        read("memory.oom.group", ExtraFileFormat::SingleValue).expect("Failed to read"),
+        pairs(&[("memory.oom.group", "1")])
+    );
+    assert_eq!(
+        read("memory.events.local", ExtraFileFormat::FlatKeyed).expect("Failed to read"),
+        pairs(&[
+            ("memory.events.local.high", "2"),
+            ("memory.events.local.low", "0")
+        ])
+    );
+    assert_eq!(
+        read("io.latency", ExtraFileFormat::NestedKeyed).expect("Failed to read"),
+        pairs(&[
+            ("io.latency.8:0.target", "75"),
+            ("io.latency.8:16.target", "100"),
+            ("io.latency.8:16.x", "y")
+        ])
+    );
+}
+
+#[test]
+fn test_extra_file_failure() {
+    let cgroup = TestCgroup::new();
+    cgroup.create_file_with_content("memory.oom.group", b"");
+    cgroup.create_file_with_content("io.latency", b"8:0 target=75\n");
+
+    let cgroup_reader = cgroup.get_reader();
+    let err = cgroup_reader
+        .read_extra_file(&ExtraFile {
+            name: "memory.oom.group".to_owned(),
+            format: ExtraFileFormat::SingleValue,
+        })
+        .expect_err("Did not fail to read memory.oom.group");
+    match err {
+        Error::InvalidFileFormat(_) => {}
+        _ => panic!("Got unexpected error type: {}", err),
+    }
+    let err = cgroup_reader
+        .read_extra_file(&ExtraFile {
+            name: "io.latency".to_owned(),
+            format: ExtraFileFormat::FlatKeyed,
+        })
+        .expect_err("Did not fail to read io.latency");
+    match err {
+        Error::UnexpectedLine(_, _) => {}
+        _ => panic!("Got unexpected error type: {}", err),
+    }
+}
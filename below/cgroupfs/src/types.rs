@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -53,13 +56,23 @@ pub struct MemoryStat {
     pub unevictable: Option<u64>,
     pub slab_reclaimable: Option<u64>,
     pub slab_unreclaimable: Option<u64>,
+    pub percpu: Option<u64>,
+    pub sec_pagetables: Option<u64>,
+    pub zswap: Option<u64>,
+    pub zswapped: Option<u64>,
+    pub file_thp: Option<u64>,
+    pub shmem_thp: Option<u64>,
     pub pgfault: Option<u64>,
     pub pgmajfault: Option<u64>,
     pub workingset_refault: Option<u64>,
+    pub workingset_refault_anon: Option<u64>,
+    pub workingset_refault_file: Option<u64>,
     pub workingset_activate: Option<u64>,
     pub workingset_nodereclaim: Option<u64>,
     pub pgrefill: Option<u64>,
     pub pgscan: Option<u64>,
+    pub pgscan_direct: Option<u64>,
+    pub pgscan_kswapd: Option<u64>,
     pub pgsteal: Option<u64>,
     pub pgactivate: Option<u64>,
     pub pgdeactivate: Option<u64>,
@@ -67,6 +80,10 @@ pub struct MemoryStat {
     pub pglazyfreed: Option<u64>,
     pub thp_fault_alloc: Option<u64>,
     pub thp_collapse_alloc: Option<u64>,
+    /// Keys not known to this version, so they are not lost when the kernel
+    /// adds a counter
+    #[serde(default)]
+    pub other: BTreeMap<String, u64>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -109,3 +126,106 @@ pub struct MemoryEvents {
     pub oom: Option<u64>,
     pub oom_kill: Option<u64>,
 }
+
+/// Limits of cpu.max in microseconds, where a quota of -1 means max
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CpuMax {
+    pub max_usec: Option<i64>,
+    pub period_usec: Option<u64>,
+}
+
+/// Limits of a device in io.max, where -1 means max
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IoMax {
+    pub rbps: Option<i64>,
+    pub wbps: Option<i64>,
+    pub riops: Option<i64>,
+    pub wiops: Option<i64>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PidsEvents {
+    pub max: Option<u64>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CgroupStat {
+    pub nr_descendants: Option<u64>,
+    pub nr_dying_descendants: Option<u64>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CgroupEvents {
+    pub populated: Option<u64>,
+    pub frozen: Option<u64>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MemoryNumaStat {
+    pub anon: Option<u64>,
+    pub file: Option<u64>,
+    pub kernel_stack: Option<u64>,
+    pub pagetables: Option<u64>,
+    pub shmem: Option<u64>,
+    pub file_mapped: Option<u64>,
+    pub file_dirty: Option<u64>,
+    pub file_writeback: Option<u64>,
+    pub anon_thp: Option<u64>,
+    pub inactive_anon: Option<u64>,
+    pub active_anon: Option<u64>,
+    pub inactive_file: Option<u64>,
+    pub active_file: Option<u64>,
+    pub unevictable: Option<u64>,
+    pub slab_reclaimable: Option<u64>,
+    pub slab_unreclaimable: Option<u64>,
+}
+
+/// CPU or memory node ids of a cpuset, e.g. cpuset.cpus.effective
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Cpuset(pub BTreeSet<u32>);
+
+impl fmt::Display for Cpuset {
+    /// Formats like the kernel does, e.g. "0-3,8"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids = self.0.iter().peekable();
+        let mut first = true;
+        while let Some(&start) = ids.next() {
+            let mut end = start;
+            while ids.peek() == Some(&&(end + 1)) {
+                end += 1;
+                ids.next();
+            }
+            if !first {
+                write!(f, ",")?;
+            }
+            first = false;
+            if start == end {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, end)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Layout of a cgroup interface file, see "Interface Files" in the cgroup v2
+/// documentation
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtraFileFormat {
+    /// A single value, e.g. memory.oom.group
+    SingleValue,
+    /// One "<key> <value>" per line, e.g. memory.events
+    FlatKeyed,
+    /// One "<key> <sub_key>=<value> ..." per line, e.g. io.latency
+    NestedKeyed,
+}
+
+/// A cgroup interface file that is read in addition to the ones below
+/// collects by default
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtraFile {
+    pub name: String,
+    pub format: ExtraFileFormat,
+}
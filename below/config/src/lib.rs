@@ -25,6 +25,7 @@ mod test;
 pub const BELOW_DEFAULT_CONF: &str = "/etc/below/below.conf";
 const BELOW_DEFAULT_LOG: &str = "/var/log/below";
 const BELOW_DEFAULT_STORE: &str = "/var/log/below/store";
+const BELOW_DEFAULT_ROLLUP_STORE: &str = "/var/log/below/rollup_store";
 
 /// Global below config
 pub static BELOW_CONFIG: OnceCell<BelowConfig> = OnceCell::new();
@@ -35,8 +36,22 @@ pub static BELOW_CONFIG: OnceCell<BelowConfig> = OnceCell::new();
 pub struct BelowConfig {
     pub log_dir: PathBuf,
     pub store_dir: PathBuf,
+    pub rollup_store_dir: PathBuf,
     pub cgroup_root: PathBuf,
     pub cgroup_filter_out: String,
+    pub alerts: AlertConfig,
+    /// Cgroup interface files to collect in addition to the default ones
+    pub extra_cgroup_files: Vec<cgroupfs::ExtraFile>,
+}
+
+/// Threshold rules `below record` evaluates on every sample. See
+/// `model::AlertRule` for the rule syntax.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub rules: Vec<String>,
+    /// Run with `sh -c` whenever an alert fires or resolves
+    pub command: Option<String>,
 }
 
 impl Default for BelowConfig {
@@ -44,8 +59,11 @@ impl Default for BelowConfig {
         BelowConfig {
             log_dir: BELOW_DEFAULT_LOG.into(),
             store_dir: BELOW_DEFAULT_STORE.into(),
+            rollup_store_dir: BELOW_DEFAULT_ROLLUP_STORE.into(),
             cgroup_root: cgroupfs::DEFAULT_CG_ROOT.into(),
             cgroup_filter_out: String::new(),
+            alerts: Default::default(),
+            extra_cgroup_files: Vec::new(),
         }
     }
 }
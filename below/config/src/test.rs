@@ -26,11 +26,18 @@ fn test_config_default() {
         below_config.store_dir.to_string_lossy(),
         "/var/log/below/store"
     );
+    assert_eq!(
+        below_config.rollup_store_dir.to_string_lossy(),
+        "/var/log/below/rollup_store"
+    );
     assert_eq!(
         below_config.cgroup_root.to_string_lossy(),
         cgroupfs::DEFAULT_CG_ROOT
     );
     assert_eq!(below_config.cgroup_filter_out, String::new());
+    assert!(below_config.alerts.rules.is_empty());
+    assert_eq!(below_config.alerts.command, None);
+    assert!(below_config.extra_cgroup_files.is_empty());
 }
 
 #[test]
@@ -74,6 +81,18 @@ fn test_config_load_success() {
         cgroup_filter_out = 'user.slice'
         # I'm a comment
         something_else = "demacia"
+
+        [alerts]
+        rules = ['system.mem.free < 1GiB']
+        command = 'logger below alert'
+
+        [[extra_cgroup_files]]
+        name = 'memory.oom.group'
+        format = 'single_value'
+
+        [[extra_cgroup_files]]
+        name = 'io.max'
+        format = 'nested_keyed'
     "#;
     file.write_all(config_str.as_bytes())
         .expect("Faild to write temp conf file during testing ignore");
@@ -87,6 +106,24 @@ fn test_config_load_success() {
     assert_eq!(below_config.store_dir.to_string_lossy(), "/var/log/below");
     assert_eq!(below_config.cgroup_root.to_string_lossy(), "/sys/fs/cgroup");
     assert_eq!(below_config.cgroup_filter_out, "user.slice");
+    assert_eq!(below_config.alerts.rules, vec!["system.mem.free < 1GiB"]);
+    assert_eq!(
+        below_config.alerts.command.as_deref(),
+        Some("logger below alert")
+    );
+    assert_eq!(
+        below_config.extra_cgroup_files,
+        vec![
+            cgroupfs::ExtraFile {
+                name: "memory.oom.group".into(),
+                format: cgroupfs::ExtraFileFormat::SingleValue,
+            },
+            cgroupfs::ExtraFile {
+                name: "io.max".into(),
+                format: cgroupfs::ExtraFileFormat::NestedKeyed,
+            },
+        ]
+    );
 }
 
 #[test]
@@ -0,0 +1,345 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reduction of every selected field over the dumped time range, e.g.
+//! `below dump cgroup --aggregate max,p95` prints one row per cgroup and
+//! aggregation instead of one row per cgroup and sample.
+//!
+//! Numeric values are reduced through their f64 conversion and the result is
+//! converted back to the type of the field, so it renders like any other
+//! value of the field. Non-numeric fields (e.g. names) show their last value.
+
+use super::*;
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use render::RenderConfig;
+
+use command::Aggregation;
+
+/// Dumpers that can reduce their fields over a time range
+pub trait Aggregatable {
+    type FieldId: FieldId;
+
+    fn fields(&self) -> &[DumpField<Self::FieldId>];
+
+    /// Entities of `model` passing the dump filter, with a key identifying
+    /// the entity across samples, e.g. the cgroup path or the pid.
+    fn entities<'a>(
+        &self,
+        model: &'a model::Model,
+    ) -> Vec<(String, &'a <Self::FieldId as FieldId>::Queriable)>;
+}
+
+/// Whether `q` passes the `--select`/`--filter` options of a dump
+pub fn is_selected<Q: Queriable>(
+    select: Option<&Q::FieldId>,
+    filter: Option<&Regex>,
+    q: &Q,
+) -> bool {
+    match (select, filter) {
+        (Some(field_id), Some(filter)) => {
+            filter.is_match(&q.query(field_id).map_or("?".to_owned(), |v| v.to_string()))
+        }
+        _ => true,
+    }
+}
+
+fn is_numeric(field: &Field) -> bool {
+    !matches!(field, Field::Str(_) | Field::PidState(_))
+}
+
+/// Convert `val` to the numeric type of `like`
+fn field_like(like: &Field, val: f64) -> Field {
+    match like {
+        Field::U32(_) => Field::U32(val.round() as u32),
+        Field::U64(_) => Field::U64(val.round() as u64),
+        Field::I32(_) => Field::I32(val.round() as i32),
+        Field::I64(_) => Field::I64(val.round() as i64),
+        _ => Field::F64(val),
+    }
+}
+
+impl Aggregation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Avg => "avg",
+            Self::P50 => "p50",
+            Self::P95 => "p95",
+            Self::P99 => "p99",
+        }
+    }
+
+    /// Reduce `sorted`, which must be sorted and not empty. Percentiles use
+    /// the nearest rank, so they are always one of the values.
+    fn reduce(&self, sorted: &[f64]) -> f64 {
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.max(1) - 1]
+        };
+        match self {
+            Self::Min => sorted[0],
+            Self::Max => sorted[sorted.len() - 1],
+            Self::Avg => sorted.iter().sum::<f64>() / sorted.len() as f64,
+            Self::P50 => percentile(50.0),
+            Self::P95 => percentile(95.0),
+            Self::P99 => percentile(99.0),
+        }
+    }
+
+    /// Reduce all values of a field. Non-numeric fields are not reduced and
+    /// the last value is returned.
+    pub fn apply(&self, values: &[Field]) -> Option<Field> {
+        let last = values.last()?;
+        if !is_numeric(last) {
+            return Some(last.clone());
+        }
+        let mut sorted: Vec<f64> = values
+            .iter()
+            .filter(|v| is_numeric(v))
+            .map(|v| f64::from(v.clone()))
+            .collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        Some(field_like(last, self.reduce(&sorted)))
+    }
+}
+
+/// Values of all fields of all entities seen so far, in order of first
+/// appearance of the entity
+pub struct Aggregator<'d, A: Aggregatable> {
+    dumper: &'d A,
+    /// Only FieldId fields are aggregated. Timestamps would be meaningless.
+    field_ids: Vec<A::FieldId>,
+    index: HashMap<String, usize>,
+    entities: Vec<(String, Vec<Vec<Field>>)>,
+}
+
+impl<'d, A> Aggregator<'d, A>
+where
+    A: Aggregatable,
+    A::FieldId: Clone,
+    <A::FieldId as FieldId>::Queriable: HasRenderConfigForDump,
+{
+    pub fn new(dumper: &'d A) -> Self {
+        let field_ids = dumper
+            .fields()
+            .iter()
+            .filter_map(|field| match field {
+                DumpField::FieldId(field_id) => Some(field_id.clone()),
+                DumpField::Common(_) => None,
+            })
+            .collect();
+        Self {
+            dumper,
+            field_ids,
+            index: HashMap::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, model: &model::Model) {
+        for (key, q) in self.dumper.entities(model) {
+            let idx = match self.index.get(&key) {
+                Some(idx) => *idx,
+                None => {
+                    self.index.insert(key.clone(), self.entities.len());
+                    self.entities
+                        .push((key, vec![Vec::new(); self.field_ids.len()]));
+                    self.entities.len() - 1
+                }
+            };
+            let values = &mut self.entities[idx].1;
+            for (field_idx, field_id) in self.field_ids.iter().enumerate() {
+                if let Some(value) = q.query(field_id) {
+                    values[field_idx].push(value);
+                }
+            }
+        }
+    }
+
+    fn render_configs(&self, raw: bool) -> Vec<RenderConfig> {
+        self.field_ids
+            .iter()
+            .map(|field_id| {
+                let mut config = DumpField::FieldId(field_id.clone()).get_render_config();
+                if raw {
+                    config.format = None;
+                    config.suffix = None;
+                }
+                config
+            })
+            .collect()
+    }
+
+    /// Write one row per entity and aggregation
+    pub fn write(
+        &self,
+        aggregations: &[Aggregation],
+        opts: &GeneralOpt,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        let configs = self.render_configs(opts.raw);
+        let key_width = self
+            .entities
+            .iter()
+            .map(|(key, _)| key.len())
+            .max()
+            .unwrap_or(0);
+        let key_config = render::RenderConfigBuilder::new()
+            .title("Entity")
+            .width(key_width + 1)
+            .get();
+        let agg_config = render::RenderConfigBuilder::new().title("Agg").get();
+        let titles = || {
+            std::iter::once(&key_config)
+                .chain(std::iter::once(&agg_config))
+                .chain(configs.iter())
+        };
+
+        let mut rows = Vec::new();
+        for (key, values) in &self.entities {
+            for aggregation in aggregations {
+                let mut row = vec![
+                    Some(Field::from(key.clone())),
+                    Some(Field::from(aggregation.name().to_owned())),
+                ];
+                row.extend(values.iter().map(|values| aggregation.apply(values)));
+                rows.push(row);
+            }
+        }
+
+        match opts.output_format {
+            Some(OutputFormat::Raw) | None => {
+                if !opts.disable_title {
+                    for config in titles() {
+                        write!(output, "{} ", config.render_title(true))?;
+                    }
+                    writeln!(output)?;
+                }
+                for row in rows {
+                    for (config, value) in titles().zip(row) {
+                        write!(output, "{} ", config.render(value, true))?;
+                    }
+                    writeln!(output)?;
+                }
+            }
+            Some(OutputFormat::Csv) => {
+                if !opts.disable_title {
+                    for config in titles() {
+                        write!(output, "{},", config.render_title(false))?;
+                    }
+                    writeln!(output)?;
+                }
+                for row in rows {
+                    for (config, value) in titles().zip(row) {
+                        write!(output, "{},", config.render(value, false))?;
+                    }
+                    writeln!(output)?;
+                }
+            }
+            Some(OutputFormat::KeyVal) => {
+                for row in rows {
+                    for (config, value) in titles().zip(row) {
+                        writeln!(
+                            output,
+                            "{}: {}",
+                            config.render_title(false),
+                            config.render(value, false)
+                        )?;
+                    }
+                    writeln!(output)?;
+                }
+            }
+            Some(OutputFormat::Json) => {
+                let mut jval = json!([]);
+                for row in rows {
+                    let mut jrow = json!({});
+                    for (config, value) in titles().zip(row) {
+                        jrow[config.render_title(false)] = json!(config.render(value, false));
+                    }
+                    jval.as_array_mut().unwrap().push(jrow);
+                }
+                write!(output, "{}", jval)?;
+            }
+            Some(OutputFormat::OpenMetrics) => {
+                bail!("--aggregate is not supported with openmetrics output")
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Like `dump_timeseries`, but reduces all samples of the time range into
+/// one row per entity and aggregation.
+pub fn dump_aggregated<A>(
+    mut advance: Advance,
+    time_begin: SystemTime,
+    time_end: SystemTime,
+    dumper: &A,
+    opts: &GeneralOpt,
+    output: &mut dyn Write,
+    errs: Receiver<Error>,
+) -> Result<()>
+where
+    A: Aggregatable,
+    A::FieldId: Clone,
+    <A::FieldId as FieldId>::Queriable: HasRenderConfigForDump,
+{
+    if opts.output_format == Some(OutputFormat::OpenMetrics) {
+        bail!("--aggregate is not supported with openmetrics output");
+    }
+    let mut model = match advance.jump_sample_to(time_begin) {
+        Some(m) => m,
+        None => bail!(
+            "No initial sample could be found!\n\
+            You may have provided a time in the future or no data was recorded during the provided time. \
+            Please check your input and timezone.\n\
+            If you are using remote, please make sure the below service on target host is running."
+        ),
+    };
+    cliutil::check_initial_sample_time_in_time_range(model.timestamp, time_begin, time_end)?;
+
+    let mut aggregator = Aggregator::new(dumper);
+    loop {
+        // Received external error, e.g. stop signal
+        if let Ok(e) = errs.try_recv() {
+            bail!(e);
+        }
+        aggregator.add(&model);
+
+        if advance.get_next_ts() > time_end {
+            break;
+        }
+        model = match advance.advance(Direction::Forward) {
+            Some(m) => m,
+            None => break,
+        };
+    }
+
+    match aggregator.write(&opts.aggregate, opts, output) {
+        Err(e)
+            if e.downcast_ref::<std::io::Error>()
+                .map_or(false, |e| e.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            return Ok(());
+        }
+        res => res?,
+    }
+
+    cliutil::check_final_sample_time_with_requested_time(model.timestamp, time_end);
+    Ok(())
+}
@@ -0,0 +1,100 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use std::path::Path;
+
+use store::annotation::{read_annotations, Annotation};
+
+const TITLES: [&str; 4] = ["Datetime", "Timestamp", "Source", "Text"];
+
+fn values(annotation: &Annotation) -> [String; 4] {
+    [
+        timestamp_to_datetime(&(annotation.timestamp as i64)),
+        annotation.timestamp.to_string(),
+        annotation.source.clone(),
+        annotation.text.clone(),
+    ]
+}
+
+/// Write the annotations of the store at `dir` within the time range
+pub fn dump_annotations(
+    dir: &Path,
+    time_begin: SystemTime,
+    time_end: SystemTime,
+    opts: &GeneralOpt,
+    output: &mut dyn Write,
+) -> Result<()> {
+    let annotations = read_annotations(dir, time_begin, time_end)?;
+    match opts.output_format {
+        Some(OutputFormat::Raw) | None => {
+            let source_width = annotations
+                .iter()
+                .map(|a| a.source.len())
+                .chain(std::iter::once(TITLES[2].len()))
+                .max()
+                .unwrap_or(0);
+            let write_row = |output: &mut dyn Write, row: [&str; 4]| {
+                writeln!(
+                    output,
+                    "{:<19} {:<10} {:<source_width$} {}",
+                    row[0],
+                    row[1],
+                    row[2],
+                    row[3],
+                    source_width = source_width
+                )
+            };
+            if !opts.disable_title {
+                write_row(output, TITLES)?;
+            }
+            for annotation in &annotations {
+                let row = values(annotation);
+                write_row(output, [&row[0], &row[1], &row[2], &row[3]])?;
+            }
+        }
+        Some(OutputFormat::Csv) => {
+            if !opts.disable_title {
+                writeln!(output, "{},", TITLES.join(","))?;
+            }
+            for annotation in &annotations {
+                writeln!(output, "{},", values(annotation).join(","))?;
+            }
+        }
+        Some(OutputFormat::KeyVal) => {
+            for annotation in &annotations {
+                for (title, value) in TITLES.iter().zip(values(annotation)) {
+                    writeln!(output, "{}: {}", title, value)?;
+                }
+                writeln!(output)?;
+            }
+        }
+        Some(OutputFormat::Json) => {
+            let mut jval = json!([]);
+            for annotation in &annotations {
+                let mut jrow = json!({});
+                for (title, value) in TITLES.iter().zip(values(annotation)) {
+                    jrow[*title] = json!(value);
+                }
+                jval.as_array_mut().unwrap().push(jrow);
+            }
+            write!(output, "{}", jval)?;
+        }
+        Some(OutputFormat::OpenMetrics) => {
+            bail!("Annotations cannot be dumped in openmetrics format")
+        }
+    }
+    Ok(())
+}
@@ -14,7 +14,9 @@
 
 use super::*;
 
-use model::SingleCgroupModelFieldId;
+use std::collections::BTreeSet;
+
+use model::{CgroupExtraModelFieldId, SingleCgroupModelFieldId};
 use render::RenderConfig;
 
 impl HasRenderConfigForDump for model::SingleCgroupModel {
@@ -27,8 +29,9 @@ impl HasRenderConfigForDump for model::SingleCgroupModel {
         };
         use model::CgroupMemoryModelFieldId::{
             Anon, File, Pgactivate, Pgdeactivate, Pgfault, Pglazyfree, Pglazyfreed, Pgmajfault,
-            Pgrefill, Pgscan, Pgsteal, Shmem, Slab, Sock, Swap, ThpCollapseAlloc, ThpFaultAlloc,
-            Total, WorkingsetActivate, WorkingsetNodereclaim, WorkingsetRefault,
+            Pgrefill, Pgscan, PgscanDirect, PgscanKswapd, Pgsteal, Shmem, Slab, Sock, Swap,
+            ThpCollapseAlloc, ThpFaultAlloc, Total, WorkingsetActivate, WorkingsetNodereclaim,
+            WorkingsetRefault, WorkingsetRefaultAnon, WorkingsetRefaultFile,
         };
         use model::CgroupPressureModelFieldId::{MemoryFullPct, MemorySomePct};
         use model::SingleCgroupModelFieldId::{Cpu, Io, Mem, Name, Pressure};
@@ -55,10 +58,14 @@ impl HasRenderConfigForDump for model::SingleCgroupModel {
             Mem(Pgfault) => rc.title("Pgfault"),
             Mem(Pgmajfault) => rc.title("Pgmajfault"),
             Mem(WorkingsetRefault) => rc.title("Workingset Refault"),
+            Mem(WorkingsetRefaultAnon) => rc.title("Workingset Refault Anon"),
+            Mem(WorkingsetRefaultFile) => rc.title("Workingset Refault File"),
             Mem(WorkingsetActivate) => rc.title("Workingset Activate"),
             Mem(WorkingsetNodereclaim) => rc.title("Workingset Nodereclaim"),
             Mem(Pgrefill) => rc.title("Pgrefill"),
             Mem(Pgscan) => rc.title("Pgscan"),
+            Mem(PgscanDirect) => rc.title("Pgscan Direct"),
+            Mem(PgscanKswapd) => rc.title("Pgscan Kswapd"),
             Mem(Pgsteal) => rc.title("Pgsteal"),
             Mem(Pgactivate) => rc.title("Pgactivate"),
             Mem(Pgdeactivate) => rc.title("Pgdeactivate"),
@@ -74,6 +81,60 @@ impl HasRenderConfigForDump for model::SingleCgroupModel {
     }
 }
 
+/// `extra` expands to this placeholder, as the extra cgroup file values only
+/// become known from the samples
+fn is_extra_placeholder(field: &CgroupField) -> bool {
+    matches!(
+        field,
+        DumpField::FieldId(SingleCgroupModelFieldId::Extra(CgroupExtraModelFieldId {
+            key: None
+        }))
+    )
+}
+
+fn collect_extra_keys(model: &model::CgroupModel, keys: &mut BTreeSet<String>) {
+    if let Some(extra) = &model.data.extra {
+        keys.extend(extra.0.keys().cloned());
+    }
+    for child in &model.children {
+        collect_extra_keys(child, keys);
+    }
+}
+
+/// Replace the `extra` placeholder with one field per extra cgroup file value
+/// in the first sample of the dump
+pub fn expand_extra_fields(
+    fields: Vec<CgroupField>,
+    advance: &mut Advance,
+    time_begin: SystemTime,
+) -> Vec<CgroupField> {
+    if !fields.iter().any(is_extra_placeholder) {
+        return fields;
+    }
+    let mut keys = BTreeSet::new();
+    if let Some(model) = advance.jump_sample_to(time_begin) {
+        collect_extra_keys(&model.cgroup, &mut keys);
+    }
+    fields
+        .into_iter()
+        .flat_map(|field| {
+            if is_extra_placeholder(&field) {
+                keys.iter()
+                    .map(|key| {
+                        DumpField::FieldId(SingleCgroupModelFieldId::Extra(
+                            CgroupExtraModelFieldId {
+                                key: Some(key.clone()),
+                            },
+                        ))
+                    })
+                    .collect()
+            } else {
+                vec![field]
+            }
+        })
+        .collect()
+}
+
 pub struct Cgroup {
     opts: GeneralOpt,
     select: Option<SingleCgroupModelFieldId>,
@@ -155,6 +216,24 @@ impl Dumper for Cgroup {
                         "{}",
                         print::dump_kv(&handle.fields, ctx, cgroup, handle.opts.raw)
                     )?,
+                    Some(OutputFormat::OpenMetrics) => write!(
+                        output,
+                        "{}",
+                        print::dump_openmetrics(
+                            &handle.fields,
+                            ctx,
+                            cgroup,
+                            "cgroup",
+                            &[(
+                                "path",
+                                if cgroup.full_path.is_empty() {
+                                    "/"
+                                } else {
+                                    cgroup.full_path.as_str()
+                                }
+                            )]
+                        )
+                    )?,
                     Some(OutputFormat::Json) => {
                         *jval = print::dump_json(&handle.fields, ctx, cgroup, handle.opts.raw);
                         jval["children"] = json!([]);
@@ -206,3 +285,35 @@ impl Dumper for Cgroup {
         Ok(IterExecResult::Success)
     }
 }
+
+impl Aggregatable for Cgroup {
+    type FieldId = SingleCgroupModelFieldId;
+
+    fn fields(&self) -> &[CgroupField] {
+        &self.fields
+    }
+
+    fn entities<'a>(&self, model: &'a model::Model) -> Vec<(String, &'a model::SingleCgroupModel)> {
+        fn collect<'a>(
+            handle: &Cgroup,
+            model: &'a model::CgroupModel,
+            res: &mut Vec<(String, &'a model::SingleCgroupModel)>,
+        ) {
+            let cgroup = &model.data;
+            if is_selected(handle.select.as_ref(), handle.opts.filter.as_ref(), cgroup) {
+                let path = if cgroup.full_path.is_empty() {
+                    "/"
+                } else {
+                    cgroup.full_path.as_str()
+                };
+                res.push((path.to_owned(), cgroup));
+            }
+            for child in &model.children {
+                collect(handle, child, res);
+            }
+        }
+        let mut res = Vec::new();
+        collect(self, &model.cgroup, &mut res);
+        res
+    }
+}
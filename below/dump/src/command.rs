@@ -15,8 +15,9 @@
 use crate::{CommonField, DumpField};
 use model::EnumIter;
 use model::{
-    FieldId, NetworkModelFieldId, SingleCgroupModelFieldId, SingleDiskModelFieldId,
-    SingleNetModelFieldId, SingleProcessModelFieldId, SystemModelFieldId,
+    CgroupMemoryNumaModelFieldId, FieldId, NetworkModelFieldId, SingleCgroupModelFieldId,
+    SingleDiskModelFieldId, SingleNetModelFieldId, SingleNodeModelFieldId,
+    SingleProcessModelFieldId, SystemModelFieldId,
 };
 
 use anyhow::{bail, Error, Result};
@@ -218,6 +219,9 @@ cpus.N.<cpu_field> for individual CPU data. N is individual CPU index and
 
 {all_stat_fields}
 
+nodes.N.<node_field> for individual NUMA node data. N is the node index and
+<node_field> is one of [{node_fields}].
+
 ********************** Aggregated fields **********************
 
 * cpu: includes [{agg_cpu_fields}].
@@ -246,6 +250,7 @@ $ below dump system -b "08:30:00" -e "08:30:30" -f datetime vm hostname -O csv
         all_memory_fields = join(SystemAggField::Mem.expand(true)),
         all_vm_fields = join(SystemAggField::Vm.expand(true)),
         all_stat_fields = join(SystemAggField::Stat.expand(true)),
+        node_fields = join(SingleNodeModelFieldId::unit_variant_iter()),
         agg_cpu_fields = join(SystemAggField::Cpu.expand(false)),
         agg_memory_fields = join(SystemAggField::Mem.expand(false)),
         agg_vm_fields = join(SystemAggField::Vm.expand(false)),
@@ -463,6 +468,10 @@ Output stats for top 5 CPU intense processes for each time slice from 08:30:00 t
 
 $ below dump process -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top 5
 
+Output stats for process 1234 and its threads, recorded with --collect-threads, from 08:30:00 to 08:30:30:
+
+$ below dump process -b "08:30:00" -e "08:30:30" --threads -s tgid -F "^1234$" -f pid tgid comm cpu
+
 "#,
         about = PROCESS_ABOUT,
         common_fields = join(CommonField::unit_variant_iter()),
@@ -477,7 +486,7 @@ $ below dump process -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top
     )
 });
 
-/// Represents the four sub-model of SingleCgroupModel.
+/// Represents the sub-models of SingleCgroupModel.
 #[derive(
     Clone,
     Debug,
@@ -490,13 +499,17 @@ pub enum CgroupAggField {
     Mem,
     Io,
     Pressure,
+    Pids,
+    Extra,
 }
 
 impl AggField<SingleCgroupModelFieldId> for CgroupAggField {
     fn expand(&self, detail: bool) -> Vec<SingleCgroupModelFieldId> {
         use model::CgroupCpuModelFieldId as Cpu;
+        use model::CgroupExtraModelFieldId as Extra;
         use model::CgroupIoModelFieldId as Io;
         use model::CgroupMemoryModelFieldId as Mem;
+        use model::CgroupPidsModelFieldId as Pids;
         use model::CgroupPressureModelFieldId as Pressure;
         use model::SingleCgroupModelFieldId as FieldId;
 
@@ -508,6 +521,9 @@ impl AggField<SingleCgroupModelFieldId> for CgroupAggField {
                 Self::Pressure => Pressure::unit_variant_iter()
                     .map(FieldId::Pressure)
                     .collect(),
+                Self::Pids => Pids::unit_variant_iter().map(FieldId::Pids).collect(),
+                // Replaced with the extra cgroup file values of the samples
+                Self::Extra => vec![FieldId::Extra(Extra { key: None })],
             }
         } else {
             // Default fields for each group
@@ -520,6 +536,8 @@ impl AggField<SingleCgroupModelFieldId> for CgroupAggField {
                     FieldId::Pressure(Pressure::MemoryFullPct),
                     FieldId::Pressure(Pressure::IoFullPct),
                 ],
+                Self::Pids => vec![FieldId::Pids(Pids::Current), FieldId::Pids(Pids::Max)],
+                Self::Extra => vec![FieldId::Extra(Extra { key: None })],
             }
         }
     }
@@ -538,6 +556,20 @@ pub static DEFAULT_CGROUP_FIELDS: &[CgroupOptionField] = &[
     DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
 ];
 
+/// DEFAULT_CGROUP_FIELDS plus the aggregated fields added later, which are
+/// left out of the defaults to keep their columns stable
+pub static EVERYTHING_CGROUP_FIELDS: &[CgroupOptionField] = &[
+    DumpOptionField::Unit(DumpField::FieldId(SingleCgroupModelFieldId::Name)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleCgroupModelFieldId::InodeNumber)),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Datetime)),
+    DumpOptionField::Agg(CgroupAggField::Cpu),
+    DumpOptionField::Agg(CgroupAggField::Mem),
+    DumpOptionField::Agg(CgroupAggField::Io),
+    DumpOptionField::Agg(CgroupAggField::Pressure),
+    DumpOptionField::Agg(CgroupAggField::Pids),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
+];
+
 const CGROUP_ABOUT: &str = "Dump cgroup stats";
 
 /// Generated about message for Cgroup dump so supported fields are up-to-date.
@@ -557,6 +589,17 @@ static CGROUP_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
 
 {all_pressure_fields}
 
+{all_pids_fields}
+
+mem_numa.N.<numa_field> for memory of individual NUMA nodes. N is the node
+index and <numa_field> is one of [{numa_fields}].
+
+hugetlb.<size>.current for hugetlb usage of each huge page size, e.g.
+hugetlb.2MB.current.
+
+mem.other.<key> for memory.stat keys not known to this version of below,
+with their raw values, e.g. mem.other.pgpromote_success.
+
 ********************** Aggregated fields **********************
 
 * cpu: includes [{agg_cpu_fields}].
@@ -567,11 +610,17 @@ static CGROUP_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
 
 * pressure: includes [{agg_pressure_fields}].
 
+* pids: includes [{agg_pids_fields}].
+
+* extra: includes [extra.<key>] for each value of the extra_cgroup_files of
+  below.conf in the first dumped sample, e.g. extra.memory.oom.group.
+
 * --detail: includes [<agg_field>.*] for each given aggregated field.
 
 * --default: includes [{default_fields}].
 
-* --everything: includes everything (equivalent to --default --detail).
+* --everything: includes everything (equivalent to --default --detail, plus
+  pids.*).
 
 ********************** Example Commands **********************
 
@@ -597,10 +646,13 @@ $ below dump cgroup -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top 5
         all_memory_fields = join(CgroupAggField::Mem.expand(true)),
         all_io_fields = join(CgroupAggField::Io.expand(true)),
         all_pressure_fields = join(CgroupAggField::Pressure.expand(true)),
+        all_pids_fields = join(CgroupAggField::Pids.expand(true)),
+        numa_fields = join(CgroupMemoryNumaModelFieldId::unit_variant_iter()),
         agg_cpu_fields = join(CgroupAggField::Cpu.expand(false)),
         agg_memory_fields = join(CgroupAggField::Mem.expand(false)),
         agg_io_fields = join(CgroupAggField::Io.expand(false)),
         agg_pressure_fields = join(CgroupAggField::Pressure.expand(false)),
+        agg_pids_fields = join(CgroupAggField::Pids.expand(false)),
         default_fields = join(DEFAULT_CGROUP_FIELDS.to_owned()),
     )
 });
@@ -891,11 +943,41 @@ $ below dump transport -b "08:30:00" -e "08:30:30" -f tcp udp -O json
     )
 });
 
+const ANNOTATIONS_ABOUT: &str = "Dump annotations of the store, e.g. fired alerts";
+
+const ANNOTATIONS_LONG_ABOUT: &str = r#"Dump annotations of the store, e.g. fired alerts
+
+Annotations are added with `below annotate` or by alert rules of `below record`.
+They are only available for local stores. Field options like --fields, --select,
+--aggregate or --detail do not apply.
+
+********************** Example Commands **********************
+
+Example:
+
+$ below dump annotations -b "08:30:00" -e "09:30:00" -O csv
+
+"#;
+
 make_option! (OutputFormat {
     "raw": Raw,
     "csv": Csv,
     "json": Json,
     "kv": KeyVal,
+    "openmetrics": OpenMetrics,
+});
+
+make_option! (ExportFormat {
+    "parquet": Parquet,
+});
+
+make_option! (Aggregation {
+    "min": Min,
+    "max": Max,
+    "avg": Avg,
+    "p50": P50,
+    "p95": P95,
+    "p99": P99,
 });
 
 #[derive(Debug, StructOpt, Default, Clone)]
@@ -930,7 +1012,7 @@ pub struct GeneralOpt {
     /// Repeat title, for each N line, it will render a line of title. Only for raw output format.
     #[structopt(long = "repeat-title")]
     pub repeat_title: Option<usize>,
-    /// Output format. Choose from raw, csv, kv, json. Default to raw
+    /// Output format. Choose from raw, csv, kv, json, openmetrics. Default to raw
     #[structopt(long, short = "O")]
     pub output_format: Option<OutputFormat>,
     /// Output destination, default to stdout.
@@ -948,6 +1030,13 @@ pub struct GeneralOpt {
     /// Dump raw data without units or conversion
     #[structopt(long)]
     pub raw: bool,
+    /// Reduce each field over the time range instead of dumping every
+    /// sample, printing one row per entity (e.g. cgroup or pid) and
+    /// aggregation. Choose from min, max, avg, p50, p95, p99, e.g.
+    /// --aggregate max,p95. Filters apply to each sample, --sort, --rsort
+    /// and --top are ignored.
+    #[structopt(long, use_delimiter = true)]
+    pub aggregate: Vec<Aggregation>,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -990,6 +1079,11 @@ pub enum DumpCommand {
         /// Saved pattern in the dumprc file under [process] section.
         #[structopt(long, short, conflicts_with("fields"))]
         pattern: Option<String>,
+        /// Dump the threads of every process right after it, if they were
+        /// recorded with --collect-threads. Threads have their tid as pid and
+        /// the pid of their process as tgid.
+        #[structopt(long)]
+        threads: bool,
     },
     #[structopt(about = CGROUP_ABOUT, long_about = CGROUP_LONG_ABOUT.as_str())]
     Cgroup {
@@ -1041,4 +1135,9 @@ pub enum DumpCommand {
         #[structopt(long, short, conflicts_with("fields"))]
         pattern: Option<String>,
     },
+    #[structopt(about = ANNOTATIONS_ABOUT, long_about = ANNOTATIONS_LONG_ABOUT)]
+    Annotations {
+        #[structopt(flatten)]
+        opts: GeneralOpt,
+    },
 }
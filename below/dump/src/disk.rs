@@ -117,6 +117,17 @@ impl Dumper for Disk {
                         "{}",
                         print::dump_kv(&self.fields, ctx, model, self.opts.raw)
                     )?,
+                    Some(OutputFormat::OpenMetrics) => write!(
+                        output,
+                        "{}",
+                        print::dump_openmetrics(
+                            &self.fields,
+                            ctx,
+                            model,
+                            "disk",
+                            &[("disk", model.name.as_deref().unwrap_or("?"))]
+                        )
+                    )?,
                     Some(OutputFormat::Json) => {
                         let par = print::dump_json(&self.fields, ctx, model, self.opts.raw);
                         json_output.as_array_mut().unwrap().push(par);
@@ -136,3 +147,21 @@ impl Dumper for Disk {
         Ok(IterExecResult::Success)
     }
 }
+
+impl Aggregatable for Disk {
+    type FieldId = SingleDiskModelFieldId;
+
+    fn fields(&self) -> &[DiskField] {
+        &self.fields
+    }
+
+    fn entities<'a>(&self, model: &'a model::Model) -> Vec<(String, &'a model::SingleDiskModel)> {
+        model
+            .system
+            .disks
+            .iter()
+            .filter(|(_, sdm)| is_selected(self.select.as_ref(), self.opts.filter.as_ref(), *sdm))
+            .map(|(name, sdm)| (name.clone(), sdm))
+            .collect()
+    }
+}
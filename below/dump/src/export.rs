@@ -0,0 +1,439 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of a time range into typed columnar files, one table per model.
+//!
+//! Every table has a `timestamp` column followed by one column per field of
+//! `below dump <model> --everything`, named after its FieldId. Column types
+//! follow the `Field` variant the sample model produces for the field, so
+//! the schema is the same for every export. Fields the sample model has no
+//! value for are strings. Every table is written, even if it has no rows.
+
+use super::*;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::{
+    ArrayRef, Float64Array, Int32Array, Int64Array, StringArray, TimestampSecondArray, UInt32Array,
+    UInt64Array,
+};
+use arrow::datatypes::{DataType, Field as ArrowField, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use structopt::StructOpt;
+
+pub use command::ExportFormat;
+use command::{expand_fields, AggField, DumpOptionField};
+
+/// Number of rows buffered per table before they are written out
+const BATCH_ROWS: usize = 64 * 1024;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportOpt {
+    /// Begin time, same format as replay
+    #[structopt(long, short)]
+    pub begin: String,
+    /// End time, same format as replay
+    #[structopt(long, short)]
+    pub end: Option<String>,
+    /// Days adjuster, same as -r option in replay.
+    #[structopt(short = "r")]
+    pub yesterdays: Option<String>,
+    /// Output format. Only parquet is supported for now.
+    #[structopt(long, default_value = "parquet")]
+    pub format: ExportFormat,
+    /// Directory to write the tables to, one file per model, e.g.
+    /// cgroup.parquet
+    #[structopt(long, short, parse(from_os_str))]
+    pub output_dir: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColumnType {
+    U32,
+    U64,
+    I32,
+    I64,
+    F64,
+    Str,
+}
+
+impl ColumnType {
+    fn of(field: &Field) -> Self {
+        match field {
+            Field::U32(_) => Self::U32,
+            Field::U64(_) => Self::U64,
+            Field::I32(_) => Self::I32,
+            Field::I64(_) => Self::I64,
+            Field::F64(_) => Self::F64,
+            Field::Str(_) | Field::PidState(_) => Self::Str,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::U32 => DataType::UInt32,
+            Self::U64 => DataType::UInt64,
+            Self::I32 => DataType::Int32,
+            Self::I64 => DataType::Int64,
+            Self::F64 => DataType::Float64,
+            Self::Str => DataType::Utf8,
+        }
+    }
+
+    /// Build an array of this type. Values of any other type become null,
+    /// except that everything can be stored as a string.
+    fn build_array(&self, values: impl Iterator<Item = Option<Field>>) -> ArrayRef {
+        match self {
+            Self::U32 => Arc::new(
+                values
+                    .map(|v| match v {
+                        Some(Field::U32(v)) => Some(v),
+                        _ => None,
+                    })
+                    .collect::<UInt32Array>(),
+            ),
+            Self::U64 => Arc::new(
+                values
+                    .map(|v| match v {
+                        Some(Field::U64(v)) => Some(v),
+                        _ => None,
+                    })
+                    .collect::<UInt64Array>(),
+            ),
+            Self::I32 => Arc::new(
+                values
+                    .map(|v| match v {
+                        Some(Field::I32(v)) => Some(v),
+                        _ => None,
+                    })
+                    .collect::<Int32Array>(),
+            ),
+            Self::I64 => Arc::new(
+                values
+                    .map(|v| match v {
+                        Some(Field::I64(v)) => Some(v),
+                        _ => None,
+                    })
+                    .collect::<Int64Array>(),
+            ),
+            Self::F64 => Arc::new(
+                values
+                    .map(|v| match v {
+                        Some(Field::F64(v)) => Some(v),
+                        _ => None,
+                    })
+                    .collect::<Float64Array>(),
+            ),
+            Self::Str => Arc::new(
+                values
+                    .map(|v| v.map(|v| v.to_string()))
+                    .collect::<StringArray>(),
+            ),
+        }
+    }
+}
+
+/// A parquet file holding one row per entity (e.g. cgroup) per sample
+struct Table<Q: Queriable> {
+    path: PathBuf,
+    field_ids: Vec<Q::FieldId>,
+    column_types: Vec<ColumnType>,
+    timestamps: Vec<i64>,
+    rows: Vec<Vec<Option<Field>>>,
+    writer: Option<(SchemaRef, ArrowWriter<File>)>,
+}
+
+impl<Q> Table<Q>
+where
+    Q: Queriable,
+    Q::FieldId: ToString,
+{
+    /// Column types are taken from the first of `examples` with a value for
+    /// the field
+    fn new(dir: &Path, name: &str, field_ids: Vec<Q::FieldId>, examples: &[&Q]) -> Self {
+        let column_types = field_ids
+            .iter()
+            .map(|field_id| {
+                examples
+                    .iter()
+                    .find_map(|q| q.query(field_id))
+                    .map_or(ColumnType::Str, |f| ColumnType::of(&f))
+            })
+            .collect();
+        Self {
+            path: dir.join(format!("{}.parquet", name)),
+            field_ids,
+            column_types,
+            timestamps: Vec::new(),
+            rows: Vec::new(),
+            writer: None,
+        }
+    }
+
+    fn push(&mut self, timestamp: i64, q: &Q) -> Result<()> {
+        self.timestamps.push(timestamp);
+        self.rows
+            .push(self.field_ids.iter().map(|id| q.query(id)).collect());
+        if self.rows.len() >= BATCH_ROWS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn schema(&self) -> SchemaRef {
+        let mut fields = vec![ArrowField::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Second, None),
+            false,
+        )];
+        for (field_id, column_type) in self.field_ids.iter().zip(&self.column_types) {
+            fields.push(ArrowField::new(
+                &field_id.to_string(),
+                column_type.data_type(),
+                true,
+            ));
+        }
+        Arc::new(Schema::new(fields))
+    }
+
+    fn create(&mut self) -> Result<()> {
+        let schema = self.schema();
+        let file = File::create(&self.path)
+            .with_context(|| format!("Failed to create {}", self.path.display()))?;
+        let props = WriterProperties::builder()
+            .set_compression(Compression::ZSTD)
+            .build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+        self.writer = Some((schema, writer));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        if self.writer.is_none() {
+            self.create()?;
+        }
+        let (schema, writer) = self.writer.as_mut().unwrap();
+
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(
+            self.timestamps
+                .drain(..)
+                .map(Some)
+                .collect::<TimestampSecondArray>(),
+        )];
+        let rows = std::mem::take(&mut self.rows);
+        for (idx, column_type) in self.column_types.iter().enumerate() {
+            columns.push(column_type.build_array(rows.iter().map(|row| row[idx].clone())));
+        }
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+        Ok(())
+    }
+
+    fn close(mut self) -> Result<()> {
+        self.flush()?;
+        // Tables without rows are written too so the set of files is the
+        // same for every export
+        if self.writer.is_none() {
+            self.create()?;
+        }
+        if let Some((_, mut writer)) = self.writer.take() {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+/// FieldIds of `below dump --everything`, in order
+fn everything<F, A>(fields: &[DumpOptionField<F, A>]) -> Vec<F>
+where
+    F: FieldId + Clone,
+    A: AggField<F>,
+{
+    expand_fields(fields, true)
+        .into_iter()
+        .filter_map(|field| match field {
+            DumpField::FieldId(field_id) => Some(field_id),
+            DumpField::Common(_) => None,
+        })
+        .collect()
+}
+
+fn flatten_cgroups<'a>(model: &'a model::CgroupModel, res: &mut Vec<&'a model::SingleCgroupModel>) {
+    res.push(&model.data);
+    for child in &model.children {
+        flatten_cgroups(child, res);
+    }
+}
+
+/// One table per model, written to a directory
+pub(crate) struct Tables {
+    system: Table<model::SystemModel>,
+    cgroup: Table<model::SingleCgroupModel>,
+    process: Table<model::SingleProcessModel>,
+    iface: Table<model::SingleNetModel>,
+    disk: Table<model::SingleDiskModel>,
+    network: Table<model::NetworkModel>,
+}
+
+impl Tables {
+    pub(crate) fn new(dir: &Path) -> Self {
+        // The sample model has (nearly) every field populated, so it tells us
+        // the type of each column independent of the exported data.
+        let example = model::get_sample_model();
+        let mut cgroups = Vec::new();
+        flatten_cgroups(&example.cgroup, &mut cgroups);
+        // Network and transport dumps both cover NetworkModel
+        let mut network_field_ids = everything(command::DEFAULT_NETWORK_FIELDS);
+        for field_id in everything(command::DEFAULT_TRANSPORT_FIELDS) {
+            if !network_field_ids.contains(&field_id) {
+                network_field_ids.push(field_id);
+            }
+        }
+        Self {
+            system: Table::new(
+                dir,
+                "system",
+                everything(command::DEFAULT_SYSTEM_FIELDS),
+                &[&example.system],
+            ),
+            cgroup: Table::new(
+                dir,
+                "cgroup",
+                everything(command::EVERYTHING_CGROUP_FIELDS),
+                &cgroups,
+            ),
+            process: Table::new(
+                dir,
+                "process",
+                everything(command::DEFAULT_PROCESS_FIELDS),
+                &example.process.processes.values().collect::<Vec<_>>(),
+            ),
+            iface: Table::new(
+                dir,
+                "iface",
+                everything(command::DEFAULT_IFACE_FIELDS),
+                &example.network.interfaces.values().collect::<Vec<_>>(),
+            ),
+            disk: Table::new(
+                dir,
+                "disk",
+                everything(command::DEFAULT_DISK_FIELDS),
+                &example.system.disks.values().collect::<Vec<_>>(),
+            ),
+            network: Table::new(dir, "network", network_field_ids, &[&example.network]),
+        }
+    }
+
+    pub(crate) fn push(&mut self, model: &model::Model) -> Result<()> {
+        let timestamp = model
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs() as i64;
+        self.system.push(timestamp, &model.system)?;
+        let mut cgroups = Vec::new();
+        flatten_cgroups(&model.cgroup, &mut cgroups);
+        for cgroup in cgroups {
+            self.cgroup.push(timestamp, cgroup)?;
+        }
+        for process in model.process.processes.values() {
+            self.process.push(timestamp, process)?;
+        }
+        for iface in model.network.interfaces.values() {
+            self.iface.push(timestamp, iface)?;
+        }
+        for disk in model.system.disks.values() {
+            self.disk.push(timestamp, disk)?;
+        }
+        self.network.push(timestamp, &model.network)?;
+        Ok(())
+    }
+
+    pub(crate) fn close(self) -> Result<()> {
+        self.system.close()?;
+        self.cgroup.close()?;
+        self.process.close()?;
+        self.iface.close()?;
+        self.disk.close()?;
+        self.network.close()?;
+        Ok(())
+    }
+}
+
+pub fn run(
+    logger: slog::Logger,
+    errs: Receiver<Error>,
+    dir: PathBuf,
+    host: Option<String>,
+    port: Option<u16>,
+    opts: ExportOpt,
+) -> Result<()> {
+    let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
+        opts.begin.as_str(),
+        opts.end.as_deref(),
+        opts.yesterdays.as_deref(),
+    )?;
+    let mut advance = if let Some(host) = host {
+        new_advance_remote(logger.clone(), host, port, time_begin)?
+    } else {
+        new_advance_local(logger.clone(), dir, time_begin)
+    };
+    advance.initialize();
+
+    let mut model = match advance.jump_sample_to(time_begin) {
+        Some(m) => m,
+        None => bail!(
+            "No initial sample could be found!\n\
+            You may have provided a time in the future or no data was recorded during the provided time. \
+            Please check your input and timezone."
+        ),
+    };
+    cliutil::check_initial_sample_time_in_time_range(model.timestamp, time_begin, time_end)?;
+
+    std::fs::create_dir_all(&opts.output_dir).with_context(|| {
+        format!(
+            "Failed to create output directory {}",
+            opts.output_dir.display()
+        )
+    })?;
+    let mut tables = match opts.format {
+        ExportFormat::Parquet => Tables::new(&opts.output_dir),
+    };
+
+    loop {
+        // Received external error, e.g. stop signal
+        if let Ok(e) = errs.try_recv() {
+            bail!(e);
+        }
+        tables.push(&model)?;
+
+        if advance.get_next_ts() > time_end {
+            break;
+        }
+        model = match advance.advance(Direction::Forward) {
+            Some(m) => m,
+            None => break,
+        };
+    }
+    tables.close()?;
+
+    cliutil::check_final_sample_time_with_requested_time(model.timestamp, time_end);
+    Ok(())
+}
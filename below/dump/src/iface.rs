@@ -100,6 +100,17 @@ impl Dumper for Iface {
                         "{}",
                         print::dump_kv(&self.fields, ctx, model, self.opts.raw)
                     )?,
+                    Some(OutputFormat::OpenMetrics) => write!(
+                        output,
+                        "{}",
+                        print::dump_openmetrics(
+                            &self.fields,
+                            ctx,
+                            model,
+                            "iface",
+                            &[("interface", model.interface.as_str())]
+                        )
+                    )?,
                     Some(OutputFormat::Json) => {
                         let par = print::dump_json(&self.fields, ctx, model, self.opts.raw);
                         json_output.as_array_mut().unwrap().push(par);
@@ -119,3 +130,21 @@ impl Dumper for Iface {
         Ok(IterExecResult::Success)
     }
 }
+
+impl Aggregatable for Iface {
+    type FieldId = SingleNetModelFieldId;
+
+    fn fields(&self) -> &[IfaceField] {
+        &self.fields
+    }
+
+    fn entities<'a>(&self, model: &'a model::Model) -> Vec<(String, &'a model::SingleNetModel)> {
+        model
+            .network
+            .interfaces
+            .iter()
+            .filter(|(_, snm)| is_selected(self.select.as_ref(), self.opts.filter.as_ref(), *snm))
+            .map(|(name, snm)| (name.clone(), snm))
+            .collect()
+    }
+}
@@ -27,17 +27,22 @@ use common::cliutil;
 use common::util::{get_belowrc_dump_section_key, get_belowrc_filename, timestamp_to_datetime};
 use model::{EnumIter, Field, FieldId, Queriable};
 
-use store::advance::{new_advance_local, new_advance_remote};
+use store::advance::{new_advance_local, new_advance_remote, new_advance_snapshot};
 use store::Advance;
 use store::Direction;
 
+pub mod aggregate;
+pub mod annotations;
 pub mod cgroup;
 pub mod command;
 pub mod disk;
+pub mod export;
 pub mod iface;
 pub mod network;
+pub mod openmetrics;
 pub mod print;
 pub mod process;
+pub mod serve;
 pub mod system;
 pub mod tmain;
 pub mod transport;
@@ -45,6 +50,7 @@ pub mod transport;
 #[cfg(test)]
 mod test;
 
+use aggregate::{dump_aggregated, is_selected, Aggregatable};
 pub use command::DumpCommand;
 use command::{expand_fields, GeneralOpt, OutputFormat};
 use print::HasRenderConfigForDump;
@@ -103,6 +109,7 @@ fn get_advance(
     dir: PathBuf,
     host: Option<String>,
     port: Option<u16>,
+    snapshot: Option<PathBuf>,
     opts: &command::GeneralOpt,
 ) -> Result<(SystemTime, SystemTime, Advance)> {
     let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
@@ -111,7 +118,9 @@ fn get_advance(
         opts.yesterdays.as_deref(),
     )?;
 
-    let mut advance = if let Some(host) = host {
+    let mut advance = if let Some(snapshot) = snapshot {
+        new_advance_snapshot(logger.clone(), &snapshot, time_begin)?
+    } else if let Some(host) = host {
         new_advance_remote(logger.clone(), host, port, time_begin)?
     } else {
         new_advance_local(logger.clone(), dir, time_begin)
@@ -122,6 +131,38 @@ fn get_advance(
     Ok((time_begin, time_end, advance))
 }
 
+/// Dump every sample of the time range, or one reduced row per entity and
+/// aggregation if --aggregate is given.
+fn dump<D>(
+    advance: Advance,
+    time_begin: SystemTime,
+    time_end: SystemTime,
+    dumper: &D,
+    opts: &GeneralOpt,
+    output: &mut dyn Write,
+    errs: Receiver<Error>,
+) -> Result<()>
+where
+    D: Dumper + Aggregatable,
+    D::FieldId: Clone,
+    <D::FieldId as FieldId>::Queriable: HasRenderConfigForDump,
+{
+    if opts.aggregate.is_empty() {
+        dump_timeseries(
+            advance,
+            time_begin,
+            time_end,
+            dumper,
+            output,
+            opts.output_format,
+            opts.br.clone(),
+            errs,
+        )
+    } else {
+        dump_aggregated(advance, time_begin, time_end, dumper, opts, output, errs)
+    }
+}
+
 /// Try to read $HOME/.config/below/belowrc file and generate a list of keys which will
 /// be used as fields. Any errors happen in this function will directly trigger a panic.
 pub fn parse_pattern<T: FromStr>(
@@ -180,8 +221,33 @@ pub fn run(
     dir: PathBuf,
     host: Option<String>,
     port: Option<u16>,
+    snapshot: Option<PathBuf>,
     cmd: DumpCommand,
 ) -> Result<()> {
+    run_with_output(logger, errs, dir, host, port, snapshot, cmd, |opts| {
+        let output: Box<dyn Write> = match opts.output.as_ref() {
+            Some(file_path) => Box::new(File::create(file_path)?),
+            None => Box::new(io::stdout()),
+        };
+        Ok(output)
+    })
+}
+
+/// Same as `run`, but the dump is written to whatever `get_output` returns
+/// instead of the destination given by `--output`.
+pub fn run_with_output<'a, F>(
+    logger: slog::Logger,
+    errs: Receiver<Error>,
+    dir: PathBuf,
+    host: Option<String>,
+    port: Option<u16>,
+    snapshot: Option<PathBuf>,
+    cmd: DumpCommand,
+    get_output: F,
+) -> Result<()>
+where
+    F: FnOnce(&GeneralOpt) -> Result<Box<dyn Write + 'a>>,
+{
     let filename = get_belowrc_filename();
 
     match cmd {
@@ -190,7 +256,8 @@ pub fn run(
             opts,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -206,18 +273,14 @@ pub fn run(
                 detail,
             );
             let system = system::System::new(&opts, fields);
-            let mut output: Box<dyn Write> = match opts.output.as_ref() {
-                Some(file_path) => Box::new(File::create(file_path)?),
-                None => Box::new(io::stdout()),
-            };
-            dump_timeseries(
+            let mut output = get_output(&opts)?;
+            dump(
                 advance,
                 time_begin,
                 time_end,
                 &system,
+                &opts,
                 output.as_mut(),
-                opts.output_format,
-                opts.br,
                 errs,
             )
         }
@@ -227,7 +290,8 @@ pub fn run(
             select,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -243,18 +307,14 @@ pub fn run(
                 detail,
             );
             let disk = disk::Disk::new(&opts, select, fields);
-            let mut output: Box<dyn Write> = match opts.output.as_ref() {
-                Some(file_path) => Box::new(File::create(file_path)?),
-                None => Box::new(io::stdout()),
-            };
-            dump_timeseries(
+            let mut output = get_output(&opts)?;
+            dump(
                 advance,
                 time_begin,
                 time_end,
                 &disk,
+                &opts,
                 output.as_mut(),
-                opts.output_format,
-                opts.br,
                 errs,
             )
         }
@@ -263,8 +323,10 @@ pub fn run(
             opts,
             select,
             pattern,
+            threads,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -279,19 +341,15 @@ pub fn run(
                 },
                 detail,
             );
-            let process = process::Process::new(&opts, select, fields);
-            let mut output: Box<dyn Write> = match opts.output.as_ref() {
-                Some(file_path) => Box::new(File::create(file_path)?),
-                None => Box::new(io::stdout()),
-            };
-            dump_timeseries(
+            let process = process::Process::new(&opts, select, fields).with_threads(threads);
+            let mut output = get_output(&opts)?;
+            dump(
                 advance,
                 time_begin,
                 time_end,
                 &process,
+                &opts,
                 output.as_mut(),
-                opts.output_format,
-                opts.br,
                 errs,
             )
         }
@@ -301,7 +359,8 @@ pub fn run(
             select,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -312,23 +371,21 @@ pub fn run(
             let fields = expand_fields(
                 match fields.as_ref() {
                     Some(fields) if !default => fields,
+                    _ if opts.everything => command::EVERYTHING_CGROUP_FIELDS,
                     _ => command::DEFAULT_CGROUP_FIELDS,
                 },
                 detail,
             );
+            let fields = cgroup::expand_extra_fields(fields, &mut advance, time_begin);
             let cgroup = cgroup::Cgroup::new(&opts, select, fields);
-            let mut output: Box<dyn Write> = match opts.output.as_ref() {
-                Some(file_path) => Box::new(File::create(file_path)?),
-                None => Box::new(io::stdout()),
-            };
-            dump_timeseries(
+            let mut output = get_output(&opts)?;
+            dump(
                 advance,
                 time_begin,
                 time_end,
                 &cgroup,
+                &opts,
                 output.as_mut(),
-                opts.output_format,
-                opts.br,
                 errs,
             )
         }
@@ -338,7 +395,8 @@ pub fn run(
             select,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -354,18 +412,14 @@ pub fn run(
                 detail,
             );
             let iface = iface::Iface::new(&opts, select, fields);
-            let mut output: Box<dyn Write> = match opts.output.as_ref() {
-                Some(file_path) => Box::new(File::create(file_path)?),
-                None => Box::new(io::stdout()),
-            };
-            dump_timeseries(
+            let mut output = get_output(&opts)?;
+            dump(
                 advance,
                 time_begin,
                 time_end,
                 &iface,
+                &opts,
                 output.as_mut(),
-                opts.output_format,
-                opts.br,
                 errs,
             )
         }
@@ -374,7 +428,8 @@ pub fn run(
             opts,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -390,18 +445,14 @@ pub fn run(
                 detail,
             );
             let network = network::Network::new(&opts, fields);
-            let mut output: Box<dyn Write> = match opts.output.as_ref() {
-                Some(file_path) => Box::new(File::create(file_path)?),
-                None => Box::new(io::stdout()),
-            };
-            dump_timeseries(
+            let mut output = get_output(&opts)?;
+            dump(
                 advance,
                 time_begin,
                 time_end,
                 &network,
+                &opts,
                 output.as_mut(),
-                opts.output_format,
-                opts.br,
                 errs,
             )
         }
@@ -410,7 +461,8 @@ pub fn run(
             opts,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -426,20 +478,28 @@ pub fn run(
                 detail,
             );
             let transport = transport::Transport::new(&opts, fields);
-            let mut output: Box<dyn Write> = match opts.output.as_ref() {
-                Some(file_path) => Box::new(File::create(file_path)?),
-                None => Box::new(io::stdout()),
-            };
-            dump_timeseries(
+            let mut output = get_output(&opts)?;
+            dump(
                 advance,
                 time_begin,
                 time_end,
                 &transport,
+                &opts,
                 output.as_mut(),
-                opts.output_format,
-                opts.br,
                 errs,
             )
         }
+        DumpCommand::Annotations { opts } => {
+            if host.is_some() || snapshot.is_some() {
+                bail!("Annotations are only available for local stores");
+            }
+            let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
+                opts.begin.as_str(),
+                opts.end.as_deref(),
+                opts.yesterdays.as_deref(),
+            )?;
+            let mut output = get_output(&opts)?;
+            annotations::dump_annotations(&dir, time_begin, time_end, &opts, output.as_mut())
+        }
     }
 }
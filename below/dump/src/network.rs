@@ -70,6 +70,11 @@ impl Dumper for Network {
                 "{}",
                 print::dump_kv(&self.fields, ctx, &model.network, self.opts.raw)
             )?,
+            Some(OutputFormat::OpenMetrics) => write!(
+                output,
+                "{}",
+                print::dump_openmetrics(&self.fields, ctx, &model.network, "network", &[])
+            )?,
             Some(OutputFormat::Json) => {
                 let json_output =
                     print::dump_json(&self.fields, ctx, &model.network, self.opts.raw);
@@ -88,3 +93,15 @@ impl Dumper for Network {
         Ok(IterExecResult::Success)
     }
 }
+
+impl Aggregatable for Network {
+    type FieldId = model::NetworkModelFieldId;
+
+    fn fields(&self) -> &[NetworkField] {
+        &self.fields
+    }
+
+    fn entities<'a>(&self, model: &'a model::Model) -> Vec<(String, &'a model::NetworkModel)> {
+        vec![("network".to_owned(), &model.network)]
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering of models in the OpenMetrics text format, plus an exporter that
+//! serves the latest model collected by `below record`.
+
+use super::*;
+
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::sync::{Arc, Mutex};
+
+use serve::{spawn_http_server, Response};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Turn arbitrary text into a valid metric name. Anything outside of
+/// `[a-zA-Z0-9_:]` is replaced by an underscore.
+pub fn metric_name(name: &str) -> String {
+    let mut res: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if res.starts_with(|c: char| c.is_ascii_digit()) {
+        res.insert(0, '_');
+    }
+    res
+}
+
+/// Escape a label value as required within double quotes
+pub fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Numeric value of a field, None for fields that can't be a sample value
+pub fn field_value(field: Field) -> Option<f64> {
+    match field {
+        Field::U32(_) | Field::U64(_) | Field::I32(_) | Field::I64(_) | Field::F64(_) => {
+            Some(field.into())
+        }
+        Field::Str(_) | Field::PidState(_) => None,
+    }
+}
+
+/// Write a single sample line. `labels` are (name, unescaped value) pairs and
+/// `timestamp` is in seconds since epoch.
+pub fn write_sample(
+    output: &mut String,
+    name: &str,
+    labels: &[(&str, &str)],
+    value: f64,
+    timestamp: Option<i64>,
+) {
+    output.push_str(name);
+    if !labels.is_empty() {
+        let labels = labels
+            .iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(output, "{{{}}}", labels).unwrap();
+    }
+    write!(output, " {}", value).unwrap();
+    if let Some(timestamp) = timestamp {
+        write!(output, " {}", timestamp).unwrap();
+    }
+    output.push('\n');
+}
+
+/// Sample lines produced by `write_sample`, grouped into families in the
+/// order each family first appears. OpenMetrics requires each family to be
+/// written exactly once, so dumps add the samples of every sample time and
+/// only write the families once the time range is done.
+#[derive(Default)]
+pub struct Families {
+    families: Vec<(String, Vec<String>)>,
+    family_idx: HashMap<String, usize>,
+}
+
+impl Families {
+    pub fn add_samples(&mut self, samples: &str) {
+        for line in samples.lines().filter(|line| !line.is_empty()) {
+            let name = line.split(|c| c == '{' || c == ' ').next().unwrap_or(line);
+            let idx = match self.family_idx.get(name) {
+                Some(idx) => *idx,
+                None => {
+                    self.families.push((name.to_owned(), Vec::new()));
+                    self.family_idx
+                        .insert(name.to_owned(), self.families.len() - 1);
+                    self.families.len() - 1
+                }
+            };
+            self.families[idx].1.push(line.to_owned());
+        }
+    }
+
+    /// Write every family followed by the terminating `# EOF`
+    pub fn write(&self, output: &mut dyn Write) -> Result<()> {
+        for (name, lines) in &self.families {
+            writeln!(output, "# TYPE {} gauge", name)?;
+            for line in lines {
+                writeln!(output, "{}", line)?;
+            }
+        }
+        writeln!(output, "# EOF")?;
+        Ok(())
+    }
+}
+
+/// Write one gauge family per field of `Q`, named after its FieldId. Each
+/// entity contributes one sample labeled with `label` (if any).
+fn write_families<K, Q>(
+    output: &mut String,
+    prefix: &str,
+    label: Option<&str>,
+    entities: &[(K, &Q)],
+) where
+    K: AsRef<str>,
+    Q: Queriable,
+    Q::FieldId: EnumIter + ToString,
+{
+    for field_id in Q::FieldId::all_variant_iter() {
+        let name = metric_name(&format!("{}_{}", prefix, field_id.to_string()));
+        let mut has_samples = false;
+        for (key, entity) in entities {
+            if let Some(value) = entity.query(&field_id).and_then(field_value) {
+                if !has_samples {
+                    writeln!(output, "# TYPE {} gauge", name).unwrap();
+                    has_samples = true;
+                }
+                match label {
+                    Some(label) => {
+                        write_sample(output, &name, &[(label, key.as_ref())], value, None)
+                    }
+                    None => write_sample(output, &name, &[], value, None),
+                }
+            }
+        }
+    }
+}
+
+fn flatten_cgroups<'a>(model: &'a model::CgroupModel, res: &mut Vec<&'a model::CgroupModel>) {
+    res.push(model);
+    for child in &model.children {
+        flatten_cgroups(child, res);
+    }
+}
+
+/// Render system, cgroup, interface and disk metrics of `model` in the
+/// OpenMetrics text format. Metric names are derived from FieldIds, e.g.
+/// `cgroup.mem.total` becomes `below_cgroup_mem_total`.
+pub fn render_model(model: &model::Model) -> String {
+    let mut output = String::new();
+
+    write_families(&mut output, "below_system", None, &[("", &model.system)]);
+
+    write_families(
+        &mut output,
+        "below_cpu",
+        Some("cpu"),
+        &model
+            .system
+            .cpus
+            .iter()
+            .map(|cpu| (cpu.idx.to_string(), cpu))
+            .collect::<Vec<_>>(),
+    );
+
+    let mut cgroups = Vec::new();
+    flatten_cgroups(&model.cgroup, &mut cgroups);
+    write_families(
+        &mut output,
+        "below_cgroup",
+        Some("path"),
+        &cgroups
+            .iter()
+            .map(|cgroup| {
+                let path = cgroup.data.full_path.as_str();
+                (if path.is_empty() { "/" } else { path }, &cgroup.data)
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    write_families(&mut output, "below_network", None, &[("", &model.network)]);
+    write_families(
+        &mut output,
+        "below_iface",
+        Some("interface"),
+        &model
+            .network
+            .interfaces
+            .iter()
+            .map(|(name, iface)| (name.as_str(), iface))
+            .collect::<Vec<_>>(),
+    );
+    write_families(
+        &mut output,
+        "below_disk",
+        Some("disk"),
+        &model
+            .system
+            .disks
+            .iter()
+            .map(|(name, disk)| (name.as_str(), disk))
+            .collect::<Vec<_>>(),
+    );
+
+    output.push_str("# EOF\n");
+    output
+}
+
+/// Serve the model stored in `latest` in the OpenMetrics text format on
+/// `address` from a background thread. Responds with 503 until the first
+/// model is available.
+pub fn start_exporter(
+    logger: slog::Logger,
+    address: &str,
+    latest: Arc<Mutex<Option<model::Model>>>,
+) -> Result<()> {
+    spawn_http_server(logger, address, move |target| {
+        let path = target.split_once('?').map_or(target, |(path, _)| path);
+        if path != "/metrics" {
+            return Response::error("404 Not Found", "Metrics are served at /metrics".to_owned());
+        }
+        match latest.lock().unwrap().as_ref() {
+            Some(model) => Response::ok(CONTENT_TYPE, render_model(model).into_bytes()),
+            None => Response::error(
+                "503 Service Unavailable",
+                "No sample has been collected yet".to_owned(),
+            ),
+        }
+    })
+}
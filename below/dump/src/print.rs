@@ -124,6 +124,43 @@ pub fn dump_json<T: HasRenderConfigForDump>(
     res
 }
 
+/// Render each numeric field as an OpenMetrics sample timestamped with the
+/// sample time. Metric names are derived from FieldIds like in
+/// `openmetrics::render_model`, e.g. `mem.total` of cgroup becomes
+/// `below_cgroup_mem_total`.
+pub fn dump_openmetrics<T: HasRenderConfigForDump>(
+    fields: &[DumpField<T::FieldId>],
+    ctx: &CommonFieldContext,
+    model: &T,
+    entity: &str,
+    labels: &[(&str, &str)],
+) -> String
+where
+    T::FieldId: ToString,
+{
+    let mut res = String::new();
+    for field in fields {
+        // Timestamp and datetime are part of every sample already
+        let field_id = match field {
+            DumpField::Common(_) => continue,
+            DumpField::FieldId(field_id) => field_id,
+        };
+        if let Some(value) = field
+            .get_field(ctx, model)
+            .and_then(openmetrics::field_value)
+        {
+            openmetrics::write_sample(
+                &mut res,
+                &openmetrics::metric_name(&format!("below_{}_{}", entity, field_id.to_string())),
+                labels,
+                value,
+                Some(ctx.timestamp),
+            );
+        }
+    }
+    res
+}
+
 fn dump_title_line<F>(fields: &[DumpField<F>], sep: &'static str, fixed_width: bool) -> String
 where
     F: FieldId,
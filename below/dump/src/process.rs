@@ -39,6 +39,7 @@ pub struct Process {
     opts: GeneralOpt,
     select: Option<SingleProcessModelFieldId>,
     fields: Vec<ProcessField>,
+    threads: bool,
 }
 
 impl Process {
@@ -51,8 +52,48 @@ impl Process {
             opts: opts.to_owned(),
             select,
             fields,
+            threads: false,
         }
     }
+
+    /// Also dump the threads of every process right after it
+    pub fn with_threads(mut self, threads: bool) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Processes that pass --filter
+    fn processes<'a>(&self, model: &'a model::Model) -> Vec<&'a model::SingleProcessModel> {
+        model
+            .process
+            .processes
+            .values()
+            .filter(|spm| is_selected(self.select.as_ref(), self.opts.filter.as_ref(), *spm))
+            .collect()
+    }
+
+    /// `processes` keyed by pid, each followed by its threads keyed by
+    /// <pid>/<tid> if requested, as the main thread has the pid as tid
+    fn rows<'a>(
+        &self,
+        processes: Vec<&'a model::SingleProcessModel>,
+    ) -> Vec<(String, &'a model::SingleProcessModel)> {
+        let mut rows = Vec::new();
+        for spm in processes {
+            let pid = spm.pid.map_or("?".to_owned(), |v| v.to_string());
+            if let (true, Some(threads)) = (self.threads, spm.threads.as_ref()) {
+                rows.push((pid.clone(), spm));
+                rows.extend(
+                    threads
+                        .iter()
+                        .map(|(tid, thread)| (format!("{}/{}", pid, tid), thread)),
+                );
+            } else {
+                rows.push((pid, spm));
+            }
+        }
+        rows
+    }
 }
 
 impl Dumper for Process {
@@ -64,24 +105,7 @@ impl Dumper for Process {
         round: &mut usize,
         comma_flag: bool,
     ) -> Result<IterExecResult> {
-        let mut processes: Vec<_> = model
-            .process
-            .processes
-            .iter()
-            .filter_map(
-                |(_, spm)| match (self.select.as_ref(), self.opts.filter.as_ref()) {
-                    (Some(field_id), Some(filter))
-                        if !filter.is_match(
-                            &spm.query(&field_id)
-                                .map_or("?".to_owned(), |v| v.to_string()),
-                        ) =>
-                    {
-                        None
-                    }
-                    _ => Some(spm),
-                },
-            )
-            .collect();
+        let mut processes = self.processes(model);
 
         // Return if we filtered everything.
         if processes.is_empty() {
@@ -101,12 +125,14 @@ impl Dumper for Process {
                 processes.truncate(self.opts.top as usize);
             }
         }
+        // Threads follow their process rather than being sorted on their own
+        let processes = self.rows(processes);
         let json = self.opts.output_format == Some(OutputFormat::Json);
         let mut json_output = json!([]);
 
         processes
             .into_iter()
-            .map(|spm| {
+            .map(|(_, spm)| {
                 match self.opts.output_format {
                     Some(OutputFormat::Raw) | None => write!(
                         output,
@@ -138,6 +164,21 @@ impl Dumper for Process {
                         "{}",
                         print::dump_kv(&self.fields, ctx, spm, self.opts.raw)
                     )?,
+                    Some(OutputFormat::OpenMetrics) => {
+                        let pid = spm.pid.map_or("?".to_owned(), |v| v.to_string());
+                        let comm = spm.comm.as_deref().unwrap_or("?");
+                        write!(
+                            output,
+                            "{}",
+                            print::dump_openmetrics(
+                                &self.fields,
+                                ctx,
+                                spm,
+                                "process",
+                                &[("pid", pid.as_str()), ("comm", comm)]
+                            )
+                        )?
+                    }
                     Some(OutputFormat::Json) => {
                         let par = print::dump_json(&self.fields, ctx, spm, self.opts.raw);
                         json_output.as_array_mut().unwrap().push(par);
@@ -157,3 +198,18 @@ impl Dumper for Process {
         Ok(IterExecResult::Success)
     }
 }
+
+impl Aggregatable for Process {
+    type FieldId = SingleProcessModelFieldId;
+
+    fn fields(&self) -> &[ProcessField] {
+        &self.fields
+    }
+
+    fn entities<'a>(
+        &self,
+        model: &'a model::Model,
+    ) -> Vec<(String, &'a model::SingleProcessModel)> {
+        self.rows(self.processes(model))
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A read-only HTTP endpoint for dump.
+//!
+//! Each request is translated into the equivalent `below dump` command line,
+//! e.g. `GET /process?top=10&rsort=cpu.usage_pct` is handled exactly like
+//! `below dump process --select cpu.usage_pct --rsort --top 10 -O json`, so
+//! the response body is identical to what dump prints.
+
+use super::*;
+
+use std::io::{BufRead, BufReader, Read};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Context;
+use slog::{error, warn};
+use structopt::StructOpt;
+
+/// Dump subcommands exposed as endpoints
+const ENDPOINTS: &[&str] = &[
+    "system",
+    "disk",
+    "process",
+    "cgroup",
+    "iface",
+    "network",
+    "transport",
+];
+
+/// Limit on the size of the request line and headers together
+const MAX_HEADER_SIZE: u64 = 64 * 1024;
+/// Timeout of each read and write on a connection, so stalled clients do
+/// not keep their thread around forever
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn decode_component(s: &str) -> Result<String> {
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => res.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .with_context(|| format!("Truncated percent encoding in {}", s))?;
+                // from_str_radix alone would accept a sign, e.g. "%+1"
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    bail!("Invalid percent encoding in {}", s);
+                }
+                res.push(u8::from_str_radix(std::str::from_utf8(hex)?, 16)?);
+                i += 2;
+            }
+            b => res.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(res).with_context(|| format!("Query is not valid UTF-8: {}", s))
+}
+
+fn parse_query(query: &str) -> Result<Vec<(String, String)>> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((decode_component(key)?, decode_component(value)?))
+        })
+        .collect()
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value {
+        "" | "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => bail!("Invalid value for {}: {}", key, value),
+    }
+}
+
+/// Translate an HTTP request target into `below dump` arguments (including
+/// argv[0]). Returns None if `path` is not a known endpoint.
+pub fn request_to_args(path: &str, query: &str) -> Result<Option<Vec<String>>> {
+    let endpoint = path.trim_matches('/');
+    if !ENDPOINTS.contains(&endpoint) {
+        return Ok(None);
+    }
+
+    let mut args = vec!["dump".to_owned(), endpoint.to_owned()];
+    let mut has_begin = false;
+    let mut has_select = false;
+    let mut cgroup_path = None;
+    for (key, value) in parse_query(query)? {
+        match key.as_str() {
+            "time" => {
+                args.push(format!("--begin={}", value));
+                args.push(format!("--end={}", value));
+                has_begin = true;
+            }
+            "begin" | "end" | "filter" | "top" | "aggregate" => {
+                has_begin |= key == "begin";
+                args.push(format!("--{}={}", key, value));
+            }
+            "select" => {
+                has_select = true;
+                args.push(format!("--select={}", value));
+            }
+            "fields" => {
+                args.push("--fields".to_owned());
+                for field in value.split(',') {
+                    // Would be parsed as another option
+                    if field.starts_with('-') {
+                        bail!("Invalid field: {}", field);
+                    }
+                    args.push(field.to_owned());
+                }
+            }
+            // `sort=<field>` is shorthand for `select=<field>&sort`
+            "sort" | "rsort" => {
+                if !value.is_empty() {
+                    has_select = true;
+                    args.push(format!("--select={}", value));
+                }
+                args.push(format!("--{}", key));
+            }
+            "default" | "everything" | "detail" | "raw" => {
+                if parse_bool(&key, &value)? {
+                    args.push(format!("--{}", key));
+                }
+            }
+            "path" if endpoint == "cgroup" => cgroup_path = Some(value),
+            _ => bail!("Unsupported query parameter: {}", key),
+        }
+    }
+
+    // Restrict the dump to the subtree rooted at `path` by filtering on the
+    // full path of each cgroup.
+    if let Some(cgroup_path) = cgroup_path {
+        if has_select {
+            bail!("path cannot be combined with select, sort or rsort");
+        }
+        let cgroup_path = cgroup_path.trim_matches('/');
+        if !cgroup_path.is_empty() {
+            args.push("--select=full_path".to_owned());
+            args.push(format!("--filter=^/{}(/|$)", regex::escape(cgroup_path)));
+        }
+    }
+
+    if !has_begin {
+        args.push("--begin=now".to_owned());
+    }
+    args.push("--output-format=json".to_owned());
+    Ok(Some(args))
+}
+
+pub(crate) struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub(crate) fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    pub(crate) fn error(status: &'static str, msg: String) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": msg }).to_string().into_bytes(),
+        }
+    }
+}
+
+fn handle_request(
+    logger: slog::Logger,
+    dir: PathBuf,
+    host: Option<String>,
+    port: Option<u16>,
+    target: &str,
+) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let args = match request_to_args(path, query) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            return Response::error(
+                "404 Not Found",
+                format!(
+                    "Unknown endpoint {}, expected one of: {}",
+                    path,
+                    ENDPOINTS.join(", ")
+                ),
+            );
+        }
+        Err(e) => return Response::error("400 Bad Request", format!("{:#}", e)),
+    };
+    let cmd = match DumpCommand::from_iter_safe(&args) {
+        Ok(cmd) => cmd,
+        Err(e) => return Response::error("400 Bad Request", e.message),
+    };
+
+    // Nothing ever stops a single request early
+    let (_err_sender, errs) = channel();
+    let mut body = Vec::new();
+    match run_with_output(logger, errs, dir, host, port, None, cmd, |_| {
+        let output: Box<dyn Write + '_> = Box::new(&mut body);
+        Ok(output)
+    }) {
+        Ok(()) => Response::ok("application/json", body),
+        Err(e) => Response::error("500 Internal Server Error", format!("{:#}", e)),
+    }
+}
+
+fn handle_connection<H>(mut stream: TcpStream, handler: &H) -> Result<()>
+where
+    H: Fn(&str) -> Response,
+{
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_HEADER_SIZE);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not used, but must be consumed before responding
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        _ if reader.limit() == 0 => Response::error(
+            "431 Request Header Fields Too Large",
+            format!("Request headers exceed {} bytes", MAX_HEADER_SIZE),
+        ),
+        (Some("GET"), Some(target)) => handler(target),
+        (Some(_), Some(_)) => Response::error(
+            "405 Method Not Allowed",
+            "Only GET requests are supported".to_owned(),
+        ),
+        _ => Response::error("400 Bad Request", "Malformed request line".to_owned()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+        Content-Type: {}\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Bind to `address` and answer GET requests with `handler` from background
+/// threads, one per connection. `handler` is given the request target.
+pub(crate) fn spawn_http_server<H>(logger: slog::Logger, address: &str, handler: H) -> Result<()>
+where
+    H: Fn(&str) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(address)
+        .with_context(|| format!("Failed to bind HTTP server to {}", address))?;
+    let handler = Arc::new(handler);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let logger = logger.clone();
+                    let handler = handler.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, handler.as_ref()) {
+                            warn!(logger, "Failed to handle HTTP request: {:#}", e);
+                        }
+                    });
+                }
+                Err(e) => error!(logger, "Failed to accept HTTP connection: {:#}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Serve dump output as JSON over HTTP on `address` until an error is
+/// received on `errs`, e.g. a stop signal.
+pub fn serve(
+    logger: slog::Logger,
+    errs: Receiver<Error>,
+    dir: PathBuf,
+    host: Option<String>,
+    port: Option<u16>,
+    address: String,
+) -> Result<()> {
+    let handler_logger = logger.clone();
+    spawn_http_server(logger, &address, move |target| {
+        handle_request(
+            handler_logger.clone(),
+            dir.clone(),
+            host.clone(),
+            port,
+            target,
+        )
+    })?;
+
+    match errs.recv() {
+        Ok(e) => Err(e),
+        Err(_) => bail!("error channel disconnected"),
+    }
+}
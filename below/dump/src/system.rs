@@ -70,6 +70,11 @@ impl Dumper for System {
                 "{}",
                 print::dump_kv(&self.fields, ctx, &model.system, self.opts.raw)
             )?,
+            Some(OutputFormat::OpenMetrics) => write!(
+                output,
+                "{}",
+                print::dump_openmetrics(&self.fields, ctx, &model.system, "system", &[])
+            )?,
             Some(OutputFormat::Json) => {
                 let par = print::dump_json(&self.fields, ctx, &model.system, self.opts.raw);
                 if comma_flag {
@@ -85,3 +90,15 @@ impl Dumper for System {
         Ok(IterExecResult::Success)
     }
 }
+
+impl Aggregatable for System {
+    type FieldId = model::SystemModelFieldId;
+
+    fn fields(&self) -> &[SystemField] {
+        &self.fields
+    }
+
+    fn entities<'a>(&self, model: &'a model::Model) -> Vec<(String, &'a model::SystemModel)> {
+        vec![("system".to_owned(), &model.system)]
+    }
+}
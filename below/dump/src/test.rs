@@ -381,6 +381,54 @@ fn test_dump_proc_select() {
     }
 }
 
+#[test]
+fn test_dump_proc_threads_top() {
+    let mut collector = Collector::new(Default::default()).with_threads(true);
+    let logger = get_logger();
+    collector.update_model(&logger).expect("Fail to get model");
+    let model = collector.update_model(&logger).expect("Fail to get model");
+
+    let fields = command::expand_fields(command::DEFAULT_PROCESS_FIELDS, true);
+    let mut opts: GeneralOpt = Default::default();
+    opts.everything = true;
+    opts.output_format = Some(OutputFormat::Json);
+    opts.sort = true;
+    opts.top = 5;
+    let process_dumper =
+        process::Process::new(&opts, Some(model::SingleProcessModelFieldId::Pid), fields)
+            .with_threads(true);
+
+    let mut process_content: Vec<u8> = Vec::new();
+    let mut round = 0;
+    let ctx = CommonFieldContext { timestamp: 0 };
+    process_dumper
+        .dump_model(&ctx, &model, &mut process_content, &mut round, false)
+        .expect("Failed to dump process model");
+
+    // --top picks the processes, each then followed by all of its threads
+    let expected: Vec<String> = model
+        .process
+        .processes
+        .values()
+        .take(5)
+        .flat_map(|spm| {
+            std::iter::once(spm)
+                .chain(spm.threads.iter().flat_map(|threads| threads.values()))
+                .map(|spm| spm.pid.unwrap().to_string())
+        })
+        .collect();
+    let jval: Value =
+        serde_json::from_slice(&process_content).expect("Fail parse json of process dump");
+    let pids: Vec<String> = jval
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["Pid"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(round, expected.len());
+    assert_eq!(pids, expected);
+}
+
 fn traverse_cgroup_tree(model: &model::CgroupModel, jval: &Value) {
     for dump_field in expand_fields(command::DEFAULT_CGROUP_FIELDS, true) {
         match dump_field {
@@ -439,7 +487,7 @@ fn test_dump_cgroup_content() {
 
 #[test]
 fn test_dump_cgroup_titles() {
-    let titles = expand_fields(command::DEFAULT_CGROUP_FIELDS, true)
+    let titles = expand_fields(command::EVERYTHING_CGROUP_FIELDS, true)
         .iter()
         .filter_map(|dump_field| match dump_field {
             DumpField::Common(_) => None,
@@ -458,6 +506,10 @@ fn test_dump_cgroup_titles() {
         "Nr Period",
         "Nr Throttled",
         "Throttled Pct",
+        "CPU Weight",
+        "CPU Max",
+        "CPU Max Period",
+        "CPU % Max",
         "Mem Total",
         "Mem Swap",
         "Mem Anon",
@@ -477,13 +529,23 @@ fn test_dump_cgroup_titles() {
         "Unevictable",
         "Slab Reclaimable",
         "Slab Unreclaimable",
+        "Percpu",
+        "Sec Page Tables",
+        "Zswap",
+        "Zswapped",
+        "File THP",
+        "Shmem THP",
         "Pgfault",
         "Pgmajfault",
         "Workingset Refault",
+        "Workingset Refault Anon",
+        "Workingset Refault File",
         "Workingset Activate",
         "Workingset Nodereclaim",
         "Pgrefill",
         "Pgscan",
+        "Pgscan Direct",
+        "Pgscan Kswapd",
         "Pgsteal",
         "Pgactivate",
         "Pgdeactivate",
@@ -492,6 +554,12 @@ fn test_dump_cgroup_titles() {
         "THP Fault Alloc",
         "THP Collapse Alloc",
         "Memory High",
+        "Memory Max",
+        "Memory Low",
+        "Memory Min",
+        "Swap Max",
+        "Mem % Max",
+        "Swap % Max",
         "Events Low",
         "Events High",
         "Events Max",
@@ -504,13 +572,37 @@ fn test_dump_cgroup_titles() {
         "DBytes",
         "D I/O",
         "RW Total",
+        "IO Weight",
+        "Read BPS Max",
+        "Write BPS Max",
+        "Read IOPS Max",
+        "Write IOPS Max",
+        "Reads % Max",
+        "Writes % Max",
         "CPU Pressure",
         "I/O Some Pressure",
         "I/O Pressure",
         "Memory Some Pressure",
         "Memory Pressure",
+        "Pids",
+        "Pids Max",
+        "Pids % Max",
+        "Pids Max Events",
     ];
     assert_eq!(titles, expected_titles);
+
+    // Fields added later are not part of the defaults
+    let default_titles = expand_fields(command::DEFAULT_CGROUP_FIELDS, true)
+        .iter()
+        .filter_map(|dump_field| match dump_field {
+            DumpField::Common(_) => None,
+            DumpField::FieldId(field_id) => {
+                let rc = model::SingleCgroupModel::get_render_config_for_dump(&field_id);
+                Some(rc.render_title(false))
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(default_titles, expected_titles[..expected_titles.len() - 4]);
 }
 
 #[test]
@@ -989,3 +1081,384 @@ proc = ["datetime", "mem.anon"]
         ))
     );
 }
+
+#[test]
+fn test_serve_request_to_args() {
+    use structopt::StructOpt;
+
+    let args = serve::request_to_args("/process", "time=10m&rsort=cpu.usage_pct&top=10")
+        .expect("Failed to translate request")
+        .expect("Unknown endpoint");
+    match DumpCommand::from_iter_safe(&args).expect("Failed to parse args") {
+        DumpCommand::Process { opts, select, .. } => {
+            assert_eq!(opts.begin, "10m");
+            assert_eq!(opts.end.as_deref(), Some("10m"));
+            assert!(opts.rsort);
+            assert_eq!(opts.top, 10);
+            assert_eq!(opts.output_format, Some(OutputFormat::Json));
+            assert_eq!(
+                select,
+                Some(model::SingleProcessModelFieldId::Cpu(
+                    model::ProcessCpuModelFieldId::UsagePct
+                ))
+            );
+        }
+        _ => panic!("Expected process command"),
+    }
+
+    let args = serve::request_to_args("/cgroup/", "path=%2Fsystem.slice%2F&fields=name,cpu")
+        .expect("Failed to translate request")
+        .expect("Unknown endpoint");
+    match DumpCommand::from_iter_safe(&args).expect("Failed to parse args") {
+        DumpCommand::Cgroup {
+            fields,
+            opts,
+            select,
+            ..
+        } => {
+            assert_eq!(opts.begin, "now");
+            assert_eq!(opts.end, None);
+            assert_eq!(fields.map(|f| f.len()), Some(2));
+            assert_eq!(select, Some(model::SingleCgroupModelFieldId::FullPath));
+            let filter = opts.filter.expect("Missing filter");
+            assert!(filter.is_match("/system.slice"));
+            assert!(filter.is_match("/system.slice/foo.service"));
+            assert!(!filter.is_match("/system.slice2"));
+        }
+        _ => panic!("Expected cgroup command"),
+    }
+
+    assert!(serve::request_to_args("/foo", "")
+        .expect("Failed to translate request")
+        .is_none());
+    assert!(serve::request_to_args("/system", "output=/tmp/foo").is_err());
+    assert!(serve::request_to_args("/cgroup", "path=/a&sort=name").is_err());
+    // Field lists cannot smuggle in other options
+    assert!(serve::request_to_args("/process", "fields=pid,--output=/tmp/foo").is_err());
+    assert!(serve::request_to_args("/process", "fields=pid,%2D%2Doutput").is_err());
+    // Percent encoding takes exactly two hex digits
+    assert!(serve::request_to_args("/process", "filter=%+1").is_err());
+    assert!(serve::request_to_args("/process", "filter=%2").is_err());
+    assert!(serve::request_to_args("/process", "filter=%41").is_ok());
+}
+
+#[test]
+fn test_openmetrics_render_model() {
+    let model = model::get_sample_model();
+    let output = openmetrics::render_model(&model);
+
+    let lines = output.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"# TYPE below_system_cpu_usage_pct gauge"));
+    assert!(lines.contains(&"below_system_cpu_usage_pct 20"));
+    assert!(lines.contains(&"below_cgroup_cpu_usage_pct{path=\"/child_b.slice\"} 3.5"));
+    assert!(lines.contains(&"below_disk_major{disk=\"vda\"} 20"));
+    assert!(lines.contains(&"below_iface_rx_bytes_per_sec{interface=\"eth0\"} 200000.5"));
+    assert_eq!(lines.last(), Some(&"# EOF"));
+
+    // Each family is declared exactly once and all of its samples follow
+    let mut families = std::collections::BTreeSet::new();
+    let mut current = "";
+    for line in &lines {
+        if let Some(name) = line.strip_prefix("# TYPE ") {
+            let name = name.split(' ').next().unwrap();
+            assert!(families.insert(name.to_owned()), "{} declared twice", name);
+            current = name;
+        } else if !line.starts_with('#') {
+            assert!(line.starts_with(current), "{} outside of {}", line, current);
+        }
+    }
+}
+
+#[test]
+fn test_dump_cgroup_openmetrics() {
+    let model = model::get_sample_model();
+    let mut opts: GeneralOpt = Default::default();
+    let fields = command::expand_fields(command::DEFAULT_CGROUP_FIELDS, false);
+    opts.output_format = Some(OutputFormat::OpenMetrics);
+    let cgroup_dumper = cgroup::Cgroup::new(&opts, None, fields);
+
+    let mut samples: Vec<u8> = Vec::new();
+    let mut round = 0;
+    for timestamp in [100, 105] {
+        let ctx = CommonFieldContext { timestamp };
+        cgroup_dumper
+            .dump_model(&ctx, &model, &mut samples, &mut round, false)
+            .expect("Failed to dump cgroup model");
+    }
+    let samples = String::from_utf8(samples).expect("Output is not UTF-8");
+    // Names follow FieldIds like the exporter, e.g. cpu.usage_pct
+    assert!(samples.contains("below_cgroup_cpu_usage_pct{path=\"/child_b.slice\"} 3.5 100\n"));
+    assert!(samples.contains("below_cgroup_cpu_usage_pct{path=\"/child_b.slice\"} 3.5 105\n"));
+    // Non-numeric fields such as the cgroup name are not exported
+    assert!(!samples.contains("below_cgroup_name"));
+}
+
+#[test]
+fn test_dump_timeseries_openmetrics() {
+    use std::time::{Duration, UNIX_EPOCH};
+    use store::{CompressionMode, DataFrame, Format, StoreWriter};
+
+    let tempdir = TempDir::new("below_dump_openmetrics").expect("Failed to create temp dir");
+    let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+    let mut writer = StoreWriter::new_with_timestamp(
+        tempdir.path(),
+        at(100),
+        CompressionMode::None,
+        Format::Cbor,
+    )
+    .expect("Failed to create store");
+    for ts in [100, 105] {
+        let mut frame = DataFrame::default();
+        for pid in [1, 2] {
+            let pid_info = frame.sample.processes.entry(pid).or_default();
+            pid_info.stat.pid = Some(pid);
+            pid_info.stat.ppid = Some(0);
+        }
+        writer
+            .put(at(ts), &frame, get_logger())
+            .expect("Failed to store data");
+    }
+
+    let mut opts: GeneralOpt = Default::default();
+    opts.output_format = Some(OutputFormat::OpenMetrics);
+    let fields = vec![
+        DumpField::FieldId(model::SingleProcessModelFieldId::Pid),
+        DumpField::FieldId(model::SingleProcessModelFieldId::Ppid),
+    ];
+    let process_dumper = process::Process::new(&opts, None, fields);
+
+    let mut advance =
+        store::advance::new_advance_local(get_logger(), tempdir.path().to_path_buf(), at(100));
+    advance.initialize();
+    let (_tx, errs) = std::sync::mpsc::channel();
+    let mut output: Vec<u8> = Vec::new();
+    tmain::dump_timeseries(
+        advance,
+        at(100),
+        at(110),
+        &process_dumper,
+        &mut output,
+        opts.output_format,
+        None,
+        errs,
+    )
+    .expect("Failed to dump timeseries");
+    let output = String::from_utf8(output).expect("Output is not UTF-8");
+
+    // Every family is written once with the samples of all sample times
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec![
+            "# TYPE below_process_pid gauge",
+            "below_process_pid{pid=\"1\",comm=\"?\"} 1 100",
+            "below_process_pid{pid=\"2\",comm=\"?\"} 2 100",
+            "below_process_pid{pid=\"1\",comm=\"?\"} 1 105",
+            "below_process_pid{pid=\"2\",comm=\"?\"} 2 105",
+            "# TYPE below_process_ppid gauge",
+            "below_process_ppid{pid=\"1\",comm=\"?\"} 0 100",
+            "below_process_ppid{pid=\"2\",comm=\"?\"} 0 100",
+            "below_process_ppid{pid=\"1\",comm=\"?\"} 0 105",
+            "below_process_ppid{pid=\"2\",comm=\"?\"} 0 105",
+            "# EOF",
+        ]
+    );
+}
+
+#[test]
+fn test_export_parquet_tables() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let tempdir = TempDir::new("below_dump_export").expect("Failed to create temp dir");
+    let model = model::get_sample_model();
+    let mut tables = export::Tables::new(tempdir.path());
+    tables.push(&model).expect("Failed to push model");
+    tables.push(&model).expect("Failed to push model");
+    tables.close().expect("Failed to write tables");
+
+    let read_table = |name: &str| {
+        let file = File::open(tempdir.path().join(format!("{}.parquet", name)))
+            .expect("Failed to open table");
+        SerializedFileReader::new(file).expect("Failed to read table")
+    };
+
+    // One row per cgroup per sample
+    let cgroup = read_table("cgroup");
+    let metadata = cgroup.metadata().file_metadata();
+    assert_eq!(metadata.num_rows(), 10);
+    let columns = metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|c| c.name().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(columns[0], "timestamp");
+    assert!(columns.contains(&"name".to_owned()));
+    assert!(columns.contains(&"cpu.usage_pct".to_owned()));
+
+    assert_eq!(
+        read_table("system").metadata().file_metadata().num_rows(),
+        2
+    );
+    assert_eq!(read_table("disk").metadata().file_metadata().num_rows(), 4);
+
+    // Column types come from the fields
+    let column_type = |name: &str| {
+        metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .find(|c| c.name() == name)
+            .map(|c| c.physical_type())
+    };
+    assert_eq!(
+        column_type("cpu.usage_pct"),
+        Some(parquet::basic::Type::DOUBLE)
+    );
+    assert_eq!(column_type("name"), Some(parquet::basic::Type::BYTE_ARRAY));
+}
+
+#[test]
+fn test_export_parquet_empty_tables() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let tempdir = TempDir::new("below_dump_export").expect("Failed to create temp dir");
+    let tables = export::Tables::new(tempdir.path());
+    tables.close().expect("Failed to write tables");
+
+    // Every table is written even without any rows
+    for name in &["system", "cgroup", "process", "iface", "disk", "network"] {
+        let file = File::open(tempdir.path().join(format!("{}.parquet", name)))
+            .expect("Failed to open table");
+        let reader = SerializedFileReader::new(file).expect("Failed to read table");
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 0);
+        assert_eq!(metadata.schema_descr().column(0).name(), "timestamp");
+        // The schema does not depend on the data
+        let column_type = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .find(|c| c.name() == "cpu.usage_pct")
+            .map(|c| c.physical_type());
+        if *name == "cgroup" || *name == "process" {
+            assert_eq!(column_type, Some(parquet::basic::Type::DOUBLE));
+        }
+    }
+}
+
+#[test]
+fn test_export_parquet_schema_from_fields() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let tempdir = TempDir::new("below_dump_export").expect("Failed to create temp dir");
+    let mut model = model::get_sample_model();
+    for process in model.process.processes.values_mut() {
+        process.cpu = None;
+    }
+    let mut tables = export::Tables::new(tempdir.path());
+    tables.push(&model).expect("Failed to push model");
+    tables.close().expect("Failed to write tables");
+
+    // Numeric columns stay numeric even without any value
+    let file = File::open(tempdir.path().join("process.parquet")).expect("Failed to open table");
+    let reader = SerializedFileReader::new(file).expect("Failed to read table");
+    let column_type = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .find(|c| c.name() == "cpu.usage_pct")
+        .map(|c| c.physical_type());
+    assert_eq!(column_type, Some(parquet::basic::Type::DOUBLE));
+}
+
+#[test]
+fn test_dump_cgroup_aggregate() {
+    use model::{CgroupMemoryModelFieldId, SingleCgroupModelFieldId};
+
+    let mut opts: GeneralOpt = Default::default();
+    opts.output_format = Some(OutputFormat::Csv);
+    opts.raw = true;
+    opts.filter = Some(regex::Regex::new("^$").unwrap());
+    let fields = vec![
+        DumpField::Common(CommonField::Timestamp),
+        DumpField::FieldId(SingleCgroupModelFieldId::Name),
+        DumpField::FieldId(SingleCgroupModelFieldId::Mem(
+            CgroupMemoryModelFieldId::Total,
+        )),
+    ];
+    // Only the root cgroup has an empty full path
+    let cgroup = cgroup::Cgroup::new(&opts, Some(SingleCgroupModelFieldId::FullPath), fields);
+
+    let mut aggregator = aggregate::Aggregator::new(&cgroup);
+    let mut model = model::get_sample_model();
+    for total in [100, 400, 200, 300] {
+        model
+            .cgroup
+            .data
+            .memory
+            .get_or_insert_with(Default::default)
+            .total = Some(total);
+        aggregator.add(&model);
+    }
+    let mut output: Vec<u8> = Vec::new();
+    aggregator
+        .write(
+            &[
+                command::Aggregation::Min,
+                command::Aggregation::Max,
+                command::Aggregation::Avg,
+                command::Aggregation::P50,
+                command::Aggregation::P99,
+            ],
+            &opts,
+            &mut output,
+        )
+        .expect("Failed to write aggregates");
+
+    let name = model.cgroup.data.name.clone();
+    let expected = std::iter::once("Entity,Agg,Name,Mem Total,".to_owned())
+        .chain(
+            [
+                ("min", 100),
+                ("max", 400),
+                ("avg", 250),
+                ("p50", 200),
+                ("p99", 400),
+            ]
+            .iter()
+            .map(|(agg, total)| format!("/,{},{},{},", agg, name, total)),
+        )
+        .collect::<Vec<_>>();
+    assert_eq!(
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn test_dump_annotations() {
+    let tempdir = TempDir::new("below_dump_annotations").expect("Failed to create temp dir");
+    let at = |secs| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+    store::annotation::append_annotation(tempdir.path(), at(100), "user", "deploy started")
+        .expect("Failed to append annotation");
+    store::annotation::append_annotation(tempdir.path(), at(200), "alert", "firing")
+        .expect("Failed to append annotation");
+
+    let mut opts: GeneralOpt = Default::default();
+    opts.output_format = Some(OutputFormat::Csv);
+    let mut output: Vec<u8> = Vec::new();
+    annotations::dump_annotations(tempdir.path(), at(0), at(150), &opts, &mut output)
+        .expect("Failed to dump annotations");
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!(
+            "Datetime,Timestamp,Source,Text,\n{},100,user,deploy started,\n",
+            common::util::timestamp_to_datetime(&100)
+        )
+    );
+}
@@ -63,6 +63,12 @@ pub fn dump_timeseries(
 
     let json = output_format == Some(OutputFormat::Json);
     let csv = output_format == Some(OutputFormat::Csv);
+    let openmetrics = output_format == Some(OutputFormat::OpenMetrics);
+
+    // OpenMetrics samples are buffered for the whole time range so that every
+    // metric family is written once with all of its samples.
+    let mut openmetrics_buf: Vec<u8> = Vec::new();
+    let mut families = crate::openmetrics::Families::default();
 
     let mut round = 0;
 
@@ -83,7 +89,18 @@ pub fn dump_timeseries(
         };
         // Base on the exec result, we will determine if we need to generate the line breaker, etc
         let comma_flag = round != 0;
-        let res = match dumper.dump_model(&ctx, &model, output, &mut round, comma_flag) {
+        let res = if openmetrics {
+            dumper
+                .dump_model(&ctx, &model, &mut openmetrics_buf, &mut round, comma_flag)
+                .and_then(|res| {
+                    families.add_samples(std::str::from_utf8(&openmetrics_buf)?);
+                    openmetrics_buf.clear();
+                    Ok(res)
+                })
+        } else {
+            dumper.dump_model(&ctx, &model, output, &mut round, comma_flag)
+        };
+        let res = match res {
             Ok(res) => res,
             Err(e) => {
                 // Swallow BrokenPipe error for write. Rust runtime will ignore SIGPIPE by default and
@@ -113,7 +130,7 @@ pub fn dump_timeseries(
 
         if json {
             write!(output, "\n")?;
-        } else if br.is_some() && !csv {
+        } else if br.is_some() && !csv && !openmetrics {
             write!(output, "{}\n", br.as_ref().unwrap())?;
         }
     }
@@ -122,6 +139,10 @@ pub fn dump_timeseries(
         write!(output, "]")?;
     }
 
+    if openmetrics {
+        families.write(output)?;
+    }
+
     cliutil::check_final_sample_time_with_requested_time(model.timestamp, time_end);
 
     Ok(())
@@ -68,6 +68,11 @@ impl Dumper for Transport {
                 "{}",
                 print::dump_kv(&self.fields, ctx, &model.network, self.opts.raw)
             )?,
+            Some(OutputFormat::OpenMetrics) => write!(
+                output,
+                "{}",
+                print::dump_openmetrics(&self.fields, ctx, &model.network, "transport", &[])
+            )?,
             Some(OutputFormat::Json) => {
                 let json_output =
                     print::dump_json(&self.fields, ctx, &model.network, self.opts.raw);
@@ -87,3 +92,15 @@ impl Dumper for Transport {
         Ok(IterExecResult::Success)
     }
 }
+
+impl Aggregatable for Transport {
+    type FieldId = model::NetworkModelFieldId;
+
+    fn fields(&self) -> &[TransportField] {
+        &self.fields
+    }
+
+    fn entities<'a>(&self, model: &'a model::Model) -> Vec<(String, &'a model::NetworkModel)> {
+        vec![("network".to_owned(), &model.network)]
+    }
+}
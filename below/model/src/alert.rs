@@ -0,0 +1,312 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Threshold rules over model fields, evaluated by the recorder on every
+//! sample. A rule looks like
+//!
+//!     <field> <op> <threshold> [for <duration>]
+//!
+//! e.g. `system.mem.free < 1GiB` or
+//! `cgroup./system.slice.pressure.memory_full_pct > 20 for 30s`. The field is
+//! any ModelFieldId, where `cgroup.<cgroup_path>.<field>` is short for
+//! `cgroup.path:<cgroup_path>/.<field>`. Thresholds take size suffixes
+//! (K, M, G, T, optionally followed by `i` and `B`), which are all powers of
+//! 1024, and an optional trailing `%` for readability.
+
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlertOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl AlertOp {
+    fn matches(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            AlertOp::Lt => value < threshold,
+            AlertOp::Le => value <= threshold,
+            AlertOp::Gt => value > threshold,
+            AlertOp::Ge => value >= threshold,
+        }
+    }
+}
+
+impl FromStr for AlertOp {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "<" => Ok(AlertOp::Lt),
+            "<=" => Ok(AlertOp::Le),
+            ">" => Ok(AlertOp::Gt),
+            ">=" => Ok(AlertOp::Ge),
+            _ => Err(anyhow!(
+                "Unknown operator: {}, expected one of <, <=, > or >=",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertRule {
+    /// The rule as written in the config
+    pub rule: String,
+    pub field_id: ModelFieldId,
+    pub op: AlertOp,
+    pub threshold: f64,
+    /// How long the condition must hold before the alert fires
+    pub duration: Duration,
+}
+
+/// Expand the `cgroup.<cgroup_path>.<field>` shorthand. As cgroup names may
+/// contain dots, the path ends at the first dot after its last `/` that
+/// leaves a valid cgroup field.
+fn parse_field_id(s: &str) -> Result<ModelFieldId> {
+    if let Some(rest) = s.strip_prefix("cgroup./") {
+        let name_start = rest.rfind('/').map_or(0, |idx| idx + 1);
+        for (idx, _) in rest
+            .match_indices('.')
+            .filter(|(idx, _)| *idx >= name_start)
+        {
+            if SingleCgroupModelFieldId::from_str(&rest[idx + 1..]).is_ok() {
+                let expanded = format!("cgroup.path:/{}/.{}", &rest[..idx], &rest[idx + 1..]);
+                return ModelFieldId::from_str(&expanded);
+            }
+        }
+        return Err(anyhow!("No cgroup field found in {}", s));
+    }
+    ModelFieldId::from_str(s).map_err(|_| anyhow!("Unknown field: {}", s))
+}
+
+fn parse_threshold(s: &str) -> Result<f64> {
+    let (num, multiplier) = if let Some(num) = s.strip_suffix('%') {
+        (num, 1)
+    } else {
+        let unit_start = s
+            .find(|c: char| matches!(c, 'K' | 'M' | 'G' | 'T'))
+            .unwrap_or(s.len());
+        let (num, unit) = s.split_at(unit_start);
+        let multiplier = match unit {
+            "" => 1,
+            "K" | "KiB" => 1u64 << 10,
+            "M" | "MiB" => 1 << 20,
+            "G" | "GiB" => 1 << 30,
+            "T" | "TiB" => 1 << 40,
+            _ => {
+                return Err(anyhow!(
+                    "Invalid threshold unit: {}, expected K, M, G, T, KiB, MiB, GiB, TiB or %",
+                    s
+                ));
+            }
+        };
+        (num, multiplier)
+    };
+    let num = f64::from_str(num).map_err(|_| anyhow!("Invalid threshold: {}", s))?;
+    Ok(num * multiplier as f64)
+}
+
+fn parse_duration(s: &str) -> Result<Duration> {
+    let (num, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let num = u64::from_str(num).map_err(|_| anyhow!("Invalid duration: {}", s))?;
+    let secs = match unit {
+        "" | "s" => num,
+        "m" => num * 60,
+        "h" => num * 60 * 60,
+        "d" => num * 60 * 60 * 24,
+        _ => {
+            return Err(anyhow!(
+                "Invalid duration unit: {}, expected s, m, h or d",
+                s
+            ))
+        }
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+impl FromStr for AlertRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let duration = match parts.len() {
+            3 => Duration::from_secs(0),
+            5 if parts[3] == "for" => parse_duration(parts[4])?,
+            _ => {
+                return Err(anyhow!(
+                    "Invalid alert rule: {}, expected <field> <op> <threshold> [for <duration>]",
+                    s
+                ));
+            }
+        };
+        Ok(Self {
+            rule: s.to_owned(),
+            field_id: parse_field_id(parts[0])?,
+            op: parts[1].parse()?,
+            threshold: parse_threshold(parts[2])?,
+            duration,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlertEvent {
+    /// The condition has held for the duration of the rule
+    Fired { value: f64 },
+    /// The condition stopped holding after the alert fired. The value is None
+    /// if the field is no longer available, e.g. the cgroup went away.
+    Resolved { value: Option<f64> },
+}
+
+/// A rule and whether it currently holds
+#[derive(Clone, Debug)]
+pub struct AlertState {
+    pub rule: AlertRule,
+    /// Timestamp of the first sample of the current run of samples matching
+    /// the rule
+    since: Option<SystemTime>,
+    firing: bool,
+}
+
+impl AlertState {
+    pub fn new(rule: AlertRule) -> Self {
+        Self {
+            rule,
+            since: None,
+            firing: false,
+        }
+    }
+
+    /// Evaluate the rule against the next model, returning an event if the
+    /// alert fired or resolved. Non-numeric fields never match.
+    pub fn evaluate(&mut self, model: &Model) -> Option<AlertEvent> {
+        let value = match model.query(&self.rule.field_id) {
+            Some(field @ (Field::U32(_) | Field::U64(_) | Field::I32(_) | Field::I64(_))) => {
+                Some(f64::from(field))
+            }
+            Some(Field::F64(value)) => Some(value),
+            _ => None,
+        };
+        match value {
+            Some(value) if self.rule.op.matches(value, self.rule.threshold) => {
+                let since = *self.since.get_or_insert(model.timestamp);
+                let held = model.timestamp.duration_since(since).unwrap_or_default();
+                if !self.firing && held >= self.rule.duration {
+                    self.firing = true;
+                    return Some(AlertEvent::Fired { value });
+                }
+                None
+            }
+            _ => {
+                self.since = None;
+                if self.firing {
+                    self.firing = false;
+                    return Some(AlertEvent::Resolved { value });
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        let rule: AlertRule = "system.mem.free < 1GiB".parse().expect("Failed to parse");
+        assert_eq!(rule.op, AlertOp::Lt);
+        assert_eq!(rule.threshold, (1u64 << 30) as f64);
+        assert_eq!(rule.duration, Duration::from_secs(0));
+
+        let rule: AlertRule = "system.mem.free < 1G".parse().expect("Failed to parse");
+        assert_eq!(rule.threshold, (1u64 << 30) as f64);
+        let rule: AlertRule = "system.mem.free < 1.5K".parse().expect("Failed to parse");
+        assert_eq!(rule.threshold, 1536.0);
+
+        let rule: AlertRule =
+            "cgroup./child_b.slice/something.service.pressure.memory_full_pct >= 20% for 2m"
+                .parse()
+                .expect("Failed to parse");
+        assert_eq!(
+            rule.field_id,
+            ModelFieldId::from_str(
+                "cgroup.path:/child_b.slice/something.service/.pressure.memory_full_pct"
+            )
+            .unwrap()
+        );
+        assert_eq!(rule.op, AlertOp::Ge);
+        assert_eq!(rule.threshold, 20.0);
+        assert_eq!(rule.duration, Duration::from_secs(120));
+
+        for bad in [
+            "system.mem.free < 1GiB for",
+            "system.mem.free ~ 1GiB",
+            "system.mem.free < 1XiB",
+            "system.mem.free < 1i",
+            "system.mem.free < 1B",
+            "system.mem.free < 1Gi",
+            "system.mem.free < 1GB",
+            "system.mem.free < 1G%",
+            "system.mem.free < 1GiBiB",
+            "system.mem.free < 1GiB for 1w",
+            "system.no_such_field > 1",
+            "cgroup./child_b.slice.no_such_field > 1",
+        ] {
+            assert!(
+                bad.parse::<AlertRule>().is_err(),
+                "{} should not parse",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn evaluate_rule() {
+        let mut model = get_sample_model();
+        let free = model.system.mem.free.unwrap();
+        let mut state = AlertState::new(
+            format!("system.mem.free < {} for 10s", free + 1)
+                .parse()
+                .unwrap(),
+        );
+        let begin = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let mut at = |secs: u64, free: u64| {
+            model.timestamp = begin + Duration::from_secs(secs);
+            model.system.mem.free = Some(free);
+            state.evaluate(&model)
+        };
+
+        assert_eq!(at(0, free), None);
+        assert_eq!(at(5, free), None);
+        assert_eq!(at(10, free), Some(AlertEvent::Fired { value: free as f64 }));
+        // Does not fire again while firing
+        assert_eq!(at(20, free), None);
+        assert_eq!(
+            at(25, free + 1),
+            Some(AlertEvent::Resolved {
+                value: Some((free + 1) as f64)
+            })
+        );
+        // Condition must hold for the whole duration again
+        assert_eq!(at(30, free), None);
+        assert_eq!(at(35, free + 1), None);
+        assert_eq!(at(40, free), None);
+        assert_eq!(at(50, free), Some(AlertEvent::Fired { value: free as f64 }));
+    }
+}
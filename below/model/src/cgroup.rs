@@ -21,6 +21,12 @@ pub struct SingleCgroupModel {
     pub name: String,
     pub full_path: String,
     pub inode_number: Option<u64>,
+    pub nr_descendants: Option<u64>,
+    pub nr_dying_descendants: Option<u64>,
+    pub populated: Option<u64>,
+    pub frozen: Option<u64>,
+    pub cpuset_cpus: Option<String>,
+    pub cpuset_mems: Option<String>,
     #[queriable(ignore)]
     pub depth: u32,
     #[queriable(subquery)]
@@ -29,6 +35,11 @@ pub struct SingleCgroupModel {
     #[queriable(preferred_name = mem)]
     pub memory: Option<CgroupMemoryModel>,
     #[queriable(subquery)]
+    #[queriable(preferred_name = mem_numa)]
+    pub memory_numa: Option<BTreeMap<u32, CgroupMemoryNumaModel>>,
+    #[queriable(subquery)]
+    pub hugetlb: Option<BTreeMap<String, CgroupHugetlbModel>>,
+    #[queriable(subquery)]
     #[queriable(preferred_name = io_details)]
     pub io: Option<BTreeMap<String, CgroupIoModel>>,
     #[queriable(subquery)]
@@ -36,6 +47,10 @@ pub struct SingleCgroupModel {
     pub io_total: Option<CgroupIoModel>,
     #[queriable(subquery)]
     pub pressure: Option<CgroupPressureModel>,
+    #[queriable(subquery)]
+    pub pids: Option<CgroupPidsModel>,
+    #[queriable(subquery)]
+    pub extra: Option<CgroupExtraModel>,
 }
 
 /// A model that represents a cgroup subtree. Each instance is a node that uses
@@ -184,7 +199,11 @@ impl CgroupModel {
         {
             // We have cumulative data, create cpu, io models
             let cpu = match (last.cpu_stat.as_ref(), sample.cpu_stat.as_ref()) {
-                (Some(begin), Some(end)) => Some(CgroupCpuModel::new(begin, end, delta)),
+                (Some(begin), Some(end)) => {
+                    let mut cpu = CgroupCpuModel::new(begin, end, delta);
+                    cpu.set_limits(sample);
+                    Some(cpu)
+                }
                 _ => None,
             };
             let io = match (last.io_stat.as_ref(), sample.io_stat.as_ref()) {
@@ -192,10 +211,10 @@ impl CgroupModel {
                     end.iter()
                         .filter_map(|(device_name, end_io_stat)| {
                             begin.get(device_name).map(|begin_io_stat| {
-                                (
-                                    device_name.clone(),
-                                    CgroupIoModel::new(&begin_io_stat, &end_io_stat, delta),
-                                )
+                                let mut io =
+                                    CgroupIoModel::new(&begin_io_stat, &end_io_stat, delta);
+                                io.set_limits(sample, device_name);
+                                (device_name.clone(), io)
                             })
                         })
                         .collect::<BTreeMap<String, CgroupIoModel>>(),
@@ -203,9 +222,14 @@ impl CgroupModel {
                 _ => None,
             };
             let io_total = io.as_ref().map(|io_map| {
-                io_map
+                let mut io_total = io_map
                     .iter()
-                    .fold(CgroupIoModel::empty(), |acc, (_, model)| acc + model)
+                    .fold(CgroupIoModel::empty(), |acc, (_, model)| acc + model);
+                io_total.weight = sample
+                    .io_weight
+                    .as_ref()
+                    .and_then(|weights| weights.get("default").cloned());
+                io_total
             });
 
             (cpu, io, io_total, false)
@@ -216,11 +240,36 @@ impl CgroupModel {
 
         let memory = Some(CgroupMemoryModel::new(sample, last));
 
+        let memory_numa = sample.memory_numa_stat.as_ref().map(|nodes| {
+            nodes
+                .iter()
+                .map(|(node, stat)| (*node, CgroupMemoryNumaModel::new(stat)))
+                .collect()
+        });
+
+        let hugetlb = sample.hugetlb_current.as_ref().map(|sizes| {
+            sizes
+                .iter()
+                .map(|(size, current)| {
+                    (
+                        size.clone(),
+                        CgroupHugetlbModel {
+                            current: Some(*current),
+                        },
+                    )
+                })
+                .collect()
+        });
+
         let pressure = sample
             .pressure
             .as_ref()
             .map(|p| CgroupPressureModel::new(p));
 
+        let pids = Some(CgroupPidsModel::new(sample));
+
+        let extra = sample.extra_files.clone().map(CgroupExtraModel);
+
         // recursively calculate view of children
         // `children` is optional, but we treat it the same as an empty map
         let empty = BTreeMap::new();
@@ -251,11 +300,39 @@ impl CgroupModel {
                 name,
                 full_path,
                 inode_number: sample.inode_number.map(|ino| ino as u64),
+                nr_descendants: sample
+                    .cgroup_stat
+                    .as_ref()
+                    .and_then(|stat| stat.nr_descendants),
+                nr_dying_descendants: sample
+                    .cgroup_stat
+                    .as_ref()
+                    .and_then(|stat| stat.nr_dying_descendants),
+                populated: sample
+                    .cgroup_events
+                    .as_ref()
+                    .and_then(|events| events.populated),
+                frozen: sample
+                    .cgroup_events
+                    .as_ref()
+                    .and_then(|events| events.frozen),
+                cpuset_cpus: sample
+                    .cpuset_cpus_effective
+                    .as_ref()
+                    .map(|cpus| cpus.to_string()),
+                cpuset_mems: sample
+                    .cpuset_mems_effective
+                    .as_ref()
+                    .map(|mems| mems.to_string()),
                 cpu,
                 memory,
+                memory_numa,
+                hugetlb,
                 io,
                 io_total,
                 pressure,
+                pids,
+                extra,
                 depth,
             },
             children,
@@ -294,6 +371,12 @@ pub struct CgroupCpuModel {
     pub nr_periods_per_sec: Option<f64>,
     pub nr_throttled_per_sec: Option<f64>,
     pub throttled_pct: Option<f64>,
+    pub weight: Option<u32>,
+    /// Quota of cpu.max per period, -1 for no limit
+    pub max_usec: Option<i64>,
+    pub max_period_usec: Option<u64>,
+    /// usage_pct as a percentage of the quota of cpu.max
+    pub usage_pct_of_max: Option<f64>,
 }
 
 impl CgroupCpuModel {
@@ -309,8 +392,37 @@ impl CgroupCpuModel {
             nr_periods_per_sec: count_per_sec!(begin.nr_periods, end.nr_periods, delta),
             nr_throttled_per_sec: count_per_sec!(begin.nr_throttled, end.nr_throttled, delta),
             throttled_pct: usec_pct!(begin.throttled_usec, end.throttled_usec, delta),
+            ..Default::default()
         }
     }
+
+    /// Add the cpu.max and cpu.weight limits of `sample`
+    fn set_limits(&mut self, sample: &CgroupSample) {
+        self.weight = sample.cpu_weight;
+        if let Some(cpu_max) = &sample.cpu_max {
+            self.max_usec = cpu_max.max_usec;
+            self.max_period_usec = cpu_max.period_usec;
+            // The quota in the unit of usage_pct, e.g. 200% for 2 CPUs
+            let max_pct = match (cpu_max.max_usec, cpu_max.period_usec) {
+                (Some(max), Some(period)) if max > 0 && period > 0 => {
+                    Some(max as f64 * 100.0 / period as f64)
+                }
+                _ => None,
+            };
+            if let (Some(usage_pct), Some(max_pct)) = (self.usage_pct, max_pct) {
+                self.usage_pct_of_max = Some(usage_pct * 100.0 / max_pct);
+            }
+        }
+    }
+}
+
+/// `value` as a percentage of `limit`, unless there is no limit, i.e. it is
+/// -1 for max
+fn pct_of_limit(value: Option<f64>, limit: Option<i64>) -> Option<f64> {
+    match (value, limit) {
+        (Some(value), Some(limit)) if limit > 0 => Some(value * 100.0 / limit as f64),
+        _ => None,
+    }
 }
 
 #[derive(
@@ -330,6 +442,18 @@ pub struct CgroupIoModel {
     pub dbytes_per_sec: Option<f64>,
     pub dios_per_sec: Option<f64>,
     pub rwbytes_per_sec: Option<f64>,
+    pub weight: Option<u32>,
+    /// Limits of io.max, -1 for no limit
+    pub rbps_max: Option<i64>,
+    pub wbps_max: Option<i64>,
+    pub riops_max: Option<i64>,
+    pub wiops_max: Option<i64>,
+    /// rbytes_per_sec as a percentage of rbps_max. For the total of all
+    /// devices, the highest percentage of any device.
+    pub rbytes_pct_of_max: Option<f64>,
+    /// wbytes_per_sec as a percentage of wbps_max. For the total of all
+    /// devices, the highest percentage of any device.
+    pub wbytes_pct_of_max: Option<f64>,
 }
 
 impl CgroupIoModel {
@@ -345,6 +469,25 @@ impl CgroupIoModel {
             dbytes_per_sec: count_per_sec!(begin.dbytes, end.dbytes, delta),
             dios_per_sec: count_per_sec!(begin.dios, end.dios, delta),
             rwbytes_per_sec,
+            ..Default::default()
+        }
+    }
+
+    /// Add the io.max and io.weight limits of `sample` for `device_name`
+    fn set_limits(&mut self, sample: &CgroupSample, device_name: &str) {
+        self.weight = sample.io_weight.as_ref().and_then(|weights| {
+            weights
+                .get(device_name)
+                .or_else(|| weights.get("default"))
+                .cloned()
+        });
+        if let Some(io_max) = sample.io_max.as_ref().and_then(|m| m.get(device_name)) {
+            self.rbps_max = io_max.rbps;
+            self.wbps_max = io_max.wbps;
+            self.riops_max = io_max.riops;
+            self.wiops_max = io_max.wiops;
+            self.rbytes_pct_of_max = pct_of_limit(self.rbytes_per_sec, io_max.rbps);
+            self.wbytes_pct_of_max = pct_of_limit(self.wbytes_per_sec, io_max.wbps);
         }
     }
 
@@ -359,6 +502,7 @@ impl CgroupIoModel {
             dbytes_per_sec: Some(0.0),
             dios_per_sec: Some(0.0),
             rwbytes_per_sec: Some(0.0),
+            ..Default::default()
         }
     }
 }
@@ -375,6 +519,14 @@ impl std::ops::Add<&CgroupIoModel> for CgroupIoModel {
             dbytes_per_sec: opt_add(self.dbytes_per_sec, other.dbytes_per_sec),
             dios_per_sec: opt_add(self.dios_per_sec, other.dios_per_sec),
             rwbytes_per_sec: opt_add(self.rwbytes_per_sec, other.rwbytes_per_sec),
+            // Limits are per device
+            weight: None,
+            rbps_max: None,
+            wbps_max: None,
+            riops_max: None,
+            wiops_max: None,
+            rbytes_pct_of_max: opt_max(self.rbytes_pct_of_max, other.rbytes_pct_of_max),
+            wbytes_pct_of_max: opt_max(self.wbytes_pct_of_max, other.wbytes_pct_of_max),
         }
     }
 }
@@ -408,13 +560,23 @@ pub struct CgroupMemoryModel {
     pub unevictable: Option<u64>,
     pub slab_reclaimable: Option<u64>,
     pub slab_unreclaimable: Option<u64>,
+    pub percpu: Option<u64>,
+    pub sec_pagetables: Option<u64>,
+    pub zswap: Option<u64>,
+    pub zswapped: Option<u64>,
+    pub file_thp: Option<u64>,
+    pub shmem_thp: Option<u64>,
     pub pgfault: Option<u64>,
     pub pgmajfault: Option<u64>,
     pub workingset_refault: Option<u64>,
+    pub workingset_refault_anon: Option<u64>,
+    pub workingset_refault_file: Option<u64>,
     pub workingset_activate: Option<u64>,
     pub workingset_nodereclaim: Option<u64>,
     pub pgrefill: Option<u64>,
     pub pgscan: Option<u64>,
+    pub pgscan_direct: Option<u64>,
+    pub pgscan_kswapd: Option<u64>,
     pub pgsteal: Option<u64>,
     pub pgactivate: Option<u64>,
     pub pgdeactivate: Option<u64>,
@@ -423,11 +585,23 @@ pub struct CgroupMemoryModel {
     pub thp_fault_alloc: Option<u64>,
     pub thp_collapse_alloc: Option<u64>,
     pub memory_high: Option<i64>,
+    pub memory_max: Option<i64>,
+    pub memory_low: Option<i64>,
+    pub memory_min: Option<i64>,
+    pub memory_swap_max: Option<i64>,
+    /// total as a percentage of memory_max
+    pub total_pct_of_max: Option<f64>,
+    /// swap as a percentage of memory_swap_max
+    pub swap_pct_of_max: Option<f64>,
     pub events_low: Option<u64>,
     pub events_high: Option<u64>,
     pub events_max: Option<u64>,
     pub events_oom: Option<u64>,
     pub events_oom_kill: Option<u64>,
+    /// memory.stat keys not known to this version, e.g. ones added by newer
+    /// kernels, with their raw values
+    #[queriable(subquery)]
+    pub other: Option<CgroupMemoryOtherModel>,
 }
 
 impl std::ops::Add for CgroupMemoryModel {
@@ -454,9 +628,23 @@ impl std::ops::Add for CgroupMemoryModel {
             unevictable: opt_add(self.unevictable, other.unevictable),
             slab_reclaimable: opt_add(self.slab_reclaimable, other.slab_reclaimable),
             slab_unreclaimable: opt_add(self.slab_unreclaimable, other.slab_unreclaimable),
+            percpu: opt_add(self.percpu, other.percpu),
+            sec_pagetables: opt_add(self.sec_pagetables, other.sec_pagetables),
+            zswap: opt_add(self.zswap, other.zswap),
+            zswapped: opt_add(self.zswapped, other.zswapped),
+            file_thp: opt_add(self.file_thp, other.file_thp),
+            shmem_thp: opt_add(self.shmem_thp, other.shmem_thp),
             pgfault: opt_add(self.pgfault, other.pgfault),
             pgmajfault: opt_add(self.pgmajfault, other.pgmajfault),
             workingset_refault: opt_add(self.workingset_refault, other.workingset_refault),
+            workingset_refault_anon: opt_add(
+                self.workingset_refault_anon,
+                other.workingset_refault_anon,
+            ),
+            workingset_refault_file: opt_add(
+                self.workingset_refault_file,
+                other.workingset_refault_file,
+            ),
             workingset_activate: opt_add(self.workingset_activate, other.workingset_activate),
             workingset_nodereclaim: opt_add(
                 self.workingset_nodereclaim,
@@ -464,6 +652,8 @@ impl std::ops::Add for CgroupMemoryModel {
             ),
             pgrefill: opt_add(self.pgrefill, other.pgrefill),
             pgscan: opt_add(self.pgscan, other.pgscan),
+            pgscan_direct: opt_add(self.pgscan_direct, other.pgscan_direct),
+            pgscan_kswapd: opt_add(self.pgscan_kswapd, other.pgscan_kswapd),
             pgsteal: opt_add(self.pgsteal, other.pgsteal),
             pgactivate: opt_add(self.pgactivate, other.pgactivate),
             pgdeactivate: opt_add(self.pgdeactivate, other.pgdeactivate),
@@ -472,11 +662,18 @@ impl std::ops::Add for CgroupMemoryModel {
             thp_fault_alloc: opt_add(self.thp_fault_alloc, other.thp_fault_alloc),
             thp_collapse_alloc: opt_add(self.thp_collapse_alloc, other.thp_collapse_alloc),
             memory_high: None,
+            memory_max: None,
+            memory_low: None,
+            memory_min: None,
+            memory_swap_max: None,
+            total_pct_of_max: None,
+            swap_pct_of_max: None,
             events_low: opt_add(self.events_low, other.events_low),
             events_high: opt_add(self.events_high, other.events_high),
             events_max: opt_add(self.events_max, other.events_max),
             events_oom: opt_add(self.events_oom, other.events_oom),
             events_oom_kill: opt_add(self.events_oom_kill, other.events_oom_kill),
+            other: opt_add(self.other, other.other),
         }
     }
 }
@@ -490,8 +687,14 @@ impl CgroupMemoryModel {
             total: sample.memory_current.map(|v| v as u64),
             swap: sample.memory_swap_current.map(|v| v as u64),
             memory_high: sample.memory_high,
+            memory_max: sample.memory_max,
+            memory_low: sample.memory_low,
+            memory_min: sample.memory_min,
+            memory_swap_max: sample.memory_swap_max,
             ..Default::default()
         };
+        model.total_pct_of_max = pct_of_limit(model.total.map(|v| v as f64), model.memory_max);
+        model.swap_pct_of_max = pct_of_limit(model.swap.map(|v| v as f64), model.memory_swap_max);
         if let Some(events) = &sample.memory_events {
             model.events_low = events.low.map(|v| v as u64);
             model.events_high = events.high.map(|v| v as u64);
@@ -517,6 +720,15 @@ impl CgroupMemoryModel {
             model.unevictable = stat.unevictable.map(|v| v as u64);
             model.slab_reclaimable = stat.slab_reclaimable.map(|v| v as u64);
             model.slab_unreclaimable = stat.slab_unreclaimable.map(|v| v as u64);
+            model.percpu = stat.percpu.map(|v| v as u64);
+            model.sec_pagetables = stat.sec_pagetables.map(|v| v as u64);
+            model.zswap = stat.zswap.map(|v| v as u64);
+            model.zswapped = stat.zswapped.map(|v| v as u64);
+            model.file_thp = stat.file_thp.map(|v| v as u64);
+            model.shmem_thp = stat.shmem_thp.map(|v| v as u64);
+            if !stat.other.is_empty() {
+                model.other = Some(CgroupMemoryOtherModel(stat.other.clone()));
+            }
 
             if let Some((
                 CgroupSample {
@@ -535,6 +747,18 @@ impl CgroupMemoryModel {
                     delta,
                     u64
                 );
+                model.workingset_refault_anon = count_per_sec!(
+                    last_stat.workingset_refault_anon,
+                    stat.workingset_refault_anon,
+                    delta,
+                    u64
+                );
+                model.workingset_refault_file = count_per_sec!(
+                    last_stat.workingset_refault_file,
+                    stat.workingset_refault_file,
+                    delta,
+                    u64
+                );
                 model.workingset_activate = count_per_sec!(
                     last_stat.workingset_activate,
                     stat.workingset_activate,
@@ -549,6 +773,10 @@ impl CgroupMemoryModel {
                 );
                 model.pgrefill = count_per_sec!(last_stat.pgrefill, stat.pgrefill, delta, u64);
                 model.pgscan = count_per_sec!(last_stat.pgscan, stat.pgscan, delta, u64);
+                model.pgscan_direct =
+                    count_per_sec!(last_stat.pgscan_direct, stat.pgscan_direct, delta, u64);
+                model.pgscan_kswapd =
+                    count_per_sec!(last_stat.pgscan_kswapd, stat.pgscan_kswapd, delta, u64);
                 model.pgsteal = count_per_sec!(last_stat.pgsteal, stat.pgsteal, delta, u64);
                 model.pgactivate =
                     count_per_sec!(last_stat.pgactivate, stat.pgactivate, delta, u64);
@@ -573,6 +801,82 @@ impl CgroupMemoryModel {
     }
 }
 
+/// The memory.stat breakdown of a single NUMA node
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct CgroupMemoryNumaModel {
+    pub total: Option<u64>,
+    pub anon: Option<u64>,
+    pub file: Option<u64>,
+    pub kernel_stack: Option<u64>,
+    pub pagetables: Option<u64>,
+    pub shmem: Option<u64>,
+    pub file_mapped: Option<u64>,
+    pub file_dirty: Option<u64>,
+    pub file_writeback: Option<u64>,
+    pub anon_thp: Option<u64>,
+    pub inactive_anon: Option<u64>,
+    pub active_anon: Option<u64>,
+    pub inactive_file: Option<u64>,
+    pub active_file: Option<u64>,
+    pub unevictable: Option<u64>,
+    pub slab_reclaimable: Option<u64>,
+    pub slab_unreclaimable: Option<u64>,
+}
+
+impl CgroupMemoryNumaModel {
+    fn new(stat: &cgroupfs::MemoryNumaStat) -> CgroupMemoryNumaModel {
+        CgroupMemoryNumaModel {
+            total: [
+                stat.file,
+                stat.kernel_stack,
+                stat.pagetables,
+                stat.slab_reclaimable,
+                stat.slab_unreclaimable,
+            ]
+            .into_iter()
+            .fold(stat.anon, opt_add),
+            anon: stat.anon,
+            file: stat.file,
+            kernel_stack: stat.kernel_stack,
+            pagetables: stat.pagetables,
+            shmem: stat.shmem,
+            file_mapped: stat.file_mapped,
+            file_dirty: stat.file_dirty,
+            file_writeback: stat.file_writeback,
+            anon_thp: stat.anon_thp,
+            inactive_anon: stat.inactive_anon,
+            active_anon: stat.active_anon,
+            inactive_file: stat.inactive_file,
+            active_file: stat.active_file,
+            unevictable: stat.unevictable,
+            slab_reclaimable: stat.slab_reclaimable,
+            slab_unreclaimable: stat.slab_unreclaimable,
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct CgroupHugetlbModel {
+    /// hugetlb.<size>.current in bytes
+    pub current: Option<u64>,
+}
+
 #[derive(
     Clone,
     Debug,
@@ -605,6 +909,157 @@ impl CgroupPressureModel {
     }
 }
 
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct CgroupPidsModel {
+    pub current: Option<u64>,
+    /// -1 if there is no limit
+    pub max: Option<i64>,
+    pub current_pct_of_max: Option<f64>,
+    /// Number of times forking failed because of the limit
+    pub events_max: Option<u64>,
+}
+
+impl CgroupPidsModel {
+    fn new(sample: &CgroupSample) -> CgroupPidsModel {
+        CgroupPidsModel {
+            current: sample.pids_current,
+            max: sample.pids_max,
+            current_pct_of_max: pct_of_limit(
+                sample.pids_current.map(|v| v as f64),
+                sample.pids_max,
+            ),
+            events_max: sample.pids_events.as_ref().and_then(|events| events.max),
+        }
+    }
+}
+
+/// Raw values of memory.stat keys by key. The keys depend on the kernel, so
+/// unlike other models this one has no fixed fields.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CgroupMemoryOtherModel(pub BTreeMap<String, u64>);
+
+impl std::ops::Add for CgroupMemoryOtherModel {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self::Output {
+        for (key, value) in other.0 {
+            *self.0.entry(key).or_default() += value;
+        }
+        self
+    }
+}
+
+/// Selects a value of CgroupMemoryOtherModel by its memory.stat key
+#[derive(Clone, Debug, PartialEq)]
+pub struct CgroupMemoryOtherModelFieldId {
+    /// None is only for listing variants and otherwise invalid.
+    pub key: Option<String>,
+}
+
+impl FieldId for CgroupMemoryOtherModelFieldId {
+    type Queriable = CgroupMemoryOtherModel;
+}
+
+impl EnumIter for CgroupMemoryOtherModelFieldId {
+    fn all_variant_iter() -> Box<dyn Iterator<Item = Self>> {
+        Box::new(std::iter::once(Self { key: None }))
+    }
+}
+
+impl std::string::ToString for CgroupMemoryOtherModelFieldId {
+    fn to_string(&self) -> String {
+        match &self.key {
+            Some(key) => key.clone(),
+            None => "<key>".to_owned(),
+        }
+    }
+}
+
+impl std::str::FromStr for CgroupMemoryOtherModelFieldId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Err(anyhow!("Missing memory.stat key"));
+        }
+        Ok(Self {
+            key: Some(s.to_owned()),
+        })
+    }
+}
+
+impl Queriable for CgroupMemoryOtherModel {
+    type FieldId = CgroupMemoryOtherModelFieldId;
+    fn query(&self, field_id: &Self::FieldId) -> Option<Field> {
+        self.0.get(field_id.key.as_ref()?).map(|v| Field::U64(*v))
+    }
+}
+
+/// Raw values of the extra cgroup files of the below config. The keys depend
+/// on the config, so unlike other models this one has no fixed fields.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CgroupExtraModel(pub BTreeMap<String, String>);
+
+/// Selects a value of CgroupExtraModel by its key. Keys usually contain dots,
+/// e.g. `memory.oom.group`, so the whole string is the key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CgroupExtraModelFieldId {
+    /// None is only for listing variants and otherwise invalid.
+    pub key: Option<String>,
+}
+
+impl FieldId for CgroupExtraModelFieldId {
+    type Queriable = CgroupExtraModel;
+}
+
+impl EnumIter for CgroupExtraModelFieldId {
+    fn all_variant_iter() -> Box<dyn Iterator<Item = Self>> {
+        Box::new(std::iter::once(Self { key: None }))
+    }
+}
+
+impl std::string::ToString for CgroupExtraModelFieldId {
+    fn to_string(&self) -> String {
+        match &self.key {
+            Some(key) => key.clone(),
+            None => "<key>".to_owned(),
+        }
+    }
+}
+
+impl std::str::FromStr for CgroupExtraModelFieldId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Err(anyhow!("Missing key of extra cgroup file"));
+        }
+        Ok(Self {
+            key: Some(s.to_owned()),
+        })
+    }
+}
+
+impl Queriable for CgroupExtraModel {
+    type FieldId = CgroupExtraModelFieldId;
+    /// Values are u64 if they parse as such, so they sort numerically
+    fn query(&self, field_id: &Self::FieldId) -> Option<Field> {
+        let value = self.0.get(field_id.key.as_ref()?)?;
+        Some(match value.parse::<u64>() {
+            Ok(v) => Field::U64(v),
+            Err(_) => Field::Str(value.clone()),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -691,4 +1146,134 @@ mod tests {
             Some(Field::F64(42.0))
         );
     }
+
+    #[test]
+    fn limits() {
+        let last = CgroupSample {
+            cpu_stat: Some(cgroupfs::CpuStat {
+                usage_usec: Some(0),
+                ..Default::default()
+            }),
+            io_stat: Some(BTreeMap::from([(
+                "8:16".to_owned(),
+                cgroupfs::IoStat {
+                    rbytes: Some(0),
+                    wbytes: Some(0),
+                    ..Default::default()
+                },
+            )])),
+            ..Default::default()
+        };
+        let sample = CgroupSample {
+            cpu_stat: Some(cgroupfs::CpuStat {
+                usage_usec: Some(500_000),
+                ..Default::default()
+            }),
+            io_stat: Some(BTreeMap::from([(
+                "8:16".to_owned(),
+                cgroupfs::IoStat {
+                    rbytes: Some(1 << 20),
+                    wbytes: Some(1 << 20),
+                    ..Default::default()
+                },
+            )])),
+            memory_current: Some(1 << 30),
+            memory_max: Some(4 << 30),
+            memory_swap_max: Some(-1),
+            cpu_max: Some(cgroupfs::CpuMax {
+                max_usec: Some(200_000),
+                period_usec: Some(100_000),
+            }),
+            cpu_weight: Some(100),
+            io_max: Some(BTreeMap::from([(
+                "8:16".to_owned(),
+                cgroupfs::IoMax {
+                    rbps: Some(2 << 20),
+                    wbps: Some(-1),
+                    riops: Some(-1),
+                    wiops: Some(-1),
+                },
+            )])),
+            io_weight: Some(BTreeMap::from([("default".to_owned(), 100)])),
+            pids_current: Some(10),
+            pids_max: Some(40),
+            pids_events: Some(cgroupfs::PidsEvents { max: Some(2) }),
+            ..Default::default()
+        };
+        let model = CgroupModel::new(
+            "<root>".to_owned(),
+            "".to_owned(),
+            0,
+            &sample,
+            Some((&last, Duration::from_secs(1))),
+        )
+        .data;
+
+        let cpu = model.cpu.unwrap();
+        assert_eq!(cpu.max_usec, Some(200_000));
+        assert_eq!(cpu.weight, Some(100));
+        // 50% of one CPU out of a quota of two
+        assert_eq!(cpu.usage_pct_of_max, Some(25.0));
+
+        let memory = model.memory.unwrap();
+        assert_eq!(memory.total_pct_of_max, Some(25.0));
+        // No limit
+        assert_eq!(memory.memory_swap_max, Some(-1));
+        assert_eq!(memory.swap_pct_of_max, None);
+
+        let io = &model.io.unwrap()["8:16"];
+        assert_eq!(io.weight, Some(100));
+        assert_eq!(io.rbytes_pct_of_max, Some(50.0));
+        assert_eq!(io.wbytes_pct_of_max, None);
+        let io_total = model.io_total.unwrap();
+        assert_eq!(io_total.weight, Some(100));
+        assert_eq!(io_total.rbps_max, None);
+        assert_eq!(io_total.rbytes_pct_of_max, Some(50.0));
+
+        let pids = model.pids.unwrap();
+        assert_eq!(pids.current_pct_of_max, Some(25.0));
+        assert_eq!(pids.events_max, Some(2));
+    }
+
+    #[test]
+    fn extra_files() {
+        let sample = CgroupSample {
+            extra_files: Some(BTreeMap::from([
+                ("memory.oom.group".to_owned(), "1".to_owned()),
+                ("io.latency.8:16.target".to_owned(), "max".to_owned()),
+            ])),
+            ..Default::default()
+        };
+        let model = CgroupModel::new("<root>".to_owned(), "".to_owned(), 0, &sample, None).data;
+        let query = |s: &str| model.query(&SingleCgroupModelFieldId::from_str(s).unwrap());
+        assert_eq!(query("extra.memory.oom.group"), Some(Field::U64(1)));
+        assert_eq!(
+            query("extra.io.latency.8:16.target"),
+            Some(Field::Str("max".to_owned()))
+        );
+        assert_eq!(query("extra.memory.oom"), None);
+        assert_eq!(
+            SingleCgroupModelFieldId::from_str("extra.memory.oom.group")
+                .unwrap()
+                .to_string(),
+            "extra.memory.oom.group"
+        );
+    }
+
+    #[test]
+    fn memory_stat_other() {
+        let sample = CgroupSample {
+            memory_stat: Some(cgroupfs::MemoryStat {
+                anon: Some(1),
+                other: BTreeMap::from([("pgpromote_success".to_owned(), 2)]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let model = CgroupModel::new("<root>".to_owned(), "".to_owned(), 0, &sample, None).data;
+        let query = |s: &str| model.query(&SingleCgroupModelFieldId::from_str(s).unwrap());
+        assert_eq!(query("mem.anon"), Some(Field::U64(1)));
+        assert_eq!(query("mem.other.pgpromote_success"), Some(Field::U64(2)));
+        assert_eq!(query("mem.other.pgdemote_kswapd"), None);
+    }
 }
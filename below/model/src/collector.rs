@@ -22,6 +22,8 @@ use std::path::{Path, PathBuf};
 /// Collects data samples and maintains the latest data
 pub struct Collector {
     cgroup_root: PathBuf,
+    extra_cgroup_files: Vec<cgroupfs::ExtraFile>,
+    collect_threads: bool,
     last: Option<(Sample, Instant)>,
     exit_data: Arc<Mutex<procfs::PidMap>>,
 }
@@ -40,11 +42,25 @@ impl Collector {
     ) -> Collector {
         Collector {
             cgroup_root,
+            extra_cgroup_files: Vec::new(),
+            collect_threads: false,
             last: None,
             exit_data,
         }
     }
 
+    /// Also read these cgroup files into every sample
+    pub fn with_extra_cgroup_files(mut self, extra_cgroup_files: Vec<cgroupfs::ExtraFile>) -> Self {
+        self.extra_cgroup_files = extra_cgroup_files;
+        self
+    }
+
+    /// Also read the threads of every process into every sample
+    pub fn with_threads(mut self, collect_threads: bool) -> Self {
+        self.collect_threads = collect_threads;
+        self
+    }
+
     /// Collect a new `Sample`, returning an updated Model
     pub fn update_model(&mut self, logger: &slog::Logger) -> Result<Model> {
         let now = Instant::now();
@@ -55,6 +71,8 @@ impl Collector {
             logger,
             false,
             &None,
+            &self.extra_cgroup_files,
+            self.collect_threads,
         )?;
         let last = self.last.replace((sample, now));
         let model = Model::new(
@@ -75,6 +93,15 @@ pub fn opt_add<T: std::ops::Add<T, Output = T>>(a: Option<T>, b: Option<T>) -> O
     }
 }
 
+/// The larger of both values, or the one that is set
+pub fn opt_max<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b > a { b } else { a }),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
 pub fn opt_multiply<S: Sized + std::ops::Mul<T, Output = S>, T: Sized>(
     a: Option<S>,
     b: Option<T>,
@@ -152,6 +179,8 @@ pub fn collect_sample(
     logger: &slog::Logger,
     disable_disk_stat: bool,
     cgroup_re: &Option<Regex>,
+    extra_cgroup_files: &[cgroupfs::ExtraFile],
+    collect_threads: bool,
 ) -> Result<Sample> {
     let mut reader = procfs::ProcReader::new();
 
@@ -167,13 +196,17 @@ pub fn collect_sample(
             collect_io_stat,
             logger,
             &cgroup_re,
+            extra_cgroup_files,
         )?,
         processes: merge_procfs_and_exit_data(
-            reader
-                .read_all_pids()?
-                .into_iter()
-                .map(|(k, v)| (k, v.into()))
-                .collect(),
+            if collect_threads {
+                reader.read_all_pids_with_threads()?
+            } else {
+                reader.read_all_pids()?
+            }
+            .into_iter()
+            .map(|(k, v)| (k, v.into()))
+            .collect(),
             exit_pidmap,
         ),
         netstats: match procfs::NetReader::new().and_then(|v| v.read_netstat()) {
@@ -220,6 +253,13 @@ pub fn collect_sample(
                 }
                 (true, _) => Default::default(),
             },
+            nodes: match procfs::NodeReader::new().read_node_meminfo() {
+                Ok(nodes) => Some(nodes),
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
         },
     })
 }
@@ -269,6 +309,7 @@ fn collect_cgroup_sample(
     collect_io_stat: bool,
     logger: &slog::Logger,
     cgroup_re: &Option<Regex>,
+    extra_cgroup_files: &[cgroupfs::ExtraFile],
 ) -> Result<CgroupSample> {
     let io_stat = if collect_io_stat {
         io_stat_wrap(reader.read_io_stat())?
@@ -300,25 +341,48 @@ fn collect_cgroup_sample(
                         }
                     })
                     .map(|child| {
-                        collect_cgroup_sample(&child, collect_io_stat, logger, cgroup_re).map(
-                            |child_sample| {
-                                (
-                                    child
-                                        .name()
-                                        .file_name()
-                                        .expect("Unexpected .. in cgroup path")
-                                        .to_string_lossy()
-                                        .to_string(),
-                                    child_sample,
-                                )
-                            },
+                        collect_cgroup_sample(
+                            &child,
+                            collect_io_stat,
+                            logger,
+                            cgroup_re,
+                            extra_cgroup_files,
                         )
+                        .map(|child_sample| {
+                            (
+                                child
+                                    .name()
+                                    .file_name()
+                                    .expect("Unexpected .. in cgroup path")
+                                    .to_string_lossy()
+                                    .to_string(),
+                                child_sample,
+                            )
+                        })
                     })
                     .collect::<Result<BTreeMap<String, CgroupSample>>>()
             })
             .transpose()?,
         memory_swap_current: wrap(reader.read_memory_swap_current().map(|v| v as i64))?,
         memory_high: reader.read_memory_high()?.map(Into::into),
+        memory_max: reader.read_memory_max()?,
+        memory_low: reader.read_memory_low()?,
+        memory_min: reader.read_memory_min()?,
+        memory_swap_max: reader.read_memory_swap_max()?,
+        cpu_max: wrap(reader.read_cpu_max())?,
+        cpu_weight: wrap(reader.read_cpu_weight())?,
+        io_max: wrap(reader.read_io_max())?,
+        io_weight: wrap(reader.read_io_weight())?,
+        pids_max: reader.read_pids_max()?,
+        pids_current: wrap(reader.read_pids_current())?,
+        pids_events: wrap(reader.read_pids_events())?,
+        cgroup_stat: wrap(reader.read_cgroup_stat())?,
+        cgroup_events: wrap(reader.read_cgroup_events())?,
+        cpuset_cpus_effective: wrap(reader.read_cpuset_cpus_effective())?,
+        cpuset_mems_effective: wrap(reader.read_cpuset_mems_effective())?,
+        memory_numa_stat: wrap(reader.read_memory_numa_stat())?,
+        hugetlb_current: wrap(reader.read_hugetlb_current())?,
+        extra_files: collect_extra_files(reader, extra_cgroup_files, logger),
         memory_events: wrap(reader.read_memory_events())?.map(Into::into),
         inode_number: match reader.read_inode_number() {
             Ok(st_ino) => Some(st_ino as i64),
@@ -330,6 +394,41 @@ fn collect_cgroup_sample(
     })
 }
 
+/// Names of the extra cgroup files that already failed to parse. A
+/// misconfigured entry fails the same way for every cgroup of every sample,
+/// so it is only logged the first time.
+static FAILED_EXTRA_FILES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Read the extra cgroup files of the below config. Files that fail to
+/// parse are logged and skipped rather than failing the whole sample.
+fn collect_extra_files(
+    reader: &cgroupfs::CgroupReader,
+    extra_cgroup_files: &[cgroupfs::ExtraFile],
+    logger: &slog::Logger,
+) -> Option<BTreeMap<String, String>> {
+    let mut extra_files = BTreeMap::new();
+    for extra_file in extra_cgroup_files {
+        match wrap(reader.read_extra_file(extra_file)) {
+            Ok(Some(values)) => extra_files.extend(values),
+            Ok(None) => {}
+            Err(e) => {
+                if FAILED_EXTRA_FILES
+                    .lock()
+                    .expect("tried to acquire poisoned lock")
+                    .insert(extra_file.name.clone())
+                {
+                    error!(logger, "{:#}", e);
+                }
+            }
+        }
+    }
+    if extra_files.is_empty() {
+        None
+    } else {
+        Some(extra_files)
+    }
+}
+
 macro_rules! usec_pct {
     ($a_opt:expr, $b_opt:expr, $delta:expr) => {{
         let mut ret = None;
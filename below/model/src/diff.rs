@@ -0,0 +1,304 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Changes of cgroups and processes between two points in time.
+//!
+//! A diff is computed from two models: the model of the first sample on its
+//! own, and the model of the last sample computed against the first one
+//! (instead of against the sample right before it). Rates of the latter are
+//! averages over the whole window, so multiplying them by its length gives
+//! how much cumulative counters grew. Memory is a gauge and compared between
+//! both samples instead.
+
+use super::*;
+
+/// What to sort diff rows by. Rows are sorted by the absolute value of the
+/// change, biggest first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffSortKey {
+    Cpu,
+    Mem,
+    Io,
+    Pressure,
+}
+
+impl FromStr for DiffSortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "cpu" => Ok(DiffSortKey::Cpu),
+            "mem" => Ok(DiffSortKey::Mem),
+            "io" => Ok(DiffSortKey::Io),
+            "pressure" => Ok(DiffSortKey::Pressure),
+            _ => Err(anyhow!(
+                "Unknown sort key: {}, expected one of cpu, mem, io or pressure",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CgroupDiff {
+    pub full_path: String,
+    /// CPU time used in the window in seconds
+    pub cpu_secs: Option<f64>,
+    /// Change of memory.current in bytes
+    pub mem_bytes: Option<i64>,
+    /// Change of anonymous memory in bytes
+    pub anon_bytes: Option<i64>,
+    /// Change of swap usage in bytes
+    pub swap_bytes: Option<i64>,
+    /// Bytes read and written in the window
+    pub io_bytes: Option<f64>,
+    /// Average share of time some tasks stalled on memory in the window
+    pub memory_some_pct: Option<f64>,
+    /// Average share of time some tasks stalled on IO in the window
+    pub io_some_pct: Option<f64>,
+    /// OOM kills in the window
+    pub oom_kills: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProcessDiff {
+    pub pid: i32,
+    pub comm: Option<String>,
+    pub cgroup: Option<String>,
+    /// Whether the process started within the window
+    pub new: bool,
+    /// CPU time used in the window in seconds
+    pub cpu_secs: Option<f64>,
+    /// Change of RSS in bytes
+    pub rss_bytes: Option<i64>,
+    /// Bytes read and written in the window
+    pub io_bytes: Option<f64>,
+    /// Major page faults in the window
+    pub major_faults: Option<f64>,
+}
+
+/// Changes of all cgroups and processes alive at the end of a window
+#[derive(Clone, Debug)]
+pub struct ModelDiff {
+    pub begin: SystemTime,
+    pub end: SystemTime,
+    pub cgroups: Vec<CgroupDiff>,
+    pub processes: Vec<ProcessDiff>,
+}
+
+fn gauge_diff(begin: Option<u64>, end: Option<u64>) -> Option<i64> {
+    Some(end? as i64 - begin.unwrap_or_default() as i64)
+}
+
+fn counter_diff(begin: Option<u64>, end: Option<u64>) -> Option<u64> {
+    end?.checked_sub(begin.unwrap_or_default())
+}
+
+fn flatten_cgroups<'a>(model: &'a CgroupModel, res: &mut Vec<&'a SingleCgroupModel>) {
+    res.push(&model.data);
+    for child in &model.children {
+        flatten_cgroups(child, res);
+    }
+}
+
+impl CgroupDiff {
+    fn new(begin: Option<&SingleCgroupModel>, window: &SingleCgroupModel, secs: f64) -> Self {
+        let begin_mem = begin.and_then(|begin| begin.memory.as_ref());
+        let window_mem = window.memory.as_ref();
+        let mem_diff = |f: fn(&CgroupMemoryModel) -> Option<u64>| {
+            gauge_diff(begin_mem.and_then(f), window_mem.and_then(f))
+        };
+        CgroupDiff {
+            full_path: if window.full_path.is_empty() {
+                "/".to_owned()
+            } else {
+                window.full_path.clone()
+            },
+            cpu_secs: window
+                .cpu
+                .as_ref()
+                .and_then(|cpu| cpu.usage_pct)
+                .map(|pct| pct / 100.0 * secs),
+            mem_bytes: mem_diff(|mem| mem.total),
+            anon_bytes: mem_diff(|mem| mem.anon),
+            swap_bytes: mem_diff(|mem| mem.swap),
+            io_bytes: window
+                .io_total
+                .as_ref()
+                .and_then(|io| io.rwbytes_per_sec)
+                .map(|rate| rate * secs),
+            memory_some_pct: window.pressure.as_ref().and_then(|p| p.memory_some_pct),
+            io_some_pct: window.pressure.as_ref().and_then(|p| p.io_some_pct),
+            oom_kills: counter_diff(
+                begin_mem.and_then(|mem| mem.events_oom_kill),
+                window_mem.and_then(|mem| mem.events_oom_kill),
+            ),
+        }
+    }
+
+    fn sort_value(&self, key: DiffSortKey) -> f64 {
+        match key {
+            DiffSortKey::Cpu => self.cpu_secs.unwrap_or_default(),
+            DiffSortKey::Mem => self.mem_bytes.unwrap_or_default() as f64,
+            DiffSortKey::Io => self.io_bytes.unwrap_or_default(),
+            DiffSortKey::Pressure => self.memory_some_pct.unwrap_or_default(),
+        }
+    }
+}
+
+impl ProcessDiff {
+    fn new(begin: Option<&SingleProcessModel>, window: &SingleProcessModel, secs: f64) -> Self {
+        let rss = |model: Option<&SingleProcessModel>| model?.mem.as_ref()?.rss_bytes;
+        ProcessDiff {
+            pid: window.pid.unwrap_or_default(),
+            comm: window.comm.clone(),
+            cgroup: window.cgroup.clone(),
+            new: begin.is_none(),
+            cpu_secs: window
+                .cpu
+                .as_ref()
+                .and_then(|cpu| cpu.usage_pct)
+                .map(|pct| pct / 100.0 * secs),
+            rss_bytes: gauge_diff(rss(begin), rss(Some(window))),
+            io_bytes: window
+                .io
+                .as_ref()
+                .and_then(|io| io.rwbytes_per_sec)
+                .map(|rate| rate * secs),
+            major_faults: window
+                .mem
+                .as_ref()
+                .and_then(|mem| mem.majorfaults_per_sec)
+                .map(|rate| rate * secs),
+        }
+    }
+
+    fn sort_value(&self, key: DiffSortKey) -> f64 {
+        match key {
+            DiffSortKey::Cpu => self.cpu_secs.unwrap_or_default(),
+            DiffSortKey::Mem => self.rss_bytes.unwrap_or_default() as f64,
+            DiffSortKey::Io => self.io_bytes.unwrap_or_default(),
+            DiffSortKey::Pressure => self.major_faults.unwrap_or_default(),
+        }
+    }
+}
+
+impl ModelDiff {
+    /// `begin` is the model of the first sample on its own, `window` the
+    /// model of the last sample computed against the first one.
+    pub fn new(begin: &Model, window: &Model) -> Self {
+        let secs = window.time_elapsed.as_secs_f64();
+
+        let mut begin_cgroups = Vec::new();
+        flatten_cgroups(&begin.cgroup, &mut begin_cgroups);
+        let begin_cgroups: BTreeMap<_, _> = begin_cgroups
+            .into_iter()
+            .map(|cgroup| (cgroup.full_path.as_str(), cgroup))
+            .collect();
+        let mut window_cgroups = Vec::new();
+        flatten_cgroups(&window.cgroup, &mut window_cgroups);
+        let cgroups = window_cgroups
+            .into_iter()
+            .map(|cgroup| {
+                CgroupDiff::new(
+                    begin_cgroups.get(cgroup.full_path.as_str()).copied(),
+                    cgroup,
+                    secs,
+                )
+            })
+            .collect();
+
+        let processes = window
+            .process
+            .processes
+            .iter()
+            .map(|(pid, process)| ProcessDiff::new(begin.process.processes.get(pid), process, secs))
+            .collect();
+
+        ModelDiff {
+            begin: begin.timestamp,
+            end: window.timestamp,
+            cgroups,
+            processes,
+        }
+    }
+
+    /// Sort cgroups and processes by the biggest change of `key`. Processes
+    /// have no pressure and are sorted by major faults instead.
+    pub fn sort(&mut self, key: DiffSortKey) {
+        let by_change = |a: f64, b: f64| b.abs().total_cmp(&a.abs());
+        self.cgroups
+            .sort_by(|a, b| by_change(a.sort_value(key), b.sort_value(key)));
+        self.processes
+            .sort_by(|a, b| by_change(a.sort_value(key), b.sort_value(key)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cgroup_sample(memory_current: i64, usage_usec: u64) -> CgroupSample {
+        CgroupSample {
+            memory_current: Some(memory_current),
+            cpu_stat: Some(cgroupfs::CpuStat {
+                usage_usec: Some(usage_usec),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_across_window() {
+        let mut first = Sample::default();
+        first.cgroup = cgroup_sample(100 << 20, 0);
+        first.cgroup.children = Some(BTreeMap::from([
+            ("a".to_owned(), cgroup_sample(10 << 20, 0)),
+            ("b".to_owned(), cgroup_sample(50 << 20, 0)),
+        ]));
+        let mut last = Sample::default();
+        last.cgroup = cgroup_sample(200 << 20, 120_000_000);
+        last.cgroup.children = Some(BTreeMap::from([
+            ("a".to_owned(), cgroup_sample(90 << 20, 60_000_000)),
+            ("b".to_owned(), cgroup_sample(40 << 20, 0)),
+            ("c".to_owned(), cgroup_sample(5 << 20, 0)),
+        ]));
+
+        let begin_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let end_time = begin_time + Duration::from_secs(600);
+        let begin = Model::new(begin_time, &first, None);
+        let window = Model::new(end_time, &last, Some((&first, Duration::from_secs(600))));
+        let mut diff = ModelDiff::new(&begin, &window);
+        diff.sort(DiffSortKey::Mem);
+
+        assert_eq!(
+            diff.cgroups
+                .iter()
+                .map(|cgroup| (cgroup.full_path.as_str(), cgroup.mem_bytes))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/a", Some(80 << 20)),
+                ("/", Some(75 << 20)),
+                ("/b", Some(-10 << 20)),
+                ("/c", Some(5 << 20)),
+            ]
+        );
+        assert_eq!(diff.cgroups[0].cpu_secs.map(|v| v.round()), Some(60.0));
+        assert_eq!(diff.cgroups[3].cpu_secs, Some(0.0));
+
+        diff.sort(DiffSortKey::Cpu);
+        assert_eq!(diff.cgroups[0].full_path, "/");
+    }
+}
@@ -122,15 +122,48 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "system.disks.<key>.time_spend_discard_ms",
     "system.disks.<key>.major",
     "system.disks.<key>.minor",
+    "system.nodes.<key>.idx",
+    "system.nodes.<key>.total",
+    "system.nodes.<key>.free",
+    "system.nodes.<key>.used",
+    "system.nodes.<key>.anon",
+    "system.nodes.<key>.file",
+    "system.nodes.<key>.active_anon",
+    "system.nodes.<key>.inactive_anon",
+    "system.nodes.<key>.active_file",
+    "system.nodes.<key>.inactive_file",
+    "system.nodes.<key>.unevictable",
+    "system.nodes.<key>.mlocked",
+    "system.nodes.<key>.dirty",
+    "system.nodes.<key>.writeback",
+    "system.nodes.<key>.mapped",
+    "system.nodes.<key>.shmem",
+    "system.nodes.<key>.kernel_stack",
+    "system.nodes.<key>.page_tables",
+    "system.nodes.<key>.slab_reclaimable",
+    "system.nodes.<key>.slab_unreclaimable",
+    "system.nodes.<key>.anon_huge_pages_bytes",
+    "system.nodes.<key>.total_huge_pages",
+    "system.nodes.<key>.free_huge_pages",
     "cgroup.[path:/<cgroup_path>/.]name",
     "cgroup.[path:/<cgroup_path>/.]full_path",
     "cgroup.[path:/<cgroup_path>/.]inode_number",
+    "cgroup.[path:/<cgroup_path>/.]nr_descendants",
+    "cgroup.[path:/<cgroup_path>/.]nr_dying_descendants",
+    "cgroup.[path:/<cgroup_path>/.]populated",
+    "cgroup.[path:/<cgroup_path>/.]frozen",
+    "cgroup.[path:/<cgroup_path>/.]cpuset_cpus",
+    "cgroup.[path:/<cgroup_path>/.]cpuset_mems",
     "cgroup.[path:/<cgroup_path>/.]cpu.usage_pct",
     "cgroup.[path:/<cgroup_path>/.]cpu.user_pct",
     "cgroup.[path:/<cgroup_path>/.]cpu.system_pct",
     "cgroup.[path:/<cgroup_path>/.]cpu.nr_periods_per_sec",
     "cgroup.[path:/<cgroup_path>/.]cpu.nr_throttled_per_sec",
     "cgroup.[path:/<cgroup_path>/.]cpu.throttled_pct",
+    "cgroup.[path:/<cgroup_path>/.]cpu.weight",
+    "cgroup.[path:/<cgroup_path>/.]cpu.max_usec",
+    "cgroup.[path:/<cgroup_path>/.]cpu.max_period_usec",
+    "cgroup.[path:/<cgroup_path>/.]cpu.usage_pct_of_max",
     "cgroup.[path:/<cgroup_path>/.]mem.total",
     "cgroup.[path:/<cgroup_path>/.]mem.swap",
     "cgroup.[path:/<cgroup_path>/.]mem.anon",
@@ -150,13 +183,23 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "cgroup.[path:/<cgroup_path>/.]mem.unevictable",
     "cgroup.[path:/<cgroup_path>/.]mem.slab_reclaimable",
     "cgroup.[path:/<cgroup_path>/.]mem.slab_unreclaimable",
+    "cgroup.[path:/<cgroup_path>/.]mem.percpu",
+    "cgroup.[path:/<cgroup_path>/.]mem.sec_pagetables",
+    "cgroup.[path:/<cgroup_path>/.]mem.zswap",
+    "cgroup.[path:/<cgroup_path>/.]mem.zswapped",
+    "cgroup.[path:/<cgroup_path>/.]mem.file_thp",
+    "cgroup.[path:/<cgroup_path>/.]mem.shmem_thp",
     "cgroup.[path:/<cgroup_path>/.]mem.pgfault",
     "cgroup.[path:/<cgroup_path>/.]mem.pgmajfault",
     "cgroup.[path:/<cgroup_path>/.]mem.workingset_refault",
+    "cgroup.[path:/<cgroup_path>/.]mem.workingset_refault_anon",
+    "cgroup.[path:/<cgroup_path>/.]mem.workingset_refault_file",
     "cgroup.[path:/<cgroup_path>/.]mem.workingset_activate",
     "cgroup.[path:/<cgroup_path>/.]mem.workingset_nodereclaim",
     "cgroup.[path:/<cgroup_path>/.]mem.pgrefill",
     "cgroup.[path:/<cgroup_path>/.]mem.pgscan",
+    "cgroup.[path:/<cgroup_path>/.]mem.pgscan_direct",
+    "cgroup.[path:/<cgroup_path>/.]mem.pgscan_kswapd",
     "cgroup.[path:/<cgroup_path>/.]mem.pgsteal",
     "cgroup.[path:/<cgroup_path>/.]mem.pgactivate",
     "cgroup.[path:/<cgroup_path>/.]mem.pgdeactivate",
@@ -165,11 +208,36 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "cgroup.[path:/<cgroup_path>/.]mem.thp_fault_alloc",
     "cgroup.[path:/<cgroup_path>/.]mem.thp_collapse_alloc",
     "cgroup.[path:/<cgroup_path>/.]mem.memory_high",
+    "cgroup.[path:/<cgroup_path>/.]mem.memory_max",
+    "cgroup.[path:/<cgroup_path>/.]mem.memory_low",
+    "cgroup.[path:/<cgroup_path>/.]mem.memory_min",
+    "cgroup.[path:/<cgroup_path>/.]mem.memory_swap_max",
+    "cgroup.[path:/<cgroup_path>/.]mem.total_pct_of_max",
+    "cgroup.[path:/<cgroup_path>/.]mem.swap_pct_of_max",
     "cgroup.[path:/<cgroup_path>/.]mem.events_low",
     "cgroup.[path:/<cgroup_path>/.]mem.events_high",
     "cgroup.[path:/<cgroup_path>/.]mem.events_max",
     "cgroup.[path:/<cgroup_path>/.]mem.events_oom",
     "cgroup.[path:/<cgroup_path>/.]mem.events_oom_kill",
+    "cgroup.[path:/<cgroup_path>/.]mem.other.<key>",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.total",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.anon",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.file",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.kernel_stack",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.pagetables",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.shmem",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.file_mapped",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.file_dirty",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.file_writeback",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.anon_thp",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.inactive_anon",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.active_anon",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.inactive_file",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.active_file",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.unevictable",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.slab_reclaimable",
+    "cgroup.[path:/<cgroup_path>/.]mem_numa.<key>.slab_unreclaimable",
+    "cgroup.[path:/<cgroup_path>/.]hugetlb.<key>.current",
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.rbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.wbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.rios_per_sec",
@@ -177,6 +245,13 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.dbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.dios_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.rwbytes_per_sec",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.weight",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.rbps_max",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.wbps_max",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.riops_max",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.wiops_max",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.rbytes_pct_of_max",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.wbytes_pct_of_max",
     "cgroup.[path:/<cgroup_path>/.]io.rbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.wbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.rios_per_sec",
@@ -184,12 +259,25 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "cgroup.[path:/<cgroup_path>/.]io.dbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.dios_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.rwbytes_per_sec",
+    "cgroup.[path:/<cgroup_path>/.]io.weight",
+    "cgroup.[path:/<cgroup_path>/.]io.rbps_max",
+    "cgroup.[path:/<cgroup_path>/.]io.wbps_max",
+    "cgroup.[path:/<cgroup_path>/.]io.riops_max",
+    "cgroup.[path:/<cgroup_path>/.]io.wiops_max",
+    "cgroup.[path:/<cgroup_path>/.]io.rbytes_pct_of_max",
+    "cgroup.[path:/<cgroup_path>/.]io.wbytes_pct_of_max",
     "cgroup.[path:/<cgroup_path>/.]pressure.cpu_some_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.io_some_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.io_full_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.memory_some_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.memory_full_pct",
+    "cgroup.[path:/<cgroup_path>/.]pids.current",
+    "cgroup.[path:/<cgroup_path>/.]pids.max",
+    "cgroup.[path:/<cgroup_path>/.]pids.current_pct_of_max",
+    "cgroup.[path:/<cgroup_path>/.]pids.events_max",
+    "cgroup.[path:/<cgroup_path>/.]extra.<key>",
     "process.processes.<key>.pid",
+    "process.processes.<key>.tgid",
     "process.processes.<key>.ppid",
     "process.processes.<key>.comm",
     "process.processes.<key>.state",
@@ -20,9 +20,11 @@ use std::time::{Duration, Instant, SystemTime};
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 
+pub mod alert;
 #[macro_use]
 pub mod collector;
 pub mod cgroup;
+pub mod diff;
 #[cfg(test)]
 mod field_ids;
 pub mod network;
@@ -31,8 +33,10 @@ pub mod sample;
 mod sample_model;
 pub mod system;
 
+pub use alert::*;
 pub use cgroup::*;
 pub use collector::*;
+pub use diff::*;
 pub use network::*;
 pub use process::*;
 pub use sample::*;
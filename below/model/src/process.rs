@@ -57,6 +57,8 @@ impl ProcessModel {
 #[derive(Default, Clone, Serialize, Deserialize, below_derive::Queriable)]
 pub struct SingleProcessModel {
     pub pid: Option<i32>,
+    /// Pid of the process a thread belongs to, the pid itself for processes
+    pub tgid: Option<i32>,
     pub ppid: Option<i32>,
     pub comm: Option<String>,
     pub state: Option<procfs::PidState>,
@@ -70,6 +72,9 @@ pub struct SingleProcessModel {
     pub cpu: Option<ProcessCpuModel>,
     pub cmdline: Option<String>,
     pub exe_path: Option<String>,
+    /// Threads of the process by tid, only if they were collected
+    #[queriable(ignore)]
+    pub threads: Option<BTreeMap<i32, SingleProcessModel>>,
 }
 
 impl SingleProcessModel {
@@ -79,6 +84,7 @@ impl SingleProcessModel {
     ) -> SingleProcessModel {
         SingleProcessModel {
             pid: sample.stat.pid,
+            tgid: sample.stat.pid,
             ppid: sample.stat.ppid,
             comm: sample.stat.comm.clone(),
             state: sample.stat.state.clone(),
@@ -93,6 +99,55 @@ impl SingleProcessModel {
                 Some("?".into())
             },
             exe_path: sample.exe_path.clone(),
+            threads: sample.threads.as_ref().map(|threads| {
+                threads
+                    .iter()
+                    .map(|(tid, threadinfo)| {
+                        let last = last.and_then(|(l, d)| {
+                            l.threads
+                                .as_ref()
+                                .and_then(|threads| threads.get(tid))
+                                .map(|l| (l, d))
+                        });
+                        (
+                            *tid,
+                            SingleProcessModel::new_thread(threadinfo, last, sample),
+                        )
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    /// Threads share the cgroup, cmdline and memory of their process, so only
+    /// cpu and io are per thread
+    fn new_thread(
+        sample: &procfs::ThreadInfo,
+        last: Option<(&procfs::ThreadInfo, Duration)>,
+        process: &procfs::PidInfo,
+    ) -> SingleProcessModel {
+        SingleProcessModel {
+            pid: sample.stat.pid,
+            tgid: process.stat.pid,
+            ppid: sample.stat.ppid,
+            comm: sample.stat.comm.clone(),
+            state: sample.stat.state.clone(),
+            uptime_secs: sample.stat.running_secs.map(|s| s as u64),
+            cgroup: Some(process.cgroup.clone()),
+            io: last.map(|(l, d)| ProcessIoModel::new(&l.io, &sample.io, d)),
+            mem: None,
+            cpu: last.map(|(l, d)| ProcessCpuModel {
+                // Number of threads of the process
+                num_threads: None,
+                ..ProcessCpuModel::new(&l.stat, &sample.stat, d)
+            }),
+            cmdline: if let Some(cmd_vec) = process.cmdline_vec.as_ref() {
+                Some(cmd_vec.join(" "))
+            } else {
+                Some("?".into())
+            },
+            exe_path: process.exe_path.clone(),
+            threads: None,
         }
     }
 
@@ -101,6 +156,7 @@ impl SingleProcessModel {
     pub fn fold(left: &SingleProcessModel, right: &SingleProcessModel) -> SingleProcessModel {
         SingleProcessModel {
             pid: None,
+            tgid: None,
             ppid: None,
             comm: None,
             state: None,
@@ -112,6 +168,7 @@ impl SingleProcessModel {
             cpu: fold_optionals!(&left.cpu, &right.cpu, ProcessCpuModel::fold),
             cmdline: None,
             exe_path: None,
+            threads: None,
         }
     }
 }
@@ -260,4 +317,82 @@ mod test {
             Some(Field::Str("systemd".to_owned()))
         );
     }
+
+    #[test]
+    fn thread_model() {
+        let pidinfo = |user_usecs, rbytes| procfs::PidInfo {
+            stat: procfs::PidStat {
+                pid: Some(100),
+                comm: Some("java".to_owned()),
+                num_threads: Some(2),
+                user_usecs: Some(user_usecs),
+                ..Default::default()
+            },
+            cgroup: "/system.slice/app.service".to_owned(),
+            threads: Some(
+                vec![
+                    (
+                        100,
+                        procfs::ThreadInfo {
+                            stat: procfs::PidStat {
+                                pid: Some(100),
+                                comm: Some("java".to_owned()),
+                                num_threads: Some(2),
+                                user_usecs: Some(0),
+                                ..Default::default()
+                            },
+                            io: Default::default(),
+                        },
+                    ),
+                    (
+                        101,
+                        procfs::ThreadInfo {
+                            stat: procfs::PidStat {
+                                pid: Some(101),
+                                comm: Some("GC Thread#0".to_owned()),
+                                num_threads: Some(2),
+                                user_usecs: Some(user_usecs),
+                                ..Default::default()
+                            },
+                            io: procfs::PidIo {
+                                rbytes: Some(rbytes),
+                                wbytes: Some(0),
+                            },
+                        },
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let last = vec![(100, pidinfo(0, 0))].into_iter().collect();
+        let sample = vec![(100, pidinfo(500_000, 4096))].into_iter().collect();
+        let model = ProcessModel::new(&sample, Some((&last, Duration::from_secs(1))));
+
+        let threads = model.processes[&100]
+            .threads
+            .as_ref()
+            .expect("threads missing");
+        assert_eq!(threads.len(), 2);
+        let thread = &threads[&101];
+        assert_eq!(thread.pid, Some(101));
+        assert_eq!(thread.tgid, Some(100));
+        assert_eq!(thread.comm.as_deref(), Some("GC Thread#0"));
+        assert_eq!(thread.cgroup.as_deref(), Some("/system.slice/app.service"));
+        assert_eq!(
+            thread.query(&SingleProcessModelFieldId::from_str("cpu.user_pct").unwrap()),
+            Some(Field::F64(50.0))
+        );
+        assert_eq!(
+            thread.query(&SingleProcessModelFieldId::from_str("cpu.num_threads").unwrap()),
+            None
+        );
+        assert_eq!(
+            thread.query(&SingleProcessModelFieldId::from_str("io.rbytes_per_sec").unwrap()),
+            Some(Field::F64(4096.0))
+        );
+        assert!(thread.mem.is_none());
+        assert!(thread.threads.is_none());
+    }
 }
@@ -32,6 +32,26 @@ pub struct CgroupSample {
     pub children: Option<BTreeMap<String, CgroupSample>>,
     pub memory_swap_current: Option<i64>,
     pub memory_high: Option<i64>,
+    pub memory_max: Option<i64>,
+    pub memory_low: Option<i64>,
+    pub memory_min: Option<i64>,
+    pub memory_swap_max: Option<i64>,
+    pub cpu_max: Option<cgroupfs::CpuMax>,
+    pub cpu_weight: Option<u32>,
+    pub io_max: Option<BTreeMap<String, cgroupfs::IoMax>>,
+    pub io_weight: Option<BTreeMap<String, u32>>,
+    pub pids_max: Option<i64>,
+    pub pids_current: Option<u64>,
+    pub pids_events: Option<cgroupfs::PidsEvents>,
+    pub cgroup_stat: Option<cgroupfs::CgroupStat>,
+    pub cgroup_events: Option<cgroupfs::CgroupEvents>,
+    pub cpuset_cpus_effective: Option<cgroupfs::Cpuset>,
+    pub cpuset_mems_effective: Option<cgroupfs::Cpuset>,
+    pub memory_numa_stat: Option<BTreeMap<u32, cgroupfs::MemoryNumaStat>>,
+    pub hugetlb_current: Option<BTreeMap<String, u64>>,
+    /// Values of the extra cgroup files of the below config, see
+    /// `cgroupfs::CgroupReader::read_extra_file` for the keys
+    pub extra_files: Option<BTreeMap<String, String>>,
     pub memory_events: Option<cgroupfs::MemoryEvents>,
     pub inode_number: Option<i64>,
 }
@@ -45,4 +65,5 @@ pub struct SystemSample {
     pub disks: procfs::DiskMap,
     pub kernel_version: Option<String>,
     pub os_release: Option<String>,
+    pub nodes: Option<procfs::NodeMemInfoMap>,
 }
@@ -174,6 +174,16 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 "major": 20,
                 "minor": 0
             }
+        },
+        "nodes": {
+            "0": {
+                "idx": 0,
+                "total": 10000000000,
+                "free": 4000000000,
+                "used": 6000000000,
+                "anon": 3000000000,
+                "file": 2000000000
+            }
         }
     },
     "cgroup": {
@@ -520,6 +530,7 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
         "processes": {
             "1": {
                 "pid": 1,
+                "tgid": 1,
                 "ppid": 0,
                 "comm": "systemd",
                 "state": "Running",
@@ -40,6 +40,9 @@ pub struct SystemModel {
     pub vm: VmModel,
     #[queriable(subquery)]
     pub disks: BTreeMap<String, SingleDiskModel>,
+    #[queriable(subquery)]
+    #[serde(default)]
+    pub nodes: BTreeMap<u32, SingleNodeModel>,
 }
 
 impl SystemModel {
@@ -87,6 +90,13 @@ impl SystemModel {
             );
         });
 
+        let nodes = sample
+            .nodes
+            .iter()
+            .flatten()
+            .map(|(idx, meminfo)| (*idx, SingleNodeModel::new(*idx, meminfo)))
+            .collect();
+
         SystemModel {
             hostname: sample.hostname.clone(),
             kernel_version: sample.kernel_version.clone(),
@@ -97,6 +107,7 @@ impl SystemModel {
             mem,
             vm,
             disks,
+            nodes,
         }
     }
 }
@@ -316,6 +327,72 @@ impl MemoryModel {
     }
 }
 
+/// Memory of a single NUMA node
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct SingleNodeModel {
+    pub idx: u32,
+    pub total: Option<u64>,
+    pub free: Option<u64>,
+    pub used: Option<u64>,
+    pub anon: Option<u64>,
+    pub file: Option<u64>,
+    pub active_anon: Option<u64>,
+    pub inactive_anon: Option<u64>,
+    pub active_file: Option<u64>,
+    pub inactive_file: Option<u64>,
+    pub unevictable: Option<u64>,
+    pub mlocked: Option<u64>,
+    pub dirty: Option<u64>,
+    pub writeback: Option<u64>,
+    pub mapped: Option<u64>,
+    pub shmem: Option<u64>,
+    pub kernel_stack: Option<u64>,
+    pub page_tables: Option<u64>,
+    pub slab_reclaimable: Option<u64>,
+    pub slab_unreclaimable: Option<u64>,
+    pub anon_huge_pages_bytes: Option<u64>,
+    pub total_huge_pages: Option<u64>,
+    pub free_huge_pages: Option<u64>,
+}
+
+impl SingleNodeModel {
+    fn new(idx: u32, meminfo: &procfs::NodeMemInfo) -> SingleNodeModel {
+        SingleNodeModel {
+            idx,
+            total: meminfo.total,
+            free: meminfo.free,
+            used: meminfo.used,
+            anon: opt_add(meminfo.active_anon, meminfo.inactive_anon),
+            file: opt_add(meminfo.active_file, meminfo.inactive_file),
+            active_anon: meminfo.active_anon,
+            inactive_anon: meminfo.inactive_anon,
+            active_file: meminfo.active_file,
+            inactive_file: meminfo.inactive_file,
+            unevictable: meminfo.unevictable,
+            mlocked: meminfo.mlocked,
+            dirty: meminfo.dirty,
+            writeback: meminfo.writeback,
+            mapped: meminfo.mapped,
+            shmem: meminfo.shmem,
+            kernel_stack: meminfo.kernel_stack,
+            page_tables: meminfo.page_tables,
+            slab_reclaimable: meminfo.slab_reclaimable,
+            slab_unreclaimable: meminfo.slab_unreclaimable,
+            anon_huge_pages_bytes: meminfo.anon_huge_pages,
+            total_huge_pages: meminfo.total_huge_pages,
+            free_huge_pages: meminfo.free_huge_pages,
+        }
+    }
+}
+
 #[derive(
     Clone,
     Debug,
@@ -38,6 +38,7 @@ mod test;
 
 pub const NET_SYSFS: &str = "/sys/class/net/";
 pub const NET_PROCFS: &str = "/proc/net";
+pub const NODE_SYSFS: &str = "/sys/devices/system/node";
 
 lazy_static! {
     /// The number of microseconds per clock tick
@@ -650,7 +651,73 @@ impl ProcReader {
         Self::read_pid_exe_path_from_path(self.path.join(pid.to_string()))
     }
 
+    fn read_pid_threads_from_path<P: AsRef<Path>>(&self, path: P) -> Result<ThreadMap> {
+        let path = path.as_ref().join("task");
+        let mut threadmap: ThreadMap = Default::default();
+        for entry in std::fs::read_dir(&path).map_err(|e| Error::IoError(path.clone(), e))? {
+            let entry = match entry {
+                Err(ref e)
+                    if e.raw_os_error()
+                        .map_or(false, |ec| ec == 2 || ec == 3 /* ENOENT or ESRCH */) =>
+                {
+                    continue;
+                }
+                ent => ent.map_err(|e| Error::IoError(path.clone(), e))?,
+            };
+            let tid = match entry.file_name().to_string_lossy().parse::<i32>() {
+                Ok(tid) => tid,
+                Err(_) => continue,
+            };
+
+            let mut threadinfo: ThreadInfo = Default::default();
+
+            // The thread may have exited since we listed the directory
+            match self.read_pid_stat_from_path(entry.path()) {
+                Err(Error::IoError(_, ref e))
+                    if e.raw_os_error()
+                        .map_or(false, |ec| ec == 2 || ec == 3 /* ENOENT or ESRCH */) =>
+                {
+                    continue;
+                }
+                res => threadinfo.stat = res?,
+            }
+
+            match Self::read_pid_io_from_path(entry.path()) {
+                Err(Error::IoError(_, ref e))
+                    if e.raw_os_error()
+                        .map_or(false, |ec| ec == 2 || ec == 3 /* ENOENT or ESRCH */) =>
+                {
+                    continue;
+                }
+                // Same as /proc/pid/io, just leave io info empty
+                Err(Error::IoError(_, ref e))
+                    if e.raw_os_error().map_or(false, |ec| ec == 13 /* EACCES */) => {}
+                res => threadinfo.io = res?,
+            }
+
+            threadmap.insert(tid, threadinfo);
+        }
+
+        Ok(threadmap)
+    }
+
+    /// Read stat and io of every thread of the process from
+    /// /proc/[pid]/task/[tid]
+    pub fn read_pid_threads(&self, pid: u32) -> Result<ThreadMap> {
+        self.read_pid_threads_from_path(self.path.join(pid.to_string()))
+    }
+
     pub fn read_all_pids(&mut self) -> Result<PidMap> {
+        self.read_all_pids_impl(false)
+    }
+
+    /// Same as `read_all_pids`, but also reads the threads of every process,
+    /// which is a lot more expensive for heavily threaded processes.
+    pub fn read_all_pids_with_threads(&mut self) -> Result<PidMap> {
+        self.read_all_pids_impl(true)
+    }
+
+    fn read_all_pids_impl(&mut self, with_threads: bool) -> Result<PidMap> {
         let mut pidmap: PidMap = Default::default();
         for entry in
             std::fs::read_dir(&self.path).map_err(|e| Error::IoError(self.path.clone(), e))?
@@ -747,6 +814,18 @@ impl ProcReader {
                 pidinfo.exe_path = Some(s);
             }
 
+            if with_threads {
+                match self.read_pid_threads_from_path(entry.path()) {
+                    Err(Error::IoError(_, ref e))
+                        if e.raw_os_error()
+                            .map_or(false, |ec| ec == 2 || ec == 3 /* ENOENT or ESRCH */) =>
+                    {
+                        continue;
+                    }
+                    res => pidinfo.threads = Some(res?),
+                }
+            }
+
             let file_name = entry.file_name();
             let pid_str = file_name.to_string_lossy();
             let pid = pid_str.parse::<i32>().map_err(|_| Error::ParseError {
@@ -766,6 +845,105 @@ impl ProcReader {
     }
 }
 
+pub struct NodeReader {
+    path: PathBuf,
+}
+
+impl NodeReader {
+    pub fn new() -> NodeReader {
+        Self::new_with_custom_path(NODE_SYSFS.into())
+    }
+
+    pub fn new_with_custom_path(path: PathBuf) -> NodeReader {
+        NodeReader { path }
+    }
+
+    fn read_meminfo(path: PathBuf) -> Result<NodeMemInfo> {
+        let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
+        let buf_reader = BufReader::new(file);
+        let mut meminfo: NodeMemInfo = Default::default();
+
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
+
+            // e.g. "Node 0 MemTotal:       32768000 kB"
+            let mut items = line.split_whitespace().skip(2);
+            if let Some(item) = items.next() {
+                match item {
+                    "MemTotal:" => meminfo.total = parse_kb!(path, items.next(), line)?,
+                    "MemFree:" => meminfo.free = parse_kb!(path, items.next(), line)?,
+                    "MemUsed:" => meminfo.used = parse_kb!(path, items.next(), line)?,
+                    "Active(anon):" => meminfo.active_anon = parse_kb!(path, items.next(), line)?,
+                    "Inactive(anon):" => {
+                        meminfo.inactive_anon = parse_kb!(path, items.next(), line)?
+                    }
+                    "Active(file):" => meminfo.active_file = parse_kb!(path, items.next(), line)?,
+                    "Inactive(file):" => {
+                        meminfo.inactive_file = parse_kb!(path, items.next(), line)?
+                    }
+                    "Unevictable:" => meminfo.unevictable = parse_kb!(path, items.next(), line)?,
+                    "Mlocked:" => meminfo.mlocked = parse_kb!(path, items.next(), line)?,
+                    "Dirty:" => meminfo.dirty = parse_kb!(path, items.next(), line)?,
+                    "Writeback:" => meminfo.writeback = parse_kb!(path, items.next(), line)?,
+                    "FilePages:" => meminfo.file_pages = parse_kb!(path, items.next(), line)?,
+                    "Mapped:" => meminfo.mapped = parse_kb!(path, items.next(), line)?,
+                    "AnonPages:" => meminfo.anon_pages = parse_kb!(path, items.next(), line)?,
+                    "Shmem:" => meminfo.shmem = parse_kb!(path, items.next(), line)?,
+                    "KernelStack:" => meminfo.kernel_stack = parse_kb!(path, items.next(), line)?,
+                    "PageTables:" => meminfo.page_tables = parse_kb!(path, items.next(), line)?,
+                    "Slab:" => meminfo.slab = parse_kb!(path, items.next(), line)?,
+                    "SReclaimable:" => {
+                        meminfo.slab_reclaimable = parse_kb!(path, items.next(), line)?
+                    }
+                    "SUnreclaim:" => {
+                        meminfo.slab_unreclaimable = parse_kb!(path, items.next(), line)?
+                    }
+                    "AnonHugePages:" => {
+                        meminfo.anon_huge_pages = parse_kb!(path, items.next(), line)?
+                    }
+                    "HugePages_Total:" => {
+                        meminfo.total_huge_pages = parse_item!(path, items.next(), u64, line)?
+                    }
+                    "HugePages_Free:" => {
+                        meminfo.free_huge_pages = parse_item!(path, items.next(), u64, line)?
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if meminfo == Default::default() {
+            Err(Error::InvalidFileFormat(path))
+        } else {
+            Ok(meminfo)
+        }
+    }
+
+    /// Read the meminfo of every NUMA node, keyed by node id. Returns an
+    /// empty map if the kernel has no NUMA support.
+    pub fn read_node_meminfo(&self) -> Result<NodeMemInfoMap> {
+        let entries = match std::fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Default::default()),
+            Err(e) => return Err(Error::IoError(self.path.clone(), e)),
+        };
+        let mut nodes: NodeMemInfoMap = Default::default();
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::IoError(self.path.clone(), e))?;
+            let node = match entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("node"))
+                .and_then(|node| node.parse::<u32>().ok())
+            {
+                Some(node) => node,
+                None => continue,
+            };
+            nodes.insert(node, Self::read_meminfo(entry.path().join("meminfo"))?);
+        }
+        Ok(nodes)
+    }
+}
+
 pub trait PidStateExt {
     fn from_char(c: char) -> Option<PidState>;
     fn as_char(&self) -> Option<char>;
@@ -21,6 +21,7 @@ use tempfile::TempDir;
 
 use crate::types::*;
 use crate::NetReader;
+use crate::NodeReader;
 use crate::ProcReader;
 use crate::PAGE_SIZE;
 
@@ -305,6 +306,66 @@ DirectMap1G:     2097152 kB
     assert_eq!(meminfo.direct_map_1g, Some(2_097_152 * 1024));
 }
 
+#[test]
+fn test_node_meminfo_success() {
+    let meminfo = b"Node 1 MemTotal:       32918208 kB
+Node 1 MemFree:         1238432 kB
+Node 1 MemUsed:        31679776 kB
+Node 1 Active:         12345600 kB
+Node 1 Inactive:       15432100 kB
+Node 1 Active(anon):    4396572 kB
+Node 1 Inactive(anon):  2459820 kB
+Node 1 Active(file):    7949028 kB
+Node 1 Inactive(file): 12972280 kB
+Node 1 Unevictable:        7340 kB
+Node 1 Mlocked:            7330 kB
+Node 1 Dirty:             20448 kB
+Node 1 Writeback:             0 kB
+Node 1 FilePages:      20935652 kB
+Node 1 Mapped:           483776 kB
+Node 1 AnonPages:       6844752 kB
+Node 1 Shmem:              7192 kB
+Node 1 KernelStack:       29488 kB
+Node 1 PageTables:        78582 kB
+Node 1 Slab:            3782198 kB
+Node 1 SReclaimable:    2936708 kB
+Node 1 SUnreclaim:       845490 kB
+Node 1 AnonHugePages:     24576 kB
+Node 1 HugePages_Total:     0
+Node 1 HugePages_Free:      0
+Node 1 HugePages_Surp:      0
+";
+    let sysfs = TestProcfs::new();
+    sysfs.create_dir("node1");
+    sysfs.create_dir("power");
+    sysfs.create_file_with_content("possible", b"0-1\n");
+    sysfs.create_file_with_content("node1/meminfo", meminfo);
+    let reader = NodeReader::new_with_custom_path(sysfs.path().to_path_buf());
+
+    let nodes = reader
+        .read_node_meminfo()
+        .expect("Failed to read node meminfo");
+    assert_eq!(nodes.len(), 1);
+    let meminfo = &nodes[&1];
+    assert_eq!(meminfo.total, Some(32918208 * 1024));
+    assert_eq!(meminfo.free, Some(1238432 * 1024));
+    assert_eq!(meminfo.used, Some(31679776 * 1024));
+    assert_eq!(meminfo.active_anon, Some(4396572 * 1024));
+    assert_eq!(meminfo.inactive_file, Some(12972280 * 1024));
+    assert_eq!(meminfo.file_pages, Some(20935652 * 1024));
+    assert_eq!(meminfo.slab_unreclaimable, Some(845490 * 1024));
+    assert_eq!(meminfo.total_huge_pages, Some(0));
+
+    // No NUMA support
+    let reader = NodeReader::new_with_custom_path(sysfs.path().join("missing"));
+    assert_eq!(
+        reader
+            .read_node_meminfo()
+            .expect("Failed to read node meminfo"),
+        Default::default()
+    );
+}
+
 #[test]
 fn test_vmstat_success() {
     let vmstat = b"nr_free_pages 1091519
@@ -802,6 +863,41 @@ cancelled_write_bytes: 5431947264
     assert_eq!(pidio.wbytes, Some(284070445056));
 }
 
+#[test]
+fn test_pid_threads() {
+    let uptime = b"1631826.55 37530838.66";
+    let main_stat = b"1024 (java) S 1 1024 1024 0 -1 4194560 2 0 0 0 100 50 0 0 20 0 3 0 102803 224440320 12725 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 3 0 0 0 0 0";
+    let worker_stat = b"1025 (GC Thread#0) R 1 1024 1024 0 -1 4194624 5 0 1 0 2000 300 0 0 20 0 3 0 102900 224440320 12725 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 -1 7 0 0 0 0 0";
+    let io = b"read_bytes: 4096
+write_bytes: 8192
+";
+
+    let procfs = TestProcfs::new();
+    procfs.create_file_with_content("uptime", uptime);
+    procfs.create_dir("1024/task/1024");
+    procfs.create_dir("1024/task/1025");
+    // A thread that exited after the task directory was listed
+    procfs.create_dir("1024/task/1026");
+    procfs.create_pid_file_with_content(1024, "task/1024/stat", main_stat);
+    procfs.create_pid_file_with_content(1024, "task/1024/io", io);
+    procfs.create_pid_file_with_content(1024, "task/1025/stat", worker_stat);
+    procfs.create_pid_file_with_content(1024, "task/1025/io", io);
+    let reader = procfs.get_reader();
+    let threads = reader
+        .read_pid_threads(1024)
+        .expect("Failed to read pid threads");
+
+    assert_eq!(threads.len(), 2);
+    assert_eq!(threads[&1024].stat.comm, Some("java".to_string()));
+    assert_eq!(threads[&1025].stat.pid, Some(1025));
+    assert_eq!(threads[&1025].stat.comm, Some("GC Thread#0".to_string()));
+    assert_eq!(threads[&1025].stat.state, Some(PidState::Running));
+    assert_eq!(threads[&1025].stat.user_usecs, Some(20000000));
+    assert_eq!(threads[&1025].stat.processor, Some(7));
+    assert_eq!(threads[&1025].io.rbytes, Some(4096));
+    assert_eq!(threads[&1025].io.wbytes, Some(8192));
+}
+
 #[test]
 fn test_pid_cgroupv2() {
     let cgroup = b"0::/user.slice/user:with:colon.slice/session-3.scope
@@ -1009,6 +1105,21 @@ nonvoluntary_ctxt_switches:	37733";
             .join(" "),
         "one two three"
     );
+    assert_eq!(pidmap[&1024].threads, None);
+
+    procfs.create_dir("1024/task/1024");
+    procfs.create_pid_file_with_content(1024, "task/1024/stat", stat);
+    procfs.create_pid_file_with_content(1024, "task/1024/io", io);
+    procfs.create_dir("1025/task/1025");
+    procfs.create_pid_file_with_content(1025, "task/1025/stat", stat);
+    procfs.create_pid_file_with_content(1025, "task/1025/io", io);
+    let pidmap = reader
+        .read_all_pids_with_threads()
+        .expect("Failed to get all pids with threads");
+
+    let threads = pidmap[&1024].threads.as_ref().expect("threads missing");
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[&1024].stat.comm, Some("bash".to_string()));
 }
 
 fn write_net_map(netsysfs: &TestProcfs) {
@@ -91,6 +91,36 @@ pub struct MemInfo {
     pub hugetlb: Option<u64>,
 }
 
+// In kilobytes unless specified otherwise
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NodeMemInfo {
+    pub total: Option<u64>,
+    pub free: Option<u64>,
+    pub used: Option<u64>,
+    pub active_anon: Option<u64>,
+    pub inactive_anon: Option<u64>,
+    pub active_file: Option<u64>,
+    pub inactive_file: Option<u64>,
+    pub unevictable: Option<u64>,
+    pub mlocked: Option<u64>,
+    pub dirty: Option<u64>,
+    pub writeback: Option<u64>,
+    pub file_pages: Option<u64>,
+    pub mapped: Option<u64>,
+    pub anon_pages: Option<u64>,
+    pub shmem: Option<u64>,
+    pub kernel_stack: Option<u64>,
+    pub page_tables: Option<u64>,
+    pub slab: Option<u64>,
+    pub slab_reclaimable: Option<u64>,
+    pub slab_unreclaimable: Option<u64>,
+    pub anon_huge_pages: Option<u64>,
+    // This is in number of pages: not kilobytes
+    pub total_huge_pages: Option<u64>,
+    // This is in number of pages: not kilobytes
+    pub free_huge_pages: Option<u64>,
+}
+
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct InterfaceStat {
     pub collisions: Option<u64>,
@@ -348,11 +378,22 @@ pub struct PidInfo {
     pub cmdline_vec: Option<Vec<String>>,
     pub exe_path: Option<String>,
     pub mem: PidMem,
+    // Only collected on request, see ProcReader::read_all_pids_with_threads
+    pub threads: Option<ThreadMap>,
+}
+
+/// Per-thread stats from /proc/[pid]/task/[tid]
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ThreadInfo {
+    pub stat: PidStat,
+    pub io: PidIo,
 }
 
 pub type PidMap = BTreeMap<i32, PidInfo>;
+pub type ThreadMap = BTreeMap<i32, ThreadInfo>;
 pub type NetMap = BTreeMap<String, InterfaceStat>;
 pub type DiskMap = BTreeMap<String, DiskStat>;
+pub type NodeMemInfoMap = BTreeMap<u32, NodeMemInfo>;
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct NetStat {
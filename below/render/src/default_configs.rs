@@ -14,7 +14,7 @@
 
 use super::*;
 
-use RenderFormat::{MaxOrReadableSize, PageReadableSize, Precision, ReadableSize};
+use RenderFormat::{MaxOrNumber, MaxOrReadableSize, PageReadableSize, Precision, ReadableSize};
 
 impl HasRenderConfig for model::SingleCgroupModel {
     fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
@@ -24,13 +24,27 @@ impl HasRenderConfig for model::SingleCgroupModel {
             Name => rc.title("Name").width(50),
             FullPath => rc.title("Full Path").width(50),
             InodeNumber => rc.title("Inode Number"),
+            NrDescendants => rc.title("Descendants"),
+            NrDyingDescendants => rc.title("Dying Descendants"),
+            Populated => rc.title("Populated"),
+            Frozen => rc.title("Frozen"),
+            CpusetCpus => rc.title("Cpuset CPUs").width(20),
+            CpusetMems => rc.title("Cpuset Mems"),
             Cpu(field_id) => model::CgroupCpuModel::get_render_config_builder(field_id),
             Io(field_id) => model::CgroupIoModel::get_render_config_builder(field_id),
             IoDetails(field_id) => {
                 model::CgroupIoModel::get_render_config_builder(&field_id.subquery_id)
             }
             Mem(field_id) => model::CgroupMemoryModel::get_render_config_builder(field_id),
+            MemNuma(field_id) => {
+                model::CgroupMemoryNumaModel::get_render_config_builder(&field_id.subquery_id)
+            }
+            Hugetlb(field_id) => {
+                model::CgroupHugetlbModel::get_render_config_builder(&field_id.subquery_id)
+            }
             Pressure(field_id) => model::CgroupPressureModel::get_render_config_builder(field_id),
+            Pids(field_id) => model::CgroupPidsModel::get_render_config_builder(field_id),
+            Extra(field_id) => model::CgroupExtraModel::get_render_config_builder(field_id),
         }
     }
 }
@@ -46,6 +60,10 @@ impl HasRenderConfig for model::CgroupCpuModel {
             NrPeriodsPerSec => rc.title("Nr Period").suffix("/s").format(Precision(2)),
             NrThrottledPerSec => rc.title("Nr Throttled").suffix("/s").format(Precision(2)),
             ThrottledPct => rc.title("Throttled").suffix("%").format(Precision(2)),
+            Weight => rc.title("CPU Weight"),
+            MaxUsec => rc.title("CPU Max").format(MaxOrNumber),
+            MaxPeriodUsec => rc.title("CPU Max Period").suffix("us"),
+            UsagePctOfMax => rc.title("CPU % Max").suffix("%").format(Precision(2)),
         }
     }
 }
@@ -62,6 +80,13 @@ impl HasRenderConfig for model::CgroupIoModel {
             DbytesPerSec => rc.title("Discards").suffix("/s").format(ReadableSize),
             DiosPerSec => rc.title("Discard IOPS").format(Precision(1)),
             RwbytesPerSec => rc.title("RW Total").suffix("/s").format(ReadableSize),
+            Weight => rc.title("IO Weight"),
+            RbpsMax => rc.title("Read BPS Max").format(MaxOrReadableSize),
+            WbpsMax => rc.title("Write BPS Max").format(MaxOrReadableSize),
+            RiopsMax => rc.title("Read IOPS Max").format(MaxOrNumber),
+            WiopsMax => rc.title("Write IOPS Max").format(MaxOrNumber),
+            RbytesPctOfMax => rc.title("Reads % Max").suffix("%").format(Precision(2)),
+            WbytesPctOfMax => rc.title("Writes % Max").suffix("%").format(Precision(2)),
         }
     }
 }
@@ -74,6 +99,12 @@ impl HasRenderConfig for model::CgroupMemoryModel {
             Total => rc.title("Memory").format(ReadableSize),
             Swap => rc.title("Memory Swap").format(ReadableSize),
             MemoryHigh => rc.title("Memory High").format(MaxOrReadableSize),
+            MemoryMax => rc.title("Memory Max").format(MaxOrReadableSize),
+            MemoryLow => rc.title("Memory Low").format(MaxOrReadableSize),
+            MemoryMin => rc.title("Memory Min").format(MaxOrReadableSize),
+            MemorySwapMax => rc.title("Swap Max").format(MaxOrReadableSize),
+            TotalPctOfMax => rc.title("Mem % Max").suffix("%").format(Precision(2)),
+            SwapPctOfMax => rc.title("Swap % Max").suffix("%").format(Precision(2)),
             EventsLow => rc.title("Events Low"),
             EventsHigh => rc.title("Events High"),
             EventsMax => rc.title("Events Max"),
@@ -96,13 +127,23 @@ impl HasRenderConfig for model::CgroupMemoryModel {
             Unevictable => rc.title("Unevictable").format(ReadableSize),
             SlabReclaimable => rc.title("Slab Reclaimable").format(ReadableSize),
             SlabUnreclaimable => rc.title("Slab Unreclaimable").format(ReadableSize),
+            Percpu => rc.title("Percpu").format(ReadableSize),
+            SecPagetables => rc.title("Sec Page Tables").format(ReadableSize),
+            Zswap => rc.title("Zswap").format(ReadableSize),
+            Zswapped => rc.title("Zswapped").format(ReadableSize),
+            FileThp => rc.title("File THP").format(ReadableSize),
+            ShmemThp => rc.title("Shmem THP").format(ReadableSize),
             Pgfault => rc.title("Pgfault/s"),
             Pgmajfault => rc.title("Pgmajfault/s"),
             WorkingsetRefault => rc.title("Workingset Refault/s"),
+            WorkingsetRefaultAnon => rc.title("Workingset Refault Anon/s"),
+            WorkingsetRefaultFile => rc.title("Workingset Refault File/s"),
             WorkingsetActivate => rc.title("Workingset Activate/s"),
             WorkingsetNodereclaim => rc.title("Workingset Nodereclaim/s"),
             Pgrefill => rc.title("Pgrefill/s"),
             Pgscan => rc.title("Pgscan/s"),
+            PgscanDirect => rc.title("Pgscan Direct/s"),
+            PgscanKswapd => rc.title("Pgscan Kswapd/s"),
             Pgsteal => rc.title("Pgsteal/s"),
             Pgactivate => rc.title("Pgactivate/s"),
             Pgdeactivate => rc.title("Pgdeactivate/s"),
@@ -110,10 +151,59 @@ impl HasRenderConfig for model::CgroupMemoryModel {
             Pglazyfreed => rc.title("Pglazyfreed/s"),
             ThpFaultAlloc => rc.title("THP Fault Alloc/s"),
             ThpCollapseAlloc => rc.title("THP Collapse Alloc/s"),
+            Other(field_id) => model::CgroupMemoryOtherModel::get_render_config_builder(field_id),
         }
     }
 }
 
+impl HasRenderConfig for model::CgroupMemoryOtherModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        RenderConfigBuilder::new().title(field_id.to_string())
+    }
+}
+
+impl HasRenderConfig for model::CgroupMemoryNumaModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::CgroupMemoryNumaModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Total => rc.title("Memory").format(ReadableSize),
+            Anon => rc.title("Anon").format(ReadableSize),
+            File => rc.title("File").format(ReadableSize),
+            KernelStack => rc.title("Kernel Stack").format(ReadableSize),
+            Pagetables => rc.title("Page Tables").format(ReadableSize),
+            Shmem => rc.title("Shmem").format(ReadableSize),
+            FileMapped => rc.title("File Mapped").format(ReadableSize),
+            FileDirty => rc.title("File Dirty").format(ReadableSize),
+            FileWriteback => rc.title("File WB").format(ReadableSize),
+            AnonThp => rc.title("Anon THP").format(ReadableSize),
+            InactiveAnon => rc.title("Inactive Anon").format(ReadableSize),
+            ActiveAnon => rc.title("Active Anon").format(ReadableSize),
+            InactiveFile => rc.title("Inactive File").format(ReadableSize),
+            ActiveFile => rc.title("Active File").format(ReadableSize),
+            Unevictable => rc.title("Unevictable").format(ReadableSize),
+            SlabReclaimable => rc.title("Slab Reclaimable").format(ReadableSize),
+            SlabUnreclaimable => rc.title("Slab Unreclaimable").format(ReadableSize),
+        }
+    }
+}
+
+impl HasRenderConfig for model::CgroupHugetlbModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::CgroupHugetlbModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Current => rc.title("Hugetlb").format(ReadableSize),
+        }
+    }
+}
+
+impl HasRenderConfig for model::CgroupExtraModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        RenderConfigBuilder::new().title(field_id.to_string())
+    }
+}
+
 impl HasRenderConfig for model::CgroupPressureModel {
     fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
         use model::CgroupPressureModelFieldId::*;
@@ -134,6 +224,19 @@ impl HasRenderConfig for model::CgroupPressureModel {
     }
 }
 
+impl HasRenderConfig for model::CgroupPidsModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::CgroupPidsModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Current => rc.title("Pids"),
+            Max => rc.title("Pids Max").format(MaxOrNumber),
+            CurrentPctOfMax => rc.title("Pids % Max").suffix("%").format(Precision(2)),
+            EventsMax => rc.title("Pids Max Events"),
+        }
+    }
+}
+
 impl HasRenderConfig for model::NetworkModel {
     fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
         use model::NetworkModelFieldId::*;
@@ -336,6 +439,7 @@ impl HasRenderConfig for model::SingleProcessModel {
         let rc = RenderConfigBuilder::new();
         match field_id {
             Pid => rc.title("Pid"),
+            Tgid => rc.title("Tgid"),
             Ppid => rc.title("Ppid"),
             Comm => rc.title("Comm").width(30),
             State => rc.title("State"),
@@ -412,6 +516,9 @@ impl HasRenderConfig for model::SystemModel {
             Disks(field_id) => {
                 model::SingleDiskModel::get_render_config_builder(&field_id.subquery_id)
             }
+            Nodes(field_id) => {
+                model::SingleNodeModel::get_render_config_builder(&field_id.subquery_id)
+            }
         }
     }
 }
@@ -468,6 +575,38 @@ impl HasRenderConfig for Vec<model::SingleCpuModel> {
     }
 }
 
+impl HasRenderConfig for model::SingleNodeModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SingleNodeModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Idx => rc.title("Node"),
+            Total => rc.title("Total").format(ReadableSize),
+            Free => rc.title("Free").format(ReadableSize),
+            Used => rc.title("Used").format(ReadableSize),
+            Anon => rc.title("Anon").format(ReadableSize),
+            File => rc.title("File").format(ReadableSize),
+            ActiveAnon => rc.title("Active Anon").format(ReadableSize),
+            InactiveAnon => rc.title("Inactive Anon").format(ReadableSize),
+            ActiveFile => rc.title("Active File").format(ReadableSize),
+            InactiveFile => rc.title("Inactive File").format(ReadableSize),
+            Unevictable => rc.title("Unevictable").format(ReadableSize),
+            Mlocked => rc.title("Mlocked").format(ReadableSize),
+            Dirty => rc.title("Dirty").format(ReadableSize),
+            Writeback => rc.title("Writeback").format(ReadableSize),
+            Mapped => rc.title("Mapped").format(ReadableSize),
+            Shmem => rc.title("Shmem").format(ReadableSize),
+            KernelStack => rc.title("Kernel Stack").format(ReadableSize),
+            PageTables => rc.title("Page Tables").format(ReadableSize),
+            SlabReclaimable => rc.title("Slab Reclaimable").format(ReadableSize),
+            SlabUnreclaimable => rc.title("Slab Unreclaimable").format(ReadableSize),
+            AnonHugePagesBytes => rc.title("Anon Huge Pages").format(ReadableSize),
+            TotalHugePages => rc.title("Huge Pages Total"),
+            FreeHugePages => rc.title("Huge Pages Free"),
+        }
+    }
+}
+
 impl HasRenderConfig for model::MemoryModel {
     fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
         use model::MemoryModelFieldId::*;
@@ -0,0 +1,85 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Plain text tables of a ModelDiff, shared by `below diff` and the diff
+//! popup of the view.
+
+use std::fmt::Write;
+
+use common::util::convert_bytes;
+use model::ModelDiff;
+
+fn render_bytes_change(val: Option<i64>) -> String {
+    match val {
+        Some(val) if val < 0 => format!("-{}", convert_bytes(-val as f64)),
+        Some(val) => format!("+{}", convert_bytes(val as f64)),
+        None => "?".to_owned(),
+    }
+}
+
+fn render_opt<T>(val: Option<T>, f: impl FnOnce(T) -> String) -> String {
+    val.map_or_else(|| "?".to_owned(), f)
+}
+
+/// Render the first `top` cgroups and processes of `diff` (all if None) in
+/// their current order. Processes started within the window are marked
+/// with a `*` after their pid.
+pub fn render_model_diff(diff: &ModelDiff, top: Option<usize>) -> String {
+    let top = top.unwrap_or(usize::MAX);
+    let mut res = String::new();
+    writeln!(
+        res,
+        "{:<10} {:<10} {:<10} {:<10} {:<10} {:<8} {:<8} {:<5} Cgroup",
+        "CPU", "Mem", "Anon", "Swap", "IO", "MemSome", "IOSome", "OOMs"
+    )
+    .unwrap();
+    for cgroup in diff.cgroups.iter().take(top) {
+        writeln!(
+            res,
+            "{:<10} {:<10} {:<10} {:<10} {:<10} {:<8} {:<8} {:<5} {}",
+            render_opt(cgroup.cpu_secs, |v| format!("{:.1}s", v)),
+            render_bytes_change(cgroup.mem_bytes),
+            render_bytes_change(cgroup.anon_bytes),
+            render_bytes_change(cgroup.swap_bytes),
+            render_opt(cgroup.io_bytes, convert_bytes),
+            render_opt(cgroup.memory_some_pct, |v| format!("{:.2}%", v)),
+            render_opt(cgroup.io_some_pct, |v| format!("{:.2}%", v)),
+            render_opt(cgroup.oom_kills, |v| v.to_string()),
+            cgroup.full_path,
+        )
+        .unwrap();
+    }
+    res.push('\n');
+    writeln!(
+        res,
+        "{:<10} {:<10} {:<10} {:<10} {:<8} {:<16} Cgroup",
+        "CPU", "RSS", "IO", "MajFaults", "Pid", "Comm"
+    )
+    .unwrap();
+    for process in diff.processes.iter().take(top) {
+        writeln!(
+            res,
+            "{:<10} {:<10} {:<10} {:<10} {:<8} {:<16} {}",
+            render_opt(process.cpu_secs, |v| format!("{:.1}s", v)),
+            render_bytes_change(process.rss_bytes),
+            render_opt(process.io_bytes, convert_bytes),
+            render_opt(process.major_faults, |v| format!("{:.0}", v)),
+            format!("{}{}", process.pid, if process.new { "*" } else { "" }),
+            process.comm.as_deref().unwrap_or("?"),
+            process.cgroup.as_deref().unwrap_or("?"),
+        )
+        .unwrap();
+    }
+    res
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 mod default_configs;
+pub mod diff;
 
 use common::util::{convert_bytes, fold_string};
 use model::{Field, Queriable};
@@ -31,6 +32,9 @@ pub enum RenderFormat {
     /// Only works on int Fields. Same as ReadableSize except when Field is -1,
     /// in which case "max" is returned.
     MaxOrReadableSize,
+    /// Only works on int Fields. Same as no format except when Field is -1,
+    /// in which case "max" is returned.
+    MaxOrNumber,
 }
 
 /// Specifies how a long string is folded to fit into a shorter width.
@@ -167,6 +171,14 @@ impl RenderConfig {
                         convert_bytes(field as f64)
                     }
                 }
+                MaxOrNumber => {
+                    let field = i64::from(field);
+                    if field == -1 {
+                        "max".to_owned()
+                    } else {
+                        field.to_string()
+                    }
+                }
             },
             None => field.to_string(),
         }
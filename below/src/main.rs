@@ -18,10 +18,11 @@
 use std::cell::RefCell;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::rc::Rc;
-use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, TryRecvError};
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime};
@@ -43,7 +44,7 @@ use common::{cliutil, logutil, open_source_shim};
 use config::BelowConfig;
 use dump::DumpCommand;
 use model;
-use store::advance::{new_advance_local, new_advance_remote};
+use store::advance::{new_advance_local, new_advance_remote, new_advance_snapshot};
 use store::{self, DataFrame, Store};
 use view::ViewState;
 
@@ -76,21 +77,27 @@ enum Command {
         /// Override default port to connect remote viewing to
         #[structopt(long)]
         port: Option<u16>,
+        /// Also collect the threads of every process, shown by expanding
+        /// processes in the process view. Not supported with --host
+        #[structopt(long, conflicts_with = "host")]
+        collect_threads: bool,
     },
     /// Record local system data (daemon mode)
     Record {
         #[structopt(short, long, default_value = "5")]
         interval_s: u64,
-        /// Store retention in seconds. Data is stored in 24 hour shards.
-        /// Whever an entire shard of data is outside the retention period it
-        /// is discarded. That is, any data older than retention + 24 hours
-        /// is guaranteed to be discarded.
+        /// Store retention in seconds. Data is stored in shards of
+        /// --shard-time-s (24 hours by default). Whever an entire shard of
+        /// data is outside the retention period it is discarded. That is,
+        /// any data older than retention + shard time is guaranteed to be
+        /// discarded.
         ///
         /// N.B. If --store-size-limit is set, data may be discarded earlier
         ///      than the specified retention.
         #[structopt(long)]
         retain_for_s: Option<u64>,
-        /// Store size limit in bytes. Data is stored in 24 hour shards.
+        /// Store size limit in bytes. Data is stored in shards of
+        /// --shard-time-s (24 hours by default).
         /// Shards before the active shard are deleted, oldest first,
         /// according to the size limit. Enforcement is only triggered on new
         /// shard creation.
@@ -99,13 +106,46 @@ enum Command {
         ///      be exceeded by a single active shard.
         #[structopt(long)]
         store_size_limit: Option<u64>,
+        /// Length of each store shard in seconds, e.g. 3600 for one hour
+        /// shards on hosts with little disk space. It is recorded when the
+        /// store is created and cannot be changed for an existing store.
+        /// Defaults to 24 hours.
+        #[structopt(long)]
+        shard_time_s: Option<u64>,
+        /// Downsample data older than this many seconds into the rollup
+        /// store (rollup_store_dir in below.conf), keeping one frame per
+        /// --rollup-interval-s, so coarse history outlives --retain-for-s.
+        ///
+        /// N.B. Data discarded because of --store-size-limit before it gets
+        ///      this old is not rolled up.
+        #[structopt(long)]
+        rollup_after_s: Option<u64>,
+        /// Interval between frames of the rollup store in seconds
+        #[structopt(long, default_value = "60")]
+        rollup_interval_s: u64,
+        /// Rollup store retention in seconds
+        #[structopt(long)]
+        rollup_retain_for_s: Option<u64>,
         /// Whether or not to collect io.stat for cgroups which could
         /// be expensive
         #[structopt(long)]
         collect_io_stat: bool,
+        /// Whether or not to collect stats of every thread from
+        /// /proc/[pid]/task, which could be expensive with heavily
+        /// threaded processes
+        #[structopt(long)]
+        collect_threads: bool,
         /// Override default port for remote viewing server
         #[structopt(long)]
         port: Option<u16>,
+        /// Serve the store to `below live/replay/dump --host` on other hosts.
+        /// The server is unauthenticated and exposes all collected data,
+        /// including process cmdlines.
+        #[structopt(long)]
+        serve_remote: bool,
+        /// Address the remote viewing server binds to, defaults to loopback
+        #[structopt(long, requires("serve-remote"))]
+        serve_address: Option<std::net::IpAddr>,
         /// Threshold for hold long data collection takes to trigger warnings.
         #[structopt(long, default_value = "500")]
         skew_detection_threshold_ms: u64,
@@ -120,6 +160,41 @@ enum Command {
         /// You can expect up to ~4.5x smaller data files
         #[structopt(long)]
         compress: bool,
+        /// Compress data frames against a zstd dictionary trained from the
+        /// first frames of each shard. Implies --compress
+        #[structopt(long)]
+        dict_compress: bool,
+        /// Only store the difference of most data frames to a periodic full
+        /// keyframe. Implies --compress
+        #[structopt(long, conflicts_with = "dict-compress")]
+        delta_compress: bool,
+        /// Serve the latest collected sample in the OpenMetrics text format
+        /// at http://<address>/metrics, e.g. 0.0.0.0:9100
+        #[structopt(long)]
+        openmetrics_address: Option<String>,
+        /// Interval between samples of a capture burst in milliseconds. A
+        /// burst samples at this higher rate for a while after a trigger,
+        /// e.g. SIGUSR1, to catch short spikes.
+        #[structopt(long, default_value = "200")]
+        burst_interval_ms: u64,
+        /// How long a capture burst lasts after its last trigger in seconds
+        #[structopt(long, default_value = "10")]
+        burst_duration_s: u64,
+        /// Start a capture burst when the memory pressure (some avg10) of
+        /// the root cgroup reaches this percentage
+        #[structopt(long)]
+        burst_on_memory_pressure: Option<f64>,
+        /// Start a capture burst when a cgroup had an OOM kill since the
+        /// last sample
+        #[structopt(long)]
+        burst_on_oom_kill: bool,
+        /// Start a capture burst when a PSI trigger on the root cgroup
+        /// fires, given as <resource>:<some|full>:<stall_ms>:<window_ms>,
+        /// e.g. memory:some:150:1000 fires when tasks stalled on memory for
+        /// 150ms within 1s. The start of each stall is annotated. May be
+        /// repeated.
+        #[structopt(long)]
+        psi_trigger: Vec<PsiTrigger>,
     },
     /// Replay historical data (interactive)
     Replay {
@@ -132,12 +207,20 @@ enum Command {
         /// _
         #[structopt(short, long, verbatim_doc_comment)]
         time: String,
-        /// Supply hostname to activate remote viewing
+        /// Supply hostname to activate remote viewing. Several hosts
+        /// separated by commas are replayed side by side, e.g. a,b,c
         #[structopt(long)]
         host: Option<String>,
         /// Override default port to connect remote viewing to
         #[structopt(long)]
         port: Option<u16>,
+        /// Replay a snapshot file created by `below store snapshot`
+        #[structopt(long, parse(from_os_str), conflicts_with = "host")]
+        file: Option<PathBuf>,
+        /// Replay the store at this path instead of the configured one.
+        /// Repeat to replay several stores side by side.
+        #[structopt(long, parse(from_os_str), conflicts_with_all = &["host", "file"])]
+        store_dir: Vec<PathBuf>,
         /// Days adjuster: y[y...] for yesterday (repeated).
         /// Each "y" will deduct 1 day from the input of "--time/-t"{n}
         /// Examples:
@@ -159,9 +242,207 @@ enum Command {
         /// Override default port to connect remote dumping to
         #[structopt(long)]
         port: Option<u16>,
+        /// Dump a snapshot file created by `below store snapshot`
+        #[structopt(long, parse(from_os_str), conflicts_with = "host")]
+        file: Option<PathBuf>,
         #[structopt(subcommand)]
         cmd: DumpCommand,
     },
+    /// Serve historical data as JSON over HTTP (read-only)
+    ///
+    /// Endpoints mirror the dump subcommands, e.g. /system, /cgroup and
+    /// /process. Query parameters map to dump options, e.g.
+    /// /process?time=10m&rsort=cpu.usage_pct&top=10 or
+    /// /cgroup?path=/system.slice&fields=name,cpu. Responses are identical
+    /// to `below dump -O json`.
+    Serve {
+        /// Address to listen on
+        #[structopt(long, default_value = "127.0.0.1:8080")]
+        address: String,
+        /// Supply hostname to serve data of a remote host
+        #[structopt(long)]
+        host: Option<String>,
+        /// Override default port to connect remote serving to
+        #[structopt(long)]
+        port: Option<u16>,
+    },
+    /// Export historical data into typed columnar files (e.g. parquet)
+    ///
+    /// Writes one table per model (system, cgroup, process, iface, disk and
+    /// network) with one column per field of `below dump <model> --everything`.
+    Export {
+        /// Supply hostname to activate remote exporting
+        #[structopt(long)]
+        host: Option<String>,
+        /// Override default port to connect remote exporting to
+        #[structopt(long)]
+        port: Option<u16>,
+        #[structopt(flatten)]
+        opts: dump::export::ExportOpt,
+    },
+    /// Show how cgroups and processes changed between two points in time
+    ///
+    /// Cumulative counters (CPU, IO, OOM kills) are compared between the
+    /// first sample at or after --t1 and the last sample at or before --t2,
+    /// as are memory usage and pressure. Rows are sorted by biggest change.
+    Diff {
+        /// Start of the window (same format as Replay mode)
+        #[structopt(long = "t1")]
+        t1: String,
+        /// End of the window (same format as Replay mode)
+        #[structopt(long = "t2")]
+        t2: String,
+        /// Supply hostname to activate remote diffing
+        #[structopt(long)]
+        host: Option<String>,
+        /// Override default port to connect remote diffing to
+        #[structopt(long)]
+        port: Option<u16>,
+        /// Diff a snapshot file created by `below store snapshot`
+        #[structopt(long, parse(from_os_str), conflicts_with = "host")]
+        file: Option<PathBuf>,
+        /// Sort rows by the change of cpu, mem, io or pressure
+        #[structopt(long, default_value = "mem")]
+        sort: model::DiffSortKey,
+        /// Only show this many cgroups and processes
+        #[structopt(long)]
+        top: Option<usize>,
+    },
+    /// Add an annotation (e.g. "deploy started") at the current time to the
+    /// store of the running recorder. Replay shows annotations in the status
+    /// bar and `below dump annotations` lists them.
+    Annotate {
+        /// Text of the annotation
+        message: String,
+        /// What added the annotation
+        #[structopt(long, default_value = "user")]
+        source: String,
+    },
+    /// Merge, slice and split stores
+    Store {
+        #[structopt(subcommand)]
+        cmd: StoreCommand,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum StoreCommand {
+    /// Merge several stores into one, e.g. stores collected in different
+    /// directories. Frames with the same timestamp are taken from the store
+    /// listed first. All stores must be of the same host.
+    Merge {
+        /// Stores to merge
+        #[structopt(required = true)]
+        stores: Vec<PathBuf>,
+        /// Store to write to. Data already in it is kept and only frames
+        /// after its last frame are added.
+        #[structopt(long)]
+        to: PathBuf,
+        /// Only merge frames from this time on (same format as Replay mode)
+        #[structopt(long)]
+        begin: Option<String>,
+        /// Only merge frames up to this time (same format as Replay mode)
+        #[structopt(long)]
+        end: Option<String>,
+        /// Merge stores even if they were recorded on different hosts
+        #[structopt(long)]
+        allow_mixed_hosts: bool,
+        #[structopt(flatten)]
+        output: OutputStoreOpt,
+    },
+    /// Cut a time window out of a store into a new store, e.g. to attach an
+    /// incident to a postmortem
+    Slice {
+        /// Store to read from
+        store: PathBuf,
+        /// Store to write to
+        #[structopt(long)]
+        to: PathBuf,
+        /// Start of the window (same format as Replay mode)
+        #[structopt(long)]
+        begin: String,
+        /// End of the window (same format as Replay mode)
+        #[structopt(long)]
+        end: String,
+        #[structopt(flatten)]
+        output: OutputStoreOpt,
+    },
+    /// Split a store into one store per time window
+    Split {
+        /// Store to read from
+        store: PathBuf,
+        /// Directory to create the stores in. Each store is named after the
+        /// start of its window.
+        #[structopt(long)]
+        to: PathBuf,
+        /// Length of each window in seconds
+        #[structopt(long)]
+        every_s: u64,
+        /// Only split frames from this time on (same format as Replay mode)
+        #[structopt(long)]
+        begin: Option<String>,
+        /// Only split frames up to this time (same format as Replay mode)
+        #[structopt(long)]
+        end: Option<String>,
+        #[structopt(flatten)]
+        output: OutputStoreOpt,
+    },
+    /// Write a time window of a store into a single snapshot file, e.g. to
+    /// attach an incident to a bug report. Snapshots can be read with
+    /// `below replay --file` and `below dump --file`.
+    Snapshot {
+        /// Store to read from
+        store: PathBuf,
+        /// Snapshot file to create
+        #[structopt(long)]
+        to: PathBuf,
+        /// Start of the window (same format as Replay mode)
+        #[structopt(long)]
+        begin: String,
+        /// End of the window (same format as Replay mode)
+        #[structopt(long)]
+        end: String,
+        /// Compress each frame of the snapshot
+        #[structopt(long)]
+        compress: bool,
+    },
+}
+
+/// How `below store` writes stores
+#[derive(Debug, StructOpt)]
+struct OutputStoreOpt {
+    /// Shard time in seconds of created stores. Defaults to the shard time
+    /// of the (first) store read from.
+    #[structopt(long)]
+    shard_time_s: Option<u64>,
+    #[structopt(long)]
+    compress: bool,
+    #[structopt(long)]
+    dict_compress: bool,
+    #[structopt(long, conflicts_with = "dict-compress")]
+    delta_compress: bool,
+}
+
+impl OutputStoreOpt {
+    /// Options for writing to `dest`, or to new stores if None. Unless
+    /// requested otherwise, a new store gets the shard time of `src` and an
+    /// existing one keeps its own.
+    fn write_options(&self, src: &Path, dest: Option<&Path>) -> Result<store::merge::WriteOptions> {
+        let shard_time = match self.shard_time_s {
+            Some(shard_time) => Some(shard_time),
+            None if dest.map_or(false, Path::is_dir) => None,
+            None => Some(store::get_shard_time(src)?),
+        };
+        Ok(store::merge::WriteOptions {
+            shard_time,
+            compression_mode: compression_mode(
+                self.compress,
+                self.dict_compress,
+                self.delta_compress,
+            ),
+            format: store::Format::Cbor,
+        })
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -191,6 +472,25 @@ enum DebugCommand {
         port: Option<u16>,
         #[structopt(long)]
         compress: bool,
+        #[structopt(long)]
+        dict_compress: bool,
+        #[structopt(long, conflicts_with = "dict-compress")]
+        delta_compress: bool,
+    },
+    /// Check every shard of a store for corrupted or truncated entries,
+    /// out of order timestamps and orphaned data.
+    Fsck {
+        /// Store to check instead of the configured one
+        #[structopt(long)]
+        store_dir: Option<PathBuf>,
+        /// Rewrite the index of damaged shards so it only contains readable
+        /// entries. Shards in use by a running recorder are not repaired.
+        #[structopt(long)]
+        repair: bool,
+        /// Also rewrite the data file of repaired shards so it only contains
+        /// the data of readable entries. Implies --repair.
+        #[structopt(long)]
+        compact: bool,
     },
 }
 
@@ -325,6 +625,236 @@ fn cleanup_store(
     Ok(())
 }
 
+/// How `record` downsamples old data into the rollup store
+struct RollupOptions {
+    /// Age of data to roll up
+    after: Duration,
+    /// Interval between rolled up frames
+    interval: Duration,
+    retention: Option<Duration>,
+}
+
+/// Roll up data that became old enough since the last call and discard
+/// rolled up data according to retention. Failing to roll up is not fatal
+/// as data is rolled up on the next call, if still around.
+fn rollup_old_data(
+    store_dir: &Path,
+    rollup_store: &mut store::StoreWriter,
+    options: &RollupOptions,
+    logger: &slog::Logger,
+) -> Result<()> {
+    match store::rollup::rollup(
+        store_dir,
+        rollup_store,
+        SystemTime::now() - options.after,
+        options.interval,
+        logger,
+    ) {
+        Ok(0) => {}
+        Ok(n) => debug!(logger, "Rolled up {} frames", n),
+        Err(e) => error!(logger, "Failed to roll up data: {:#}", e),
+    }
+    cleanup_store(
+        rollup_store,
+        logger,
+        /* store_size_limit */ None,
+        options.retention,
+    )
+}
+
+/// When `record` temporarily samples at a higher rate to catch short spikes.
+/// SIGUSR1 always starts a burst.
+struct BurstOptions {
+    /// Interval between samples of a burst
+    interval: Duration,
+    /// How long a burst lasts after its last trigger
+    duration: Duration,
+    /// Start a burst when the memory pressure (some avg10) of the root
+    /// cgroup reaches this percentage
+    memory_pressure_pct: Option<f64>,
+    /// Start a burst when a cgroup had an OOM kill since the last sample
+    oom_kill: bool,
+    /// Start a burst when one of these fires
+    psi_triggers: Vec<PsiTrigger>,
+}
+
+/// A kernel PSI trigger on a pressure file of the root cgroup
+#[derive(Clone, Debug)]
+struct PsiTrigger {
+    resource: cgroupfs::PressureResource,
+    stall_type: cgroupfs::StallType,
+    threshold: Duration,
+    window: Duration,
+}
+
+impl PsiTrigger {
+    fn register(&self, cgroup: &cgroupfs::CgroupReader) -> Result<cgroupfs::PressureTrigger> {
+        cgroup
+            .register_pressure_trigger(self.resource, self.stall_type, self.threshold, self.window)
+            .with_context(|| format!("Failed to register PSI trigger {}", self))
+    }
+
+    /// Name of the trigger when it starts a burst
+    fn burst_trigger(&self) -> &'static str {
+        match self.resource {
+            cgroupfs::PressureResource::Cpu => "cpu PSI trigger",
+            cgroupfs::PressureResource::Io => "io PSI trigger",
+            cgroupfs::PressureResource::Memory => "memory PSI trigger",
+        }
+    }
+}
+
+impl std::fmt::Display for PsiTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.resource.name(),
+            self.stall_type.name(),
+            self.threshold.as_millis(),
+            self.window.as_millis()
+        )
+    }
+}
+
+impl FromStr for PsiTrigger {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.len() != 4 {
+            bail!(
+                "Invalid PSI trigger: {}, expected <resource>:<some|full>:<stall_ms>:<window_ms>",
+                s
+            );
+        }
+        let resource = match parts[0] {
+            "cpu" => cgroupfs::PressureResource::Cpu,
+            "io" => cgroupfs::PressureResource::Io,
+            "memory" => cgroupfs::PressureResource::Memory,
+            _ => bail!(
+                "Unknown PSI resource: {}, expected cpu, io or memory",
+                parts[0]
+            ),
+        };
+        let stall_type = match parts[1] {
+            "some" => cgroupfs::StallType::Some,
+            "full" => cgroupfs::StallType::Full,
+            _ => bail!("Unknown stall type: {}, expected some or full", parts[1]),
+        };
+        let millis = |part: &str| {
+            part.parse::<u64>()
+                .map(Duration::from_millis)
+                .with_context(|| format!("Invalid milliseconds in PSI trigger: {}", part))
+        };
+        let threshold = millis(parts[2])?;
+        let window = millis(parts[3])?;
+        if threshold > window {
+            bail!("Stall time of PSI trigger {} exceeds its window", s);
+        }
+        Ok(Self {
+            resource,
+            stall_type,
+            threshold,
+            window,
+        })
+    }
+}
+
+/// Wait for PSI triggers to fire and start a capture burst each time. A
+/// trigger fires about once per window for as long as the stall lasts, so
+/// only its first firing after a quiet period is annotated.
+fn watch_psi_triggers(
+    logger: slog::Logger,
+    store_dir: PathBuf,
+    specs: Vec<PsiTrigger>,
+    triggers: Vec<cgroupfs::PressureTrigger>,
+    burst_sender: Sender<&'static str>,
+) {
+    let mut last_fired: Vec<Option<Instant>> = vec![None; triggers.len()];
+    loop {
+        let fired = match cgroupfs::wait_pressure_triggers(&triggers, None) {
+            Ok(fired) => fired,
+            Err(e) => {
+                error!(logger, "Stopped watching PSI triggers: {:#}", e);
+                return;
+            }
+        };
+        let now = Instant::now();
+        for idx in fired {
+            let spec = &specs[idx];
+            debug!(logger, "PSI trigger {} fired", spec);
+            let stall_started =
+                last_fired[idx].map_or(true, |last| now.duration_since(last) > spec.window * 2);
+            last_fired[idx] = Some(now);
+            if stall_started {
+                let text = format!(
+                    "{} {} stall of {}ms within {}ms",
+                    spec.resource.name(),
+                    spec.stall_type.name(),
+                    spec.threshold.as_millis(),
+                    spec.window.as_millis()
+                );
+                if let Err(e) = store::annotation::append_annotation(
+                    &store_dir,
+                    SystemTime::now(),
+                    "psi",
+                    &text,
+                ) {
+                    error!(logger, "{:#}", e);
+                }
+            }
+            if burst_sender.send(spec.burst_trigger()).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Whether any cgroup present in both samples had an OOM kill in between
+fn cgroup_oom_killed(last: &model::CgroupSample, sample: &model::CgroupSample) -> bool {
+    let oom_kill = |s: &model::CgroupSample| s.memory_events.as_ref().and_then(|e| e.oom_kill);
+    if let (Some(last), Some(curr)) = (oom_kill(last), oom_kill(sample)) {
+        if curr > last {
+            return true;
+        }
+    }
+    match (last.children.as_ref(), sample.children.as_ref()) {
+        (Some(last_children), Some(children)) => children.iter().any(|(name, child)| {
+            last_children
+                .get(name)
+                .map_or(false, |last_child| cgroup_oom_killed(last_child, child))
+        }),
+        _ => false,
+    }
+}
+
+/// Get the trigger in `sample` that starts (or extends) a burst, if any
+fn get_burst_trigger(
+    options: &BurstOptions,
+    last: Option<&model::Sample>,
+    sample: &model::Sample,
+) -> Option<&'static str> {
+    if let Some(threshold) = options.memory_pressure_pct {
+        let pressure = sample
+            .cgroup
+            .pressure
+            .as_ref()
+            .and_then(|p| p.memory.some.avg10);
+        if pressure.map_or(false, |p| p >= threshold) {
+            return Some("memory pressure");
+        }
+    }
+    if options.oom_kill
+        && last.map_or(false, |last| {
+            cgroup_oom_killed(&last.cgroup, &sample.cgroup)
+        })
+    {
+        return Some("oom kill");
+    }
+    None
+}
+
 /// Special Error that indicates the program should stop now. It represents an
 /// actual signal, e.g. SIGINT, SIGTERM, that is handled by below and thus below
 /// can shutdown gracefully.
@@ -454,6 +984,7 @@ fn real_main(init: init::InitToken) {
         interval_s: 5,
         host: None,
         port: None,
+        collect_threads: false,
     });
     let rc = match cmd {
         Command::External(command) => commands::run_command(init, debug, below_config, command),
@@ -461,6 +992,7 @@ fn real_main(init: init::InitToken) {
             ref interval_s,
             ref host,
             ref port,
+            ref collect_threads,
         } => {
             let host = host.clone();
             let port = port.clone();
@@ -479,6 +1011,7 @@ fn real_main(init: init::InitToken) {
                         below_config,
                         host,
                         port,
+                        *collect_threads,
                     )
                 },
             )
@@ -487,12 +1020,27 @@ fn real_main(init: init::InitToken) {
             ref interval_s,
             ref retain_for_s,
             ref store_size_limit,
+            ref shard_time_s,
+            ref rollup_after_s,
+            ref rollup_interval_s,
+            ref rollup_retain_for_s,
             ref collect_io_stat,
+            ref collect_threads,
             ref port,
+            ref serve_remote,
+            ref serve_address,
             ref skew_detection_threshold_ms,
             ref disable_disk_stat,
             ref disable_exitstats,
             ref compress,
+            ref dict_compress,
+            ref delta_compress,
+            ref openmetrics_address,
+            ref burst_interval_ms,
+            ref burst_duration_s,
+            ref burst_on_memory_pressure,
+            ref burst_on_oom_kill,
+            ref psi_trigger,
         } => {
             logutil::set_current_log_target(logutil::TargetLog::Term);
             run(
@@ -502,6 +1050,18 @@ fn real_main(init: init::InitToken) {
                 Service::On(*port),
                 RedirectLogOnFail::Off,
                 |_, below_config, logger, errs| {
+                    #[cfg(not(fbcode_build))]
+                    if *serve_remote {
+                        // Keep recording even if remote viewing is unavailable
+                        if let Err(e) = store::start_remote_store_server(
+                            logger.clone(),
+                            below_config.store_dir.clone(),
+                            serve_address.unwrap_or(std::net::Ipv4Addr::LOCALHOST.into()),
+                            *port,
+                        ) {
+                            error!(logger, "{:#}", e);
+                        }
+                    }
                     record(
                         logger,
                         errs,
@@ -509,12 +1069,27 @@ fn real_main(init: init::InitToken) {
                         below_config,
                         retain_for_s.map(|r| Duration::from_secs(r as u64)),
                         *store_size_limit,
+                        *shard_time_s,
+                        rollup_after_s.map(|after| RollupOptions {
+                            after: Duration::from_secs(after),
+                            interval: Duration::from_secs(*rollup_interval_s),
+                            retention: rollup_retain_for_s.map(Duration::from_secs),
+                        }),
                         *collect_io_stat,
+                        *collect_threads,
                         Duration::from_millis(*skew_detection_threshold_ms),
                         debug,
                         *disable_disk_stat,
                         *disable_exitstats,
-                        *compress,
+                        compression_mode(*compress, *dict_compress, *delta_compress),
+                        openmetrics_address.clone(),
+                        BurstOptions {
+                            interval: Duration::from_millis(*burst_interval_ms),
+                            duration: Duration::from_secs(*burst_duration_s),
+                            memory_pressure_pct: *burst_on_memory_pressure,
+                            oom_kill: *burst_on_oom_kill,
+                            psi_triggers: psi_trigger.clone(),
+                        },
                     )
                 },
             )
@@ -523,11 +1098,15 @@ fn real_main(init: init::InitToken) {
             ref time,
             ref host,
             ref port,
+            ref file,
+            ref store_dir,
             ref yesterdays,
         } => {
             let time = time.clone();
             let host = host.clone();
             let port = port.clone();
+            let file = file.clone();
+            let store_dirs = store_dir.clone();
             let days_adjuster = yesterdays.clone();
             run(
                 init,
@@ -536,7 +1115,17 @@ fn real_main(init: init::InitToken) {
                 Service::Off,
                 RedirectLogOnFail::Off,
                 |_, below_config, logger, errs| {
-                    replay(logger, errs, time, below_config, host, port, days_adjuster)
+                    replay(
+                        logger,
+                        errs,
+                        time,
+                        below_config,
+                        host,
+                        port,
+                        file,
+                        store_dirs,
+                        days_adjuster,
+                    )
                 },
             )
         }
@@ -561,6 +1150,8 @@ fn real_main(init: init::InitToken) {
                 ref host,
                 ref port,
                 ref compress,
+                ref dict_compress,
+                ref delta_compress,
             } => {
                 let start_time = start_time.clone();
                 let end_time = end_time.clone();
@@ -584,20 +1175,41 @@ fn real_main(init: init::InitToken) {
                             to_store_dir,
                             host,
                             port,
-                            *compress,
+                            compression_mode(*compress, *dict_compress, *delta_compress),
                         )
                     },
                 )
             }
+            DebugCommand::Fsck {
+                ref store_dir,
+                ref repair,
+                ref compact,
+            } => {
+                let store_dir = store_dir
+                    .clone()
+                    .unwrap_or_else(|| below_config.store_dir.clone());
+                run(
+                    init,
+                    debug,
+                    below_config,
+                    Service::Off,
+                    RedirectLogOnFail::Off,
+                    |_, _below_config, _logger, _errs| {
+                        fsck(store_dir, *repair || *compact, *compact)
+                    },
+                )
+            }
         },
         Command::Dump {
             ref host,
             ref port,
+            ref file,
             ref cmd,
         } => {
             let store_dir = below_config.store_dir.clone();
             let host = host.clone();
             let port = port.clone();
+            let file = file.clone();
             let cmd = cmd.clone();
             run(
                 init,
@@ -606,10 +1218,101 @@ fn real_main(init: init::InitToken) {
                 Service::Off,
                 RedirectLogOnFail::Off,
                 |_, _below_config, logger, errs| {
-                    dump::run(logger, errs, store_dir, host, port, cmd)
+                    dump::run(logger, errs, store_dir, host, port, file, cmd)
                 },
             )
         }
+        Command::Serve {
+            ref address,
+            ref host,
+            ref port,
+        } => {
+            let store_dir = below_config.store_dir.clone();
+            let address = address.clone();
+            let host = host.clone();
+            let port = port.clone();
+            run(
+                init,
+                debug,
+                below_config,
+                Service::Off,
+                RedirectLogOnFail::Off,
+                |_, _below_config, logger, errs| {
+                    dump::serve::serve(logger, errs, store_dir, host, port, address)
+                },
+            )
+        }
+        Command::Export {
+            ref host,
+            ref port,
+            ref opts,
+        } => {
+            let store_dir = below_config.store_dir.clone();
+            let host = host.clone();
+            let port = port.clone();
+            let opts = opts.clone();
+            run(
+                init,
+                debug,
+                below_config,
+                Service::Off,
+                RedirectLogOnFail::Off,
+                |_, _below_config, logger, errs| {
+                    dump::export::run(logger, errs, store_dir, host, port, opts)
+                },
+            )
+        }
+        Command::Diff {
+            ref t1,
+            ref t2,
+            ref host,
+            ref port,
+            ref file,
+            ref sort,
+            ref top,
+        } => {
+            let t1 = t1.clone();
+            let t2 = t2.clone();
+            let host = host.clone();
+            let port = port.clone();
+            let file = file.clone();
+            run(
+                init,
+                debug,
+                below_config,
+                Service::Off,
+                RedirectLogOnFail::Off,
+                |_, below_config, logger, _errs| {
+                    diff(logger, below_config, t1, t2, host, port, file, *sort, *top)
+                },
+            )
+        }
+        Command::Annotate {
+            ref message,
+            ref source,
+        } => run(
+            init,
+            debug,
+            below_config,
+            Service::Off,
+            RedirectLogOnFail::Off,
+            |_, below_config, _logger, _errs| {
+                store::annotation::append_annotation(
+                    &below_config.store_dir,
+                    SystemTime::now(),
+                    source,
+                    message,
+                )
+            },
+        ),
+        Command::Store { ref cmd } => run(
+            init,
+            debug,
+            below_config,
+            Service::Off,
+            RedirectLogOnFail::Off,
+            |_, _below_config, logger, _errs| store_command(logger, cmd),
+        ),
     };
     exit(rc);
 }
@@ -621,23 +1324,68 @@ fn replay(
     below_config: &BelowConfig,
     host: Option<String>,
     port: Option<u16>,
+    snapshot: Option<PathBuf>,
+    store_dirs: Vec<PathBuf>,
     days_adjuster: Option<String>,
 ) -> Result<()> {
     let timestamp =
         cliutil::system_time_from_date_and_adjuster(time.as_str(), days_adjuster.as_deref())?;
 
-    let mut advance = if let Some(host) = host {
-        new_advance_remote(logger.clone(), host, port, timestamp)?
+    // Annotations are only kept by local stores
+    let annotation_dir = if snapshot.is_some() || host.is_some() || store_dirs.len() > 1 {
+        None
+    } else {
+        Some(
+            store_dirs
+                .first()
+                .cloned()
+                .unwrap_or_else(|| below_config.store_dir.clone()),
+        )
+    };
+
+    // Name and Advance of each replayed host
+    let mut advances = if let Some(snapshot) = snapshot {
+        vec![(
+            snapshot.display().to_string(),
+            new_advance_snapshot(logger.clone(), &snapshot, timestamp)?,
+        )]
+    } else if let Some(host) = host {
+        host.split(',')
+            .map(|host| {
+                new_advance_remote(logger.clone(), host.to_owned(), port, timestamp)
+                    .map(|advance| (host.to_owned(), advance))
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else if !store_dirs.is_empty() {
+        store_dirs
+            .into_iter()
+            .map(|dir| {
+                (
+                    dir.display().to_string(),
+                    new_advance_local(logger.clone(), dir, timestamp),
+                )
+            })
+            .collect()
     } else {
-        new_advance_local(logger.clone(), below_config.store_dir.clone(), timestamp)
+        vec![(
+            below_config.store_dir.display().to_string(),
+            new_advance_local(logger.clone(), below_config.store_dir.clone(), timestamp),
+        )]
     };
 
     // Fill the last_sample for forward iteration. If no previous sample exists,
     // this should have no effect.
-    advance.initialize();
+    for (_, advance) in advances.iter_mut() {
+        advance.initialize();
+    }
 
-    let model = match advance.jump_sample_to(timestamp) {
-        Some(m) => m,
+    // Start with the first host that has data, others may join later
+    let initial = advances
+        .iter_mut()
+        .enumerate()
+        .find_map(|(idx, (_, advance))| advance.jump_sample_to(timestamp).map(|m| (idx, m)));
+    let (current_host, model) = match initial {
+        Some(initial) => initial,
         None => bail!(
             "No initial sample could be found!\n\
             You may have provided a time in the future or no data was recorded during the provided time. \
@@ -647,11 +1395,25 @@ fn replay(
     };
 
     cliutil::check_initial_sample_time_with_requested_time(model.timestamp, timestamp);
+    for (name, _) in &advances[..current_host] {
+        warn!(logger, "No data found for {} at the requested time", name);
+    }
 
-    let mut view = view::View::new_with_advance(
-        model,
-        view::ViewMode::Replay(Rc::new(RefCell::new(advance))),
-    );
+    let mut advances = advances
+        .into_iter()
+        .map(|(name, advance)| (name, Rc::new(RefCell::new(advance))))
+        .collect::<Vec<_>>();
+    let mut view = if advances.len() > 1 {
+        view::View::new_with_hosts(model, advances, current_host)
+    } else {
+        view::View::new_with_advance(model, view::ViewMode::Replay(advances.remove(0).1))
+    };
+    if let Some(dir) = annotation_dir {
+        match store::annotation::read_annotations(&dir, SystemTime::UNIX_EPOCH, SystemTime::now()) {
+            Ok(annotations) => view.set_annotations(annotations),
+            Err(e) => warn!(logger, "Failed to read annotations: {:#}", e),
+        }
+    }
     logutil::set_current_log_target(logutil::TargetLog::File);
 
     let sink = view.cb_sink().clone();
@@ -671,6 +1433,100 @@ fn replay(
     view.run()
 }
 
+fn diff(
+    logger: slog::Logger,
+    below_config: &BelowConfig,
+    t1: String,
+    t2: String,
+    host: Option<String>,
+    port: Option<u16>,
+    snapshot: Option<PathBuf>,
+    sort: model::DiffSortKey,
+    top: Option<usize>,
+) -> Result<()> {
+    let (begin, end) =
+        cliutil::system_time_range_from_date_and_adjuster(t1.as_str(), Some(t2.as_str()), None)?;
+
+    let mut advance = if let Some(snapshot) = snapshot {
+        new_advance_snapshot(logger, &snapshot, begin)?
+    } else if let Some(host) = host {
+        new_advance_remote(logger, host, port, begin)?
+    } else {
+        new_advance_local(logger, below_config.store_dir.clone(), begin)
+    };
+
+    let (begin_model, window_model) = match advance.get_window_models(begin, end) {
+        Some(models) => models,
+        None => bail!(
+            "Could not find two samples between {} and {}",
+            common::util::systemtime_to_datetime(begin),
+            common::util::systemtime_to_datetime(end)
+        ),
+    };
+    let mut diff = model::ModelDiff::new(&begin_model, &window_model);
+    diff.sort(sort);
+
+    println!(
+        "Changes from {} to {}\n",
+        common::util::systemtime_to_datetime(diff.begin),
+        common::util::systemtime_to_datetime(diff.end)
+    );
+    print!("{}", render::diff::render_model_diff(&diff, top));
+    Ok(())
+}
+
+/// Run the configured alert command for an alert that fired or resolved,
+/// without blocking the recorder
+fn run_alert_command(logger: &slog::Logger, command: &str, rule: &str, state: &str, value: &str) {
+    let mut cmd = std::process::Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .env("BELOW_ALERT_RULE", rule)
+        .env("BELOW_ALERT_STATE", state)
+        .env("BELOW_ALERT_VALUE", value);
+    let logger = logger.clone();
+    let command = command.to_owned();
+    thread::spawn(move || match cmd.status() {
+        Ok(status) if !status.success() => {
+            warn!(logger, "Alert command `{}` failed: {}", command, status)
+        }
+        Ok(_) => {}
+        Err(e) => error!(logger, "Failed to run alert command `{}`: {}", command, e),
+    });
+}
+
+/// Evaluate the alert rules against the latest model. Alerts that fired or
+/// resolved are logged, annotated in the store and passed to the alert
+/// command if one is configured.
+fn evaluate_alerts(
+    logger: &slog::Logger,
+    alerts: &mut [model::AlertState],
+    model: &model::Model,
+    below_config: &BelowConfig,
+) {
+    for alert in alerts.iter_mut() {
+        let (state, value) = match alert.evaluate(model) {
+            Some(model::AlertEvent::Fired { value }) => ("firing", Some(value)),
+            Some(model::AlertEvent::Resolved { value }) => ("resolved", value),
+            None => continue,
+        };
+        let value = value.map_or_else(|| "?".to_owned(), |v| v.to_string());
+        let text = format!("{}: {} (value {})", state, alert.rule.rule, value);
+        warn!(logger, "Alert {}", text);
+        if let Err(e) = store::annotation::append_annotation(
+            &below_config.store_dir,
+            model.timestamp,
+            "alert",
+            &text,
+        ) {
+            error!(logger, "{:#}", e);
+        }
+        if let Some(command) = below_config.alerts.command.as_ref() {
+            run_alert_command(logger, command, &alert.rule.rule, state, &value);
+        }
+    }
+}
+
 fn record(
     logger: slog::Logger,
     errs: Receiver<Error>,
@@ -678,12 +1534,17 @@ fn record(
     below_config: &BelowConfig,
     retention: Option<Duration>,
     store_size_limit: Option<u64>,
+    shard_time: Option<u64>,
+    rollup: Option<RollupOptions>,
     collect_io_stat: bool,
+    collect_threads: bool,
     skew_detection_threshold: Duration,
     debug: bool,
     disable_disk_stat: bool,
     disable_exitstats: bool,
-    compress: bool,
+    compression_mode: store::CompressionMode,
+    openmetrics_address: Option<String>,
+    burst: BurstOptions,
 ) -> Result<()> {
     debug!(logger, "Starting up!");
 
@@ -691,9 +1552,49 @@ fn record(
         bump_memlock_rlimit()?;
     }
 
-    let mut store =
-        store::StoreWriter::new(&below_config.store_dir, compress, store::Format::Cbor)?;
+    let mut store = match shard_time {
+        Some(shard_time) => store::StoreWriter::new_with_shard_time(
+            &below_config.store_dir,
+            shard_time,
+            compression_mode,
+            store::Format::Cbor,
+        )?,
+        None => store::StoreWriter::new(
+            &below_config.store_dir,
+            compression_mode,
+            store::Format::Cbor,
+        )?,
+    };
+    let mut rollup = match rollup {
+        Some(options) => {
+            if let Some(retention) = retention {
+                if options.after > retention {
+                    bail!("--rollup-after-s must not exceed --retain-for-s");
+                }
+            }
+            // Shards of the rollup store line up with the primary store's
+            let rollup_store = store::StoreWriter::open(
+                &below_config.rollup_store_dir,
+                SystemTime::now(),
+                shard_time,
+                compression_mode,
+                store::Format::Cbor,
+            )?;
+            Some((rollup_store, options, None))
+        }
+        None => None,
+    };
     let mut stats = statistics::Statistics::new();
+    let mut alerts = below_config
+        .alerts
+        .rules
+        .iter()
+        .map(|rule| {
+            rule.parse::<model::AlertRule>()
+                .map(model::AlertState::new)
+                .with_context(|| format!("Invalid alert rule in below.conf: {}", rule))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     let (exit_buffer, bpf_errs) = if disable_exitstats {
         (Arc::new(Mutex::new(procfs::PidMap::new())), None)
@@ -702,6 +1603,52 @@ fn record(
     };
     let mut bpf_err_warned = false;
 
+    // Latest model for the OpenMetrics exporter. Models are only computed
+    // when the exporter is enabled or alert rules are configured.
+    let exporter_model = match openmetrics_address {
+        Some(address) => {
+            let latest = Arc::new(Mutex::new(None));
+            dump::openmetrics::start_exporter(logger.clone(), &address, latest.clone())?;
+            Some(latest)
+        }
+        None => None,
+    };
+    let mut last_sample: Option<(model::Sample, Instant)> = None;
+    let keep_last_sample = exporter_model.is_some() || !alerts.is_empty() || burst.oom_kill;
+
+    // Burst triggers that are not found in samples are sent over this
+    // channel, which also interrupts the sleep between samples
+    let (burst_sender, burst_receiver) = channel();
+    let mut signals = Signals::new(&[signal_hook::consts::SIGUSR1])
+        .context("Failed to register SIGUSR1 handler")?;
+    let signal_sender = burst_sender.clone();
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            if signal_sender.send("SIGUSR1").is_err() {
+                break;
+            }
+        }
+    });
+    if !burst.psi_triggers.is_empty() {
+        let root = cgroupfs::CgroupReader::new(below_config.cgroup_root.clone())?;
+        let triggers = burst
+            .psi_triggers
+            .iter()
+            .map(|trigger| trigger.register(&root))
+            .collect::<Result<Vec<_>>>()?;
+        let psi_logger = logger.clone();
+        let store_dir = below_config.store_dir.clone();
+        let specs = burst.psi_triggers.clone();
+        let psi_sender = burst_sender.clone();
+        thread::spawn(move || {
+            watch_psi_triggers(psi_logger, store_dir, specs, triggers, psi_sender)
+        });
+    }
+    let mut burst_trigger: Option<&'static str> = None;
+    // End of the current burst
+    let mut burst_until: Option<Instant> = None;
+    let mut last_timestamp: Option<SystemTime> = None;
+
     // Handle cgroup filter from conf and generate Regex
     let cgroup_re = if !below_config.cgroup_filter_out.is_empty() {
         Some(
@@ -732,6 +1679,25 @@ fn record(
         }
 
         let collect_instant = Instant::now();
+        if let Some(trigger) = burst_trigger.take() {
+            if burst_until.is_none() {
+                let text = format!("burst started ({})", trigger);
+                warn!(logger, "Capture {}", text);
+                if let Err(e) = store::annotation::append_annotation(
+                    &below_config.store_dir,
+                    SystemTime::now(),
+                    "burst",
+                    &text,
+                ) {
+                    error!(logger, "{:#}", e);
+                }
+            }
+            burst_until = Some(collect_instant + burst.duration);
+        }
+        if burst_until.map_or(false, |until| until <= collect_instant) {
+            debug!(logger, "Capture burst ended");
+            burst_until = None;
+        }
 
         let collected_sample = model::collect_sample(
             &below_config.cgroup_root,
@@ -740,6 +1706,8 @@ fn record(
             &logger,
             disable_disk_stat,
             &cgroup_re,
+            &below_config.extra_cgroup_files,
+            collect_threads,
         );
         let post_collect_sys_time = SystemTime::now();
         let post_collect_instant = Instant::now();
@@ -758,17 +1726,49 @@ fn record(
 
         match collected_sample {
             Ok(s) => {
-                match store.put(
+                if let Some(trigger) =
+                    get_burst_trigger(&burst, last_sample.as_ref().map(|(last, _)| last), &s)
+                {
+                    burst_trigger = Some(trigger);
+                }
+                if exporter_model.is_some() || !alerts.is_empty() {
+                    let model = model::Model::new(
+                        post_collect_sys_time,
+                        &s,
+                        last_sample
+                            .as_ref()
+                            .map(|(last, i)| (last, post_collect_instant.duration_since(*i))),
+                    );
+                    evaluate_alerts(&logger, &mut alerts, &model, below_config);
+                    if let Some(exporter_model) = exporter_model.as_ref() {
+                        *exporter_model.lock().unwrap() = Some(model);
+                    }
+                }
+                // Samples are stored at whole seconds unless they are part
+                // of a burst, or the second is already taken, e.g. by the
+                // last sample of a burst
+                let whole_second = common::util::get_system_time(common::util::get_unix_timestamp(
                     post_collect_sys_time,
-                    &DataFrame { sample: s },
-                    logger.clone(),
-                ) {
+                ));
+                let timestamp = if burst_until.is_none()
+                    && last_timestamp.map_or(true, |last| whole_second > last)
+                {
+                    whole_second
+                } else {
+                    post_collect_sys_time
+                };
+                let frame = DataFrame { sample: s };
+                match store.put(timestamp, &frame, logger.clone()) {
                     Ok(/* new shard */ true) => {
                         cleanup_store(&store, &logger, store_size_limit, /* retention */ None)?
                     }
                     Ok(/* new shard */ false) => {}
                     Err(e) => error!(logger, "{:#}", e),
                 }
+                last_timestamp = Some(timestamp);
+                if keep_last_sample {
+                    last_sample = Some((frame.sample, post_collect_instant));
+                }
             }
             Err(e) => {
                 // Handle cgroupfs errors
@@ -782,6 +1782,15 @@ fn record(
             }
         };
 
+        // Roll up data before retention discards it. Nothing new can be
+        // rolled up before another interval passed, e.g. during a burst.
+        if let Some((rollup_store, options, last_rollup)) = rollup.as_mut() {
+            if last_rollup.map_or(true, |last: Instant| last.elapsed() >= options.interval) {
+                rollup_old_data(&below_config.store_dir, rollup_store, options, &logger)?;
+                *last_rollup = Some(Instant::now());
+            }
+        }
+
         // Only check against retention and not size limit. Size limit is only
         // checked on creation of successful write to a new shard.
         cleanup_store(&store, &logger, /* store_size_limit */ None, retention)?;
@@ -789,13 +1798,18 @@ fn record(
         stats.report_store_size(below_config.store_dir.as_path());
 
         let collect_duration = Instant::now().duration_since(collect_instant);
-        // Sleep for at least 1s to avoid sample collision
-        let sleep_duration = if interval > collect_duration {
+        let sleep_duration = if burst_until.is_some() || burst_trigger.is_some() {
+            burst.interval.saturating_sub(collect_duration)
+        } else if interval > collect_duration {
+            // Sleep for at least 1s to avoid sample collision
             std::cmp::max(Duration::from_secs(1), interval - collect_duration)
         } else {
             Duration::from_secs(1)
         };
-        std::thread::sleep(sleep_duration);
+        // A trigger cuts the sleep short to start the burst right away
+        if let Ok(trigger) = burst_receiver.recv_timeout(sleep_duration) {
+            burst_trigger = Some(trigger);
+        }
     }
 }
 
@@ -805,6 +1819,7 @@ fn live_local(
     interval: Duration,
     debug: bool,
     below_config: &BelowConfig,
+    collect_threads: bool,
 ) -> Result<()> {
     match bump_memlock_rlimit() {
         Err(e) => {
@@ -823,7 +1838,9 @@ fn live_local(
     let mut bpf_err_warned = false;
 
     let mut collector =
-        model::Collector::new_with_cgroup_root(below_config.cgroup_root.clone(), exit_buffer);
+        model::Collector::new_with_cgroup_root(below_config.cgroup_root.clone(), exit_buffer)
+            .with_extra_cgroup_files(below_config.extra_cgroup_files.clone())
+            .with_threads(collect_threads);
     logutil::set_current_log_target(logutil::TargetLog::File);
     // Prepare advance obj for pause mode
     let mut adv = new_advance_local(
@@ -963,11 +1980,12 @@ fn live(
     below_config: &BelowConfig,
     host: Option<String>,
     port: Option<u16>,
+    collect_threads: bool,
 ) -> Result<()> {
     if let Some(host) = host {
         live_remote(logger, errs, interval, host, port)
     } else {
-        live_local(logger, errs, interval, debug, below_config)
+        live_local(logger, errs, interval, debug, below_config, collect_threads)
     }
 }
 
@@ -1003,6 +2021,169 @@ fn dump_store(
     Ok(())
 }
 
+fn parse_optional_time(time: &Option<String>) -> Result<Option<SystemTime>> {
+    time.as_deref()
+        .map(cliutil::system_time_from_date)
+        .transpose()
+}
+
+fn store_command(logger: slog::Logger, cmd: &StoreCommand) -> Result<()> {
+    match cmd {
+        StoreCommand::Merge {
+            stores,
+            to,
+            begin,
+            end,
+            allow_mixed_hosts,
+            output,
+        } => {
+            let stats = store::merge::merge(
+                stores.as_slice(),
+                to,
+                parse_optional_time(begin)?.unwrap_or(std::time::UNIX_EPOCH),
+                parse_optional_time(end)?,
+                *allow_mixed_hosts,
+                &output.write_options(&stores[0], Some(to.as_path()))?,
+                &logger,
+            )?;
+            println!(
+                "Merged {} frames into {} ({} duplicates skipped)",
+                stats.copied,
+                to.display(),
+                stats.duplicates
+            );
+        }
+        StoreCommand::Slice {
+            store: src,
+            to,
+            begin,
+            end,
+            output,
+        } => {
+            if to.is_dir() {
+                bail!("{} already exists", to.display());
+            }
+            let (begin, end) =
+                cliutil::system_time_range_from_date_and_adjuster(begin, Some(end.as_str()), None)?;
+            let stats = store::merge::merge(
+                &[src],
+                to,
+                begin,
+                Some(end),
+                false,
+                &output.write_options(src, Some(to.as_path()))?,
+                &logger,
+            )?;
+            println!("Copied {} frames into {}", stats.copied, to.display());
+        }
+        StoreCommand::Split {
+            store: src,
+            to,
+            every_s,
+            begin,
+            end,
+            output,
+        } => {
+            let stores = store::merge::split(
+                src,
+                to,
+                Duration::from_secs(*every_s),
+                parse_optional_time(begin)?.unwrap_or(std::time::UNIX_EPOCH),
+                parse_optional_time(end)?,
+                &output.write_options(src, None)?,
+                &logger,
+            )?;
+            for (path, stats) in stores {
+                println!("Copied {} frames into {}", stats.copied, path.display());
+            }
+        }
+        StoreCommand::Snapshot {
+            store: src,
+            to,
+            begin,
+            end,
+            compress,
+        } => {
+            let (begin, end) =
+                cliutil::system_time_range_from_date_and_adjuster(begin, Some(end.as_str()), None)?;
+            let written = store::snapshot::snapshot(
+                src,
+                to,
+                begin,
+                end,
+                compression_mode(*compress, false, false),
+                store::Format::Cbor,
+                &logger,
+            )?;
+            println!("Wrote {} frames into {}", written, to.display());
+        }
+    }
+    Ok(())
+}
+
+fn fsck(store_dir: PathBuf, repair: bool, compact: bool) -> Result<()> {
+    let reports = store::fsck::check_store(&store_dir)?;
+    let mut nr_damaged = 0;
+    let mut nr_failed = 0;
+    for report in reports {
+        println!(
+            "shard {:011}: {}/{} valid entries",
+            report.shard, report.valid_entries, report.entries
+        );
+        for problem in &report.problems {
+            println!("    {}", problem);
+        }
+        if report.is_clean() {
+            continue;
+        }
+        // Orphaned data alone is only cleaned up, not counted as damage
+        let damaged = report.needs_repair();
+        if damaged {
+            nr_damaged += 1;
+        }
+        if repair {
+            match store::fsck::repair_shard(&store_dir, report.shard, compact) {
+                Ok(_) => println!("    repaired"),
+                Err(e) => {
+                    if damaged {
+                        nr_failed += 1;
+                    }
+                    println!("    repair failed: {:#}", e);
+                }
+            }
+        }
+    }
+
+    if nr_damaged == 0 {
+        println!("No problems found in {}", store_dir.display());
+    } else if !repair {
+        bail!("Found {} damaged shard(s)", nr_damaged);
+    } else if nr_failed != 0 {
+        bail!(
+            "Failed to repair {} of {} damaged shard(s)",
+            nr_failed,
+            nr_damaged
+        );
+    }
+    Ok(())
+}
+
+fn compression_mode(
+    compress: bool,
+    dict_compress: bool,
+    delta_compress: bool,
+) -> store::CompressionMode {
+    if delta_compress {
+        store::CompressionMode::ZstdDelta
+    } else if dict_compress {
+        store::CompressionMode::ZstdDictionary
+    } else if compress {
+        store::CompressionMode::Zstd
+    } else {
+        store::CompressionMode::None
+    }
+}
+
 fn convert_store(
     logger: slog::Logger,
     below_config: &BelowConfig,
@@ -1012,7 +2193,7 @@ fn convert_store(
     to_store_dir: PathBuf,
     host: Option<String>,
     port: Option<u16>,
-    compress: bool,
+    compression_mode: store::CompressionMode,
 ) -> Result<()> {
     let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
         start_time.as_str(),
@@ -1046,7 +2227,8 @@ fn convert_store(
         }
     };
 
-    let mut dest_store = store::StoreWriter::new(&to_store_dir, compress, store::Format::Cbor)?;
+    let mut dest_store =
+        store::StoreWriter::new(&to_store_dir, compression_mode, store::Format::Cbor)?;
 
     pb.set_message(&format!("Writing to local store at {:?}", to_store_dir));
 
@@ -1069,7 +2251,7 @@ fn convert_store(
             }
         }
         pb.set_position(common::util::get_unix_timestamp(cur_time) - timestamp_begin);
-        cur_time += Duration::from_secs(1); // To actually move forward
+        cur_time += Duration::from_nanos(1); // To actually move forward
     }
     pb.set_message(&format!("Done. Logged {} samples.", nr_samples));
     Ok(())
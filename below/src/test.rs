@@ -27,7 +27,8 @@ use common::util::fold_string;
 fn record_replay_integration() {
     let dir = TempDir::new("below_record_replay_test").expect("tempdir failed");
     let mut store =
-        store::StoreWriter::new(&dir, false, store::Format::Cbor).expect("Failed to create store");
+        store::StoreWriter::new(&dir, store::CompressionMode::None, store::Format::Cbor)
+            .expect("Failed to create store");
 
     let cgroup_root = Path::new(cgroupfs::DEFAULT_CG_ROOT).to_path_buf();
 
@@ -40,6 +41,8 @@ fn record_replay_integration() {
         &logger,
         false,
         &None,
+        &[],
+        false,
     )
     .expect("failed to collect sample");
 
@@ -119,7 +122,8 @@ fn record_replay_integration() {
 fn advance_forward_and_reverse() {
     let dir = TempDir::new("below_record_replay_test").expect("tempdir failed");
     let mut store =
-        store::StoreWriter::new(&dir, false, store::Format::Cbor).expect("Failed to create store");
+        store::StoreWriter::new(&dir, store::CompressionMode::None, store::Format::Cbor)
+            .expect("Failed to create store");
 
     let cgroup_root = Path::new(cgroupfs::DEFAULT_CG_ROOT).to_path_buf();
 
@@ -134,6 +138,8 @@ fn advance_forward_and_reverse() {
         &logger,
         false,
         &None,
+        &[],
+        false,
     )
     .expect("failed to collect sample");
     for i in 0..3 {
@@ -189,6 +195,8 @@ fn disable_io_stat() {
         &logger,
         false,
         &None,
+        &[],
+        false,
     )
     .expect("failed to collect sample");
 
@@ -206,6 +214,8 @@ fn disable_disk_stat() {
         &logger,
         true,
         &None,
+        &[],
+        false,
     )
     .expect("failed to collect sample");
     assert!(sample.system.disks.is_empty());
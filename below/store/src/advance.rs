@@ -12,17 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
 use anyhow::Result;
 use slog::{self, error};
 
-use common::util;
 use model::{self, Model};
 
+use crate::snapshot::SnapshotStore;
 use crate::{DataFrame, Direction, LocalStore, RemoteStore, Store};
 
+/// Distance to the target timestamp when moving past a sample. Samples are
+/// usually seconds apart, but samples of a capture burst are only fractions
+/// of a second apart, so stepping any further may skip samples.
+const SAMPLE_STEP: Duration = Duration::from_nanos(1);
+
 /// A SamplePackage consists of enough information to construct a Model.
 // A SamplePackage consists of the sample(newer_sample) at target timestamp
 // and a sample before it. This is useful since we will need at least two
@@ -120,7 +125,7 @@ trait ModelStore: Store {
 
         // Get and process the sample before target sample
         if let Some((older_ts, older_sample)) = self.extract_sample_and_log(
-            res_package.timestamp - Duration::from_secs(1),
+            res_package.timestamp - SAMPLE_STEP,
             Direction::Reverse,
             logger,
         ) {
@@ -151,6 +156,14 @@ impl ModelStore for RemoteStore {
     }
 }
 
+impl ModelStore for SnapshotStore {
+    type ModelType = Model;
+
+    fn to_model(&self, sample_package: &SamplePackage<DataFrame>) -> Option<Model> {
+        Some(sample_package.to_model())
+    }
+}
+
 /// The Advance data structure will be used as an operational
 /// bridge between controller and store.
 pub struct Advance<FrameType, MType> {
@@ -231,8 +244,8 @@ impl<FrameType, ModelType> Advance<FrameType, ModelType> {
     //   already reached the end.
     pub fn advance(&mut self, direction: Direction) -> Option<ModelType> {
         let target_timestamp = match direction {
-            Direction::Forward => self.target_timestamp + Duration::from_secs(1),
-            Direction::Reverse => self.target_timestamp - Duration::from_secs(1),
+            Direction::Forward => self.target_timestamp + SAMPLE_STEP,
+            Direction::Reverse => self.target_timestamp - SAMPLE_STEP,
         };
 
         let (next_timestamp, next_sample) =
@@ -284,17 +297,31 @@ impl<FrameType, ModelType> Advance<FrameType, ModelType> {
     // and after the timestamp. One exception here is the timestamp is in future, so
     // if we get None, we will try search backward
     pub fn jump_sample_to(&mut self, timestamp: SystemTime) -> Option<ModelType> {
-        let mut sample_package = self.store.get_adjacent_sample_at_timestamp(
-            timestamp,
-            Direction::Forward,
-            &self.logger,
-        );
+        self.jump_sample_in_direction(timestamp, Direction::Forward)
+    }
+
+    /// Jump to the latest sample at or before timestamp, or the first sample
+    /// after it if there is none.
+    pub fn jump_sample_at_or_before(&mut self, timestamp: SystemTime) -> Option<ModelType> {
+        self.jump_sample_in_direction(timestamp, Direction::Reverse)
+    }
+
+    /// Jump to the nearest sample from timestamp in direction, falling back
+    /// to the opposite direction
+    fn jump_sample_in_direction(
+        &mut self,
+        timestamp: SystemTime,
+        direction: Direction,
+    ) -> Option<ModelType> {
+        let mut sample_package =
+            self.store
+                .get_adjacent_sample_at_timestamp(timestamp, direction, &self.logger);
 
-        // timestamp is in future, find the latest sample
+        // e.g. timestamp is in future, find the latest sample
         if sample_package.is_none() {
             sample_package = self.store.get_adjacent_sample_at_timestamp(
                 timestamp,
-                Direction::Reverse,
+                direction.flip(),
                 &self.logger,
             );
         }
@@ -316,17 +343,55 @@ impl<FrameType, ModelType> Advance<FrameType, ModelType> {
 
     /// Syntactic sugar for jump sample forward
     pub fn jump_sample_forward(&mut self, duration: humantime::Duration) -> Option<ModelType> {
-        self.jump_sample_to(self.target_timestamp + Duration::from_secs(duration.as_secs()))
+        self.jump_sample_to(self.target_timestamp + *duration)
     }
 
     /// Syntactic sugar for jump sample backward
     pub fn jump_sample_backward(&mut self, duration: humantime::Duration) -> Option<ModelType> {
-        let gap = Duration::from_secs(duration.as_secs());
-        if util::get_unix_timestamp(self.target_timestamp) < gap.as_secs() {
+        let target_timestamp = self.target_timestamp.checked_sub(*duration)?;
+        if target_timestamp < SystemTime::UNIX_EPOCH {
             return None;
         }
 
-        self.jump_sample_to(self.target_timestamp - gap)
+        self.jump_sample_to(target_timestamp)
+    }
+
+    /// Get the models needed to compare the first sample at or after `begin`
+    /// with the last sample at or before `end`: the model of the first
+    /// sample on its own and the model of the last sample computed against
+    /// the first one. The current position is left as is.
+    pub fn get_window_models(
+        &mut self,
+        begin: SystemTime,
+        end: SystemTime,
+    ) -> Option<(ModelType, ModelType)> {
+        let (begin_ts, begin_sample) =
+            self.store
+                .extract_sample_and_log(begin, Direction::Forward, &self.logger)?;
+        let (end_ts, end_sample) =
+            self.store
+                .extract_sample_and_log(end, Direction::Reverse, &self.logger)?;
+        if end_ts <= begin_ts {
+            return None;
+        }
+
+        let begin_model = self.store.to_model(&SamplePackage::<FrameType>::new(
+            None,
+            begin_ts,
+            begin_sample,
+            begin_ts,
+        ))?;
+        // Samples are not Clone, so query the first one again
+        let (_, older_sample) =
+            self.store
+                .extract_sample_and_log(begin_ts, Direction::Forward, &self.logger)?;
+        let window_model = self.store.to_model(&SamplePackage::<FrameType>::new(
+            Some(older_sample),
+            begin_ts,
+            end_sample,
+            end_ts,
+        ))?;
+        Some((begin_model, window_model))
     }
 
     // Convenience function will be used by dump and scuba dump
@@ -337,8 +402,8 @@ impl<FrameType, ModelType> Advance<FrameType, ModelType> {
         }
 
         match self.current_direction {
-            Direction::Forward => self.target_timestamp + Duration::from_secs(1),
-            Direction::Reverse => self.target_timestamp - Duration::from_secs(1),
+            Direction::Forward => self.target_timestamp + SAMPLE_STEP,
+            Direction::Reverse => self.target_timestamp - SAMPLE_STEP,
         }
     }
 }
@@ -379,10 +444,29 @@ pub fn new_advance_remote(
     })
 }
 
+/// Construct a new Advance object with a snapshot file
+pub fn new_advance_snapshot(
+    logger: slog::Logger,
+    path: &Path,
+    timestamp: SystemTime,
+) -> Result<Advance<DataFrame, Model>> {
+    let store = Box::new(SnapshotStore::open(path)?);
+
+    Ok(Advance {
+        logger,
+        store,
+        cached_sample: None,
+        target_timestamp: timestamp,
+        current_direction: Direction::Forward,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhow::bail;
+    use common::util;
+    use tempdir::TempDir;
 
     fn get_logger() -> slog::Logger {
         slog::Logger::root(slog::Discard, slog::o!())
@@ -421,7 +505,15 @@ mod tests {
                 bail!("error");
             }
 
-            let timestamp = util::get_unix_timestamp(timestamp);
+            // Samples are whole seconds, so round a sub-second timestamp
+            // towards the search direction
+            let mut timestamp = timestamp
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .expect("time went backwards");
+            if direction == Direction::Forward && timestamp.subsec_nanos() != 0 {
+                timestamp += Duration::from_secs(1);
+            }
+            let timestamp = timestamp.as_secs();
             // corner cases
             if self.sample.is_empty()
                 || (timestamp < *self.sample.first().unwrap() && direction == Direction::Reverse)
@@ -807,9 +899,82 @@ mod tests {
         let mut advance = get_advance_with_fake_store(3);
         assert_eq!(advance.get_next_ts(), util::get_system_time(3));
         advance.initialize();
-        assert_eq!(advance.get_next_ts(), util::get_system_time(4));
+        assert_eq!(
+            advance.get_next_ts(),
+            util::get_system_time(3) + SAMPLE_STEP
+        );
         advance.advance(Direction::Forward);
         advance.advance(Direction::Reverse);
-        assert_eq!(advance.get_next_ts(), util::get_system_time(2));
+        assert_eq!(
+            advance.get_next_ts(),
+            util::get_system_time(3) - SAMPLE_STEP
+        );
+    }
+
+    #[test]
+    fn advance_test_get_window_models() {
+        let mut advance = get_advance_with_fake_store(15);
+        advance.initialize();
+        assert_eq!(
+            advance.get_window_models(util::get_system_time(5), util::get_system_time(30)),
+            Some(("10_10".to_string(), "10_20_20_10".to_string()))
+        );
+        // Window without two distinct samples
+        assert_eq!(
+            advance.get_window_models(util::get_system_time(11), util::get_system_time(19)),
+            None
+        );
+        // Position is unchanged
+        assert_eq!(advance.get_next_ts(), util::get_system_time(21));
+    }
+
+    #[test]
+    fn advance_across_burst() {
+        let dir = TempDir::new("below_advance_test").expect("tempdir failed");
+        let begin = util::get_system_time(crate::DEFAULT_SHARD_TIME);
+        // Regular samples 5s apart with a burst of 200ms samples in between
+        let timestamps: Vec<SystemTime> = [0, 5000, 5200, 5400, 10000]
+            .iter()
+            .map(|ms| begin + Duration::from_millis(*ms))
+            .collect();
+        let mut writer = crate::StoreWriter::new_with_timestamp(
+            dir.path(),
+            begin,
+            crate::CompressionMode::None,
+            crate::Format::Cbor,
+        )
+        .expect("Failed to create store");
+        for timestamp in &timestamps {
+            writer
+                .put(*timestamp, &DataFrame::default(), get_logger())
+                .expect("Failed to store data");
+        }
+
+        let mut advance = new_advance_local(get_logger(), dir.path().to_path_buf(), begin);
+        advance.initialize();
+        let mut forward = Vec::new();
+        while let Some(model) = advance.advance(Direction::Forward) {
+            forward.push((model.timestamp, model.time_elapsed));
+        }
+        assert_eq!(
+            forward,
+            vec![
+                (timestamps[1], Duration::from_secs(5)),
+                (timestamps[2], Duration::from_millis(200)),
+                (timestamps[3], Duration::from_millis(200)),
+                (timestamps[4], Duration::from_millis(4600)),
+            ]
+        );
+        let mut reverse = Vec::new();
+        while let Some(model) = advance.advance(Direction::Reverse) {
+            reverse.push(model.timestamp);
+        }
+        assert_eq!(reverse, vec![timestamps[3], timestamps[2], timestamps[1]]);
+
+        let model = advance
+            .jump_sample_to(begin + Duration::from_millis(5100))
+            .expect("Failed to jump");
+        assert_eq!(model.timestamp, timestamps[2]);
+        assert_eq!(model.time_elapsed, Duration::from_millis(200));
     }
 }
@@ -0,0 +1,174 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Annotations are short texts attached to a point in time of a store, e.g.
+//! a fired alert. They are kept apart from the samples, in one
+//! `annotations_<shard>` file per shard, which is a sequence of CBOR encoded
+//! Annotations in the order they were written. Annotations are appended with
+//! O_APPEND, so several writers may add annotations at the same time as long
+//! as each record is written at once. Annotation files are discarded together
+//! with the rest of their shard.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use common::util::get_unix_timestamp;
+
+use crate::{calculate_shard, get_shard_time};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Seconds since epoch
+    pub timestamp: u64,
+    /// What added the annotation, e.g. "alert"
+    pub source: String,
+    pub text: String,
+}
+
+pub(crate) fn annotation_path(path: &Path, shard: u64) -> PathBuf {
+    path.join(format!("annotations_{:011}", shard))
+}
+
+/// Append an annotation at `timestamp` to the store at `path`
+pub fn append_annotation(
+    path: &Path,
+    timestamp: SystemTime,
+    source: &str,
+    text: &str,
+) -> Result<()> {
+    if !path.is_dir() {
+        bail!("No store at {}", path.display());
+    }
+    let shard = calculate_shard(timestamp, get_shard_time(path)?);
+    let annotation = Annotation {
+        timestamp: get_unix_timestamp(timestamp),
+        source: source.to_owned(),
+        text: text.to_owned(),
+    };
+    let annotation_path = annotation_path(path, shard);
+    // A single write per record so concurrent appends don't interleave
+    let buf = serde_cbor::to_vec(&annotation)?;
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&annotation_path)
+        .and_then(|mut file| file.write_all(&buf))
+        .with_context(|| format!("Failed to write {}", annotation_path.display()))
+}
+
+fn read_annotation_file(path: &Path) -> Result<Vec<Annotation>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        // Discarded in the meantime
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    // A record cut short by a crash ends the file
+    Ok(serde_cbor::Deserializer::from_slice(&bytes)
+        .into_iter::<Annotation>()
+        .map_while(|res| res.ok())
+        .collect())
+}
+
+/// Read all annotations of the store at `path` within [begin, end], sorted
+/// by timestamp
+pub fn read_annotations(
+    path: &Path,
+    begin: SystemTime,
+    end: SystemTime,
+) -> Result<Vec<Annotation>> {
+    let shard_time = get_shard_time(path)?;
+    let first_shard = calculate_shard(begin, shard_time);
+    let (begin, end) = (get_unix_timestamp(begin), get_unix_timestamp(end));
+
+    let mut annotations = Vec::new();
+    for entry in fs::read_dir(path)
+        .with_context(|| format!("Failed to read directory {}", path.display()))?
+    {
+        let entry = entry
+            .with_context(|| format!("Failed to read directory entries in {}", path.display()))?;
+        let shard = match entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix("annotations_"))
+            .and_then(|shard| shard.parse::<u64>().ok())
+        {
+            Some(shard) => shard,
+            None => continue,
+        };
+        if shard < first_shard || shard > end {
+            continue;
+        }
+        annotations.extend(
+            read_annotation_file(&entry.path())?
+                .into_iter()
+                .filter(|a| a.timestamp >= begin && a.timestamp <= end),
+        );
+    }
+    // Stable so annotations at the same time keep the order they were added
+    annotations.sort_by_key(|a| a.timestamp);
+    Ok(annotations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use tempdir::TempDir;
+
+    #[test]
+    fn annotations_across_shards() {
+        let dir = TempDir::new("below_annotation_test").expect("tempdir failed");
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let day = crate::DEFAULT_SHARD_TIME;
+
+        append_annotation(dir.path(), at(day + 10), "alert", "second").unwrap();
+        append_annotation(dir.path(), at(10), "user", "first").unwrap();
+        append_annotation(dir.path(), at(day + 10), "user", "third").unwrap();
+        append_annotation(dir.path(), at(2 * day + 10), "user", "fourth").unwrap();
+
+        let texts = |begin, end| -> Vec<String> {
+            read_annotations(dir.path(), at(begin), at(end))
+                .unwrap()
+                .into_iter()
+                .map(|a| a.text)
+                .collect()
+        };
+        assert_eq!(
+            texts(0, 3 * day),
+            vec!["first", "second", "third", "fourth"]
+        );
+        assert_eq!(texts(11, day + 10), vec!["second", "third"]);
+        assert_eq!(texts(day + 11, 2 * day), Vec::<String>::new());
+
+        let annotations = read_annotations(dir.path(), at(0), at(20)).unwrap();
+        assert_eq!(
+            annotations,
+            vec![Annotation {
+                timestamp: 10,
+                source: "user".to_owned(),
+                text: "first".to_owned(),
+            }]
+        );
+
+        assert!(append_annotation(&dir.path().join("missing"), at(10), "user", "x").is_err());
+    }
+}
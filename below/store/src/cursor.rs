@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cell::RefCell;
 use std::fs::File;
 use std::io::ErrorKind;
 use std::path::PathBuf;
@@ -23,8 +24,9 @@ use slog::{warn, Logger};
 use zstd::stream::decode_all;
 
 use crate::{
-    deserialize_frame, get_index_files, Crc32, DataFrame, Direction, Format, IndexEntry,
-    IndexEntryFlags, SerializedFrame, INDEX_ENTRY_SIZE, SHARD_TIME,
+    decompress_with_dict, deserialize_frame, get_index_files, read_dict, read_store_meta,
+    split_delta, split_subsec, Crc32, DataFrame, Direction, Format, IndexEntry, IndexEntryFlags,
+    SerializedFrame, DEFAULT_SHARD_TIME, INDEX_ENTRY_SIZE,
 };
 
 /// A read-only Iterator that can move back and forth.
@@ -76,16 +78,22 @@ pub trait KeyedCursor<Key: std::cmp::Ord>: Cursor {
     fn jump_to_key(&mut self, key: &Key, direction: Direction) -> Result<bool> {
         self.jump_near_key(key, direction);
         // Move cursor backward to get a position with lower key order, and then
-        // move forward to get the first position with higher key order.
+        // move forward to get the first position with equal or higher key
+        // order. Moving backward past equal keys makes sure the first of
+        // several positions with the same key is found.
+        let skip_order = direction.get_skip_order();
         let mut curr_key = self.get_key();
-        for curr_dir in &[direction.flip(), direction] {
-            let skip_order = curr_dir.get_skip_order();
-            while curr_key.as_ref().map_or(true, |k| k.cmp(key) == skip_order) {
-                if !self.advance(*curr_dir)? {
-                    break;
-                }
-                curr_key = self.get_key();
+        while curr_key.as_ref().map_or(true, |k| k.cmp(key) != skip_order) {
+            if !self.advance(direction.flip())? {
+                break;
+            }
+            curr_key = self.get_key();
+        }
+        while curr_key.as_ref().map_or(true, |k| k.cmp(key) == skip_order) {
+            if !self.advance(direction)? {
+                break;
             }
+            curr_key = self.get_key();
         }
         // Check if the last key satisfies the direction order
         Ok(curr_key.map_or(false, |k| k.cmp(key) != direction.get_skip_order()))
@@ -135,12 +143,20 @@ pub struct StoreCursor {
     logger: Logger,
     // Path to the store directory that contains index and data files.
     path: PathBuf,
+    // Shard time recorded in the store. None if not recorded (yet), in which
+    // case DEFAULT_SHARD_TIME is used.
+    shard_time: Option<u64>,
     // Current shard this cursor points to.
     shard: Option<u64>,
     // Mmap of the index and data files of the current shard. Could be None if
     // the current shard does not exist.
     index_mmap: Option<Mmap>,
     data_mmap: Option<Mmap>,
+    // Zstd dictionary of the current shard. None if the shard has none (yet).
+    dict: Option<Vec<u8>>,
+    // Shard, index offset and serialized frame of the keyframe last used to
+    // decode a DELTA frame. Consecutive DELTA frames share their keyframe.
+    keyframe: RefCell<Option<(u64, usize, Vec<u8>)>>,
     // Current offset into the index mmap. The combination of shard and offset
     // locates the exact sample of this store. Offset could be None if shard
     // does not exist or just moved to a newly initialized shard.
@@ -158,13 +174,41 @@ impl StoreCursor {
         Self {
             logger,
             path,
+            shard_time: None,
             shard: None,
             index_mmap: None,
             data_mmap: None,
+            dict: None,
+            keyframe: RefCell::new(None),
             index_offset: None,
         }
     }
 
+    fn shard_time(&self) -> u64 {
+        self.shard_time.unwrap_or(DEFAULT_SHARD_TIME)
+    }
+
+    /// Read the shard time recorded in the store if it's not known yet. The
+    /// store may not exist when the cursor is created.
+    fn update_shard_time(&mut self) -> Result<()> {
+        if self.shard_time.is_none() {
+            self.shard_time = read_store_meta(&self.path)?.map(|meta| meta.shard_time);
+        }
+        Ok(())
+    }
+
+    /// Same as update_shard_time for callers that can't fail
+    fn update_shard_time_or_warn(&mut self) {
+        if let Err(e) = self.update_shard_time() {
+            warn!(self.logger, "Failed to get shard time: {:#}", e);
+        }
+    }
+
+    /// StoreOffset with the shard trimmed to the shard time of this store
+    fn new_offset(&self, shard: Option<u64>, index_offset: Option<usize>) -> StoreOffset {
+        StoreOffset::new(shard, index_offset, self.shard_time())
+    }
+
     /// Get the mmap of a related store file based on the given shard. If the
     /// file is not found or empty, None will be returned.
     fn get_mmap(&self, file_type: StoreFile, shard: u64) -> Result<Option<Mmap>> {
@@ -242,6 +286,12 @@ impl StoreCursor {
         } else {
             self.shard = Some(shard);
             self.index_offset = None;
+            self.dict = None;
+        }
+        // The dictionary is written some time after the shard is created, so
+        // keep looking for it until found.
+        if self.dict.is_none() {
+            self.dict = read_dict(&self.path, shard)?;
         }
         self.index_mmap = Some(new_index_mmap);
         self.data_mmap = Some(new_data_mmap);
@@ -254,6 +304,7 @@ impl StoreCursor {
     /// more shard in the given direction. Retrying may succeed as the store
     /// directory is scanned on every call.
     fn update_or_advance_shard(&mut self, direction: Direction) -> Result<bool> {
+        self.update_shard_time()?;
         let entries = get_index_files(&self.path)?;
 
         let entries_iter: Box<dyn Iterator<Item = &String>> = match direction {
@@ -321,8 +372,12 @@ impl StoreCursor {
 
     /// Get the index entry the cursor currently pointing at.
     fn get_index_entry(&self) -> Option<&IndexEntry> {
+        self.get_index_entry_at(self.index_offset?)
+    }
+
+    /// Get the index entry at the given offset of the current shard.
+    fn get_index_entry_at(&self, index_offset: usize) -> Option<&IndexEntry> {
         let index_mmap = self.index_mmap.as_ref()?;
-        let index_offset = self.index_offset?;
 
         let index_entry_slice =
             index_mmap.get(index_offset..(index_offset.checked_add(INDEX_ENTRY_SIZE)?))?;
@@ -351,6 +406,165 @@ impl StoreCursor {
             Some(index_entry)
         }
     }
+
+    /// Get the serialized frame of an index entry, decompressing it if
+    /// needed. `index_offset` is the offset of the entry in the current
+    /// shard.
+    fn get_serialized_frame(
+        &self,
+        index_entry: &IndexEntry,
+        index_offset: usize,
+    ) -> Option<SerializedFrame<'_>> {
+        let data_mmap = self.data_mmap.as_ref()?;
+
+        let data_offset = index_entry.offset as usize;
+        let data_len = index_entry.len as usize;
+        let data_slice = data_mmap.get(data_offset..(data_offset.checked_add(data_len)?))?;
+
+        if data_slice.crc32() != index_entry.data_crc {
+            warn!(
+                self.logger,
+                "Corrupted data entry found: ts={} offset={:#x}",
+                index_entry.timestamp,
+                index_entry.offset,
+            );
+            return None;
+        }
+        let data_slice = match split_subsec(index_entry, data_slice) {
+            Some((_, data_slice)) => data_slice,
+            None => {
+                warn!(
+                    self.logger,
+                    "Truncated sub-second data entry found: ts={} offset={:#x}",
+                    index_entry.timestamp,
+                    index_entry.offset,
+                );
+                return None;
+            }
+        };
+
+        let data_decompressed = if index_entry.flags.contains(IndexEntryFlags::DELTA) {
+            SerializedFrame::Copy(self.decompress_delta(index_entry, index_offset, data_slice)?)
+        } else if index_entry.flags.contains(IndexEntryFlags::ZSTD_DICT) {
+            let dict = match self.dict.as_ref() {
+                Some(dict) => dict,
+                None => {
+                    warn!(
+                        self.logger,
+                        "Missing zstd dictionary for data entry: ts={} offset={:#x}",
+                        index_entry.timestamp,
+                        index_entry.offset,
+                    );
+                    return None;
+                }
+            };
+            SerializedFrame::Copy(match decompress_with_dict(data_slice, dict) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    warn!(self.logger, "Failed to decompress data frame: {}", e);
+                    return None;
+                }
+            })
+        } else if index_entry.flags.contains(IndexEntryFlags::COMPRESSED) {
+            SerializedFrame::Copy(match decode_all(data_slice) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    warn!(self.logger, "Failed to decompress data frame: {}", e);
+                    return None;
+                }
+            })
+        } else {
+            SerializedFrame::Slice(data_slice)
+        };
+        Some(data_decompressed)
+    }
+
+    /// Get the nanoseconds past the timestamp of an index entry. Zero unless
+    /// the entry is SUBSEC.
+    fn get_subsec_nanos(&self, index_entry: &IndexEntry) -> Option<u32> {
+        let data_offset = index_entry.offset as usize;
+        let data_slice = self
+            .data_mmap
+            .as_ref()?
+            .get(data_offset..(data_offset.checked_add(index_entry.len as usize)?))?;
+        split_subsec(index_entry, data_slice).map(|(nanos, _)| nanos)
+    }
+
+    /// Decompress a DELTA data frame against its keyframe, which is an
+    /// earlier entry of the current shard.
+    fn decompress_delta(
+        &self,
+        index_entry: &IndexEntry,
+        index_offset: usize,
+        data: &[u8],
+    ) -> Option<Vec<u8>> {
+        let shard = self.shard?;
+        let (keyframe_ts, delta) = match split_delta(data) {
+            Some(split) => split,
+            None => {
+                warn!(
+                    self.logger,
+                    "Truncated delta data entry found: ts={} offset={:#x}",
+                    index_entry.timestamp,
+                    index_entry.offset,
+                );
+                return None;
+            }
+        };
+
+        // Several keyframes can share a second when SUBSEC frames are written
+        // in bursts, so the cache is keyed by the keyframe's index offset.
+        let keyframe_offset = self.find_keyframe_offset(index_offset, keyframe_ts)?;
+        let cached = matches!(
+            self.keyframe.borrow().as_ref(),
+            Some((s, offset, _)) if *s == shard && *offset == keyframe_offset
+        );
+        if !cached {
+            let keyframe = self
+                .get_index_entry_at(keyframe_offset)
+                .and_then(|entry| self.get_serialized_frame(entry, keyframe_offset))
+                .map(|frame| frame.data().to_vec())?;
+            self.keyframe
+                .replace(Some((shard, keyframe_offset, keyframe)));
+        }
+
+        let keyframe = self.keyframe.borrow();
+        let (_, _, keyframe_bytes) = keyframe.as_ref()?;
+        match decompress_with_dict(delta, keyframe_bytes) {
+            Ok(decoded) => Some(decoded),
+            Err(e) => {
+                warn!(self.logger, "Failed to decompress data frame: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Search backward from `index_offset` for the keyframe written at
+    /// `keyframe_ts` and return its index offset. A delta is always encoded
+    /// against the latest keyframe, so this is the nearest preceding one.
+    fn find_keyframe_offset(&self, index_offset: usize, keyframe_ts: u64) -> Option<usize> {
+        let mut offset = index_offset;
+        while let Some(prev) = offset.checked_sub(INDEX_ENTRY_SIZE) {
+            offset = prev;
+            let entry = match self.get_index_entry_at(offset) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if entry.timestamp < keyframe_ts {
+                break;
+            }
+            if entry.timestamp == keyframe_ts && !entry.flags.contains(IndexEntryFlags::DELTA) {
+                return Some(offset);
+            }
+        }
+        warn!(
+            self.logger,
+            "Missing keyframe for delta data entry: keyframe_ts={} offset={:#x}",
+            keyframe_ts,
+            index_offset,
+        );
+        None
+    }
 }
 
 /// Offset of a StoreCursor.
@@ -361,11 +575,12 @@ pub struct StoreOffset {
 }
 
 impl StoreOffset {
-    /// Shard and index offset are trimmed to multiples of SHARD_TIME and
-    /// INDEX_ENTRY_SIZE respectively. Index offset is ignored if shard is None.
-    pub fn new(shard: Option<u64>, index_offset: Option<usize>) -> Self {
+    /// Shard and index offset are trimmed to multiples of shard_time (the
+    /// shard time of the store) and INDEX_ENTRY_SIZE respectively. Index
+    /// offset is ignored if shard is None.
+    pub fn new(shard: Option<u64>, index_offset: Option<usize>, shard_time: u64) -> Self {
         StoreOffset {
-            shard: shard.as_ref().map(|s| s - s % SHARD_TIME),
+            shard: shard.as_ref().map(|s| s - s % shard_time),
             index_offset: shard.and(index_offset.map(|o| o - o % INDEX_ENTRY_SIZE)),
         }
     }
@@ -384,10 +599,12 @@ impl Cursor for StoreCursor {
     type Item = (SystemTime, DataFrame);
 
     fn get_offset(&self) -> StoreOffset {
-        StoreOffset::new(self.shard, self.index_offset)
+        self.new_offset(self.shard, self.index_offset)
     }
 
     fn set_offset(&mut self, offset: StoreOffset) {
+        self.update_shard_time_or_warn();
+        let offset = self.new_offset(offset.get_shard(), offset.get_index_offset());
         if let Some(shard) = offset.get_shard() {
             if self.shard == Some(shard) || self.update_shard(shard).unwrap_or(false) {
                 self.index_offset = offset.get_index_offset();
@@ -401,6 +618,7 @@ impl Cursor for StoreCursor {
         // Keep it so get_offset may get the same value back.
         self.shard = offset.get_shard();
         self.index_mmap = None;
+        self.dict = None;
         self.index_offset = offset.get_index_offset();
     }
 
@@ -423,34 +641,8 @@ impl Cursor for StoreCursor {
     /// This does not mean samples are depleted. More could be retrieved by
     /// advancing further to skip the holes.
     fn get(&self) -> Option<(SystemTime, DataFrame)> {
-        let data_mmap = self.data_mmap.as_ref()?;
         let index_entry = self.get_index_entry()?;
-
-        let data_offset = index_entry.offset as usize;
-        let data_len = index_entry.len as usize;
-        let data_slice = data_mmap.get(data_offset..(data_offset.checked_add(data_len)?))?;
-
-        if data_slice.crc32() != index_entry.data_crc {
-            warn!(
-                self.logger,
-                "Corrupted data entry found: ts={} offset={:#x}",
-                index_entry.timestamp,
-                index_entry.offset,
-            );
-            return None;
-        }
-
-        let data_decompressed = if index_entry.flags.contains(IndexEntryFlags::COMPRESSED) {
-            SerializedFrame::Copy(match decode_all(data_slice) {
-                Ok(decoded) => decoded,
-                Err(e) => {
-                    warn!(self.logger, "Failed to decompress data frame: {}", e);
-                    return None;
-                }
-            })
-        } else {
-            SerializedFrame::Slice(data_slice)
-        };
+        let data_decompressed = self.get_serialized_frame(index_entry, self.index_offset?)?;
 
         let format = if index_entry.flags.contains(IndexEntryFlags::CBOR) {
             Format::Cbor
@@ -458,7 +650,8 @@ impl Cursor for StoreCursor {
             Format::Thrift
         };
 
-        let ts = std::time::UNIX_EPOCH + std::time::Duration::from_secs(index_entry.timestamp);
+        let ts = std::time::UNIX_EPOCH
+            + std::time::Duration::new(index_entry.timestamp, self.get_subsec_nanos(index_entry)?);
         match deserialize_frame(data_decompressed.data(), format) {
             Ok(df) => Some((ts, df)),
             Err(e) => {
@@ -480,9 +673,11 @@ impl KeyedCursor<u64> for StoreCursor {
     /// Set the cursor offset near the given timestamp by inferring shard and
     /// index offset.
     fn jump_near_key(&mut self, key: &u64, _direction: Direction) {
-        let time_offset = key % SHARD_TIME;
+        self.update_shard_time_or_warn();
+        let shard_time = self.shard_time();
+        let time_offset = key % shard_time;
         let shard = key - time_offset;
-        self.set_offset(StoreOffset::new(Some(shard), None));
+        self.set_offset(self.new_offset(Some(shard), None));
         // Move to the end of the shard.
         if self.advance_index(Direction::Reverse) {
             if let Some(last_entry) = self.get_index_entry() {
@@ -490,14 +685,14 @@ impl KeyedCursor<u64> for StoreCursor {
                     .get_offset()
                     .get_index_offset()
                     .expect("get_index_offset should return Some if get_index_entry returns Some");
-                let last_entry_time_offset = last_entry.timestamp % SHARD_TIME;
+                let last_entry_time_offset = last_entry.timestamp % shard_time;
                 if last_entry_time_offset != 0 {
                     // Assume samples are recorded in constant interval and
                     // scale index offset by time offset
                     let index_offset_hint = (last_entry_index_offset as f64
                         / last_entry_time_offset as f64
                         * time_offset as f64) as usize;
-                    self.set_offset(StoreOffset::new(Some(shard), Some(index_offset_hint)));
+                    self.set_offset(self.new_offset(Some(shard), Some(index_offset_hint)));
                 }
             }
         }
@@ -507,7 +702,10 @@ impl KeyedCursor<u64> for StoreCursor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{serialize_frame, StoreWriter};
+    use crate::{
+        serialize_frame, CompressionMode, StoreWriter, DICT_TRAINING_FRAMES, KEYFRAME_INTERVAL,
+    };
+    use common::fileutil::get_dir_size;
     use common::util::get_unix_timestamp;
     use slog::Drain;
     use std::fs::OpenOptions;
@@ -515,6 +713,9 @@ mod tests {
     use tempdir::TempDir;
     use Direction::{Forward, Reverse};
 
+    // Shard time of all stores created by tests
+    const SHARD_TIME: u64 = DEFAULT_SHARD_TIME;
+
     /// Simple cursor to illustrate implementation and test default methods.
     struct TestCursor<'a> {
         data: &'a Vec<Option<i32>>,
@@ -604,6 +805,27 @@ mod tests {
         assert_eq!(cursor.get_key(), None);
     }
 
+    /// Test default implementation of jump_to_key() with repeated keys. The
+    /// first such position is found in the given direction.
+    #[test]
+    fn default_jump_to_repeated_key() {
+        let data = vec![Some(3), Some(5), Some(5), None, Some(5), Some(9)];
+        let mut cursor = TestCursor {
+            data: &data,
+            offset: Some(2),
+        };
+        assert!(cursor.jump_to_key(&5, Forward).unwrap());
+        assert_eq!(cursor.get_offset(), Some(1));
+        cursor.set_offset(Some(2));
+        assert!(cursor.jump_to_key(&5, Reverse).unwrap());
+        assert_eq!(cursor.get_offset(), Some(4));
+        // Closest key
+        assert!(cursor.jump_to_key(&4, Forward).unwrap());
+        assert_eq!(cursor.get_offset(), Some(1));
+        assert!(cursor.jump_to_key(&6, Reverse).unwrap());
+        assert_eq!(cursor.get_offset(), Some(4));
+    }
+
     /// Test default implementation of get_near().
     #[test]
     fn default_get_near() {
@@ -646,11 +868,12 @@ mod tests {
     }
 
     /// Write a single sample in different ways and read it back.
-    fn simple_put_read(compress: bool, format: Format) {
+    fn simple_put_read(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = get_unix_timestamp(SystemTime::now());
         let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(ts);
-        let mut writer = StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+        let mut writer =
+            StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
         let mut frame = DataFrame::default();
         frame.sample.cgroup.memory_current = Some(42);
         writer
@@ -667,21 +890,110 @@ mod tests {
 
     #[test]
     fn read_cbor() {
-        simple_put_read(false, Format::Cbor);
+        simple_put_read(CompressionMode::None, Format::Cbor);
     }
     #[test]
     fn read_compressed_cbor() {
-        simple_put_read(true, Format::Cbor);
+        simple_put_read(CompressionMode::Zstd, Format::Cbor);
     }
     #[cfg(fbcode_build)]
     #[test]
     fn read_thrift() {
-        simple_put_read(false, Format::Thrift);
+        simple_put_read(CompressionMode::None, Format::Thrift);
     }
     #[cfg(fbcode_build)]
     #[test]
     fn read_compressed_thrift() {
-        simple_put_read(true, Format::Thrift);
+        simple_put_read(CompressionMode::Zstd, Format::Thrift);
+    }
+
+    /// Write `n` slightly different samples `interval` apart into a single
+    /// shard and read them back in both directions. Returns the store
+    /// directory and the start of its shard.
+    fn many_put_read(
+        compression_mode: CompressionMode,
+        n: u64,
+        interval: std::time::Duration,
+    ) -> (TempDir, u64) {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = get_unix_timestamp(SystemTime::now());
+        let shard_start = ts - ts % SHARD_TIME;
+        let mut writer =
+            StoreWriter::new(&dir, compression_mode, Format::Cbor).expect("Failed to create store");
+        let mut expected = Vec::new();
+        for i in 0..n {
+            let time = std::time::UNIX_EPOCH
+                + std::time::Duration::from_secs(shard_start)
+                + interval * i as u32;
+            let mut frame = DataFrame::default();
+            frame.sample.cgroup.memory_current = Some(i as i64 * 4096);
+            frame.sample.system.hostname = format!("host{}.example.com", i % 7);
+            writer
+                .put(time, &frame, get_logger())
+                .expect("Failed to store data");
+            expected.push((time, frame));
+        }
+
+        let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        for sample in expected.iter() {
+            assert_eq!(
+                &cursor
+                    .next(Forward)
+                    .expect("Failed to read sample")
+                    .expect("Did not find stored sample"),
+                sample
+            );
+        }
+        assert_eq!(cursor.next(Forward).expect("Failed to read sample"), None);
+
+        let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        for sample in expected.iter().rev() {
+            assert_eq!(
+                &cursor
+                    .next(Reverse)
+                    .expect("Failed to read sample")
+                    .expect("Did not find stored sample"),
+                sample
+            );
+        }
+        (dir, shard_start)
+    }
+
+    /// Frames written both before and after the dictionary is trained can
+    /// be read back.
+    #[test]
+    fn read_dict_compressed_cbor() {
+        let (dir, shard_start) = many_put_read(
+            CompressionMode::ZstdDictionary,
+            DICT_TRAINING_FRAMES as u64 * 2,
+            std::time::Duration::from_secs(1),
+        );
+        assert!(dir
+            .path()
+            .join(format!("dict_{:011}", shard_start))
+            .exists());
+    }
+
+    /// Keyframes and delta frames across several keyframe intervals can be
+    /// read back, and take less space than compressing each frame alone.
+    #[test]
+    fn read_delta_compressed_cbor() {
+        let n = KEYFRAME_INTERVAL as u64 * 2 + 1;
+        let interval = std::time::Duration::from_secs(1);
+        let (delta_dir, _) = many_put_read(CompressionMode::ZstdDelta, n, interval);
+        let (zstd_dir, _) = many_put_read(CompressionMode::Zstd, n, interval);
+        assert!(get_dir_size(delta_dir.path()) < get_dir_size(zstd_dir.path()));
+    }
+
+    /// Delta frames are decoded against their own keyframe when a burst of
+    /// sub-second frames puts several keyframes within the same second.
+    #[test]
+    fn read_delta_compressed_burst_cbor() {
+        many_put_read(
+            CompressionMode::ZstdDelta,
+            KEYFRAME_INTERVAL as u64 * 3 + 1,
+            std::time::Duration::from_millis(1),
+        );
     }
 
     /// For writing samples readable by the cursor and injecting corruptions.
@@ -917,9 +1229,9 @@ mod tests {
         writer.put(ts + SHARD_TIME * 2 + 5).unwrap();
 
         let expected_offsets = &[
-            StoreOffset::new(Some(ts), Some(0)),
-            StoreOffset::new(Some(ts), Some(INDEX_ENTRY_SIZE)),
-            StoreOffset::new(Some(ts + SHARD_TIME * 2), Some(0)),
+            StoreOffset::new(Some(ts), Some(0), SHARD_TIME),
+            StoreOffset::new(Some(ts), Some(INDEX_ENTRY_SIZE), SHARD_TIME),
+            StoreOffset::new(Some(ts + SHARD_TIME * 2), Some(0), SHARD_TIME),
         ];
 
         // Verify offset values.
@@ -938,9 +1250,14 @@ mod tests {
         cursor.set_offset(StoreOffset::new(
             Some(ts + SHARD_TIME),
             Some(INDEX_ENTRY_SIZE),
+            SHARD_TIME,
         ));
         assert!(cursor.get_key().is_none());
-        cursor.set_offset(StoreOffset::new(Some(ts + SHARD_TIME * 2), None));
+        cursor.set_offset(StoreOffset::new(
+            Some(ts + SHARD_TIME * 2),
+            None,
+            SHARD_TIME,
+        ));
         assert!(cursor.get_key().is_none());
         // Get values from expected offsets.
         cursor.set_offset(expected_offsets[1].clone());
@@ -963,19 +1280,66 @@ mod tests {
         writer.put(ts + SHARD_TIME * 2).unwrap();
 
         // To same shard
-        cursor.set_offset(StoreOffset::new(Some(ts), Some(INDEX_ENTRY_SIZE)));
+        cursor.set_offset(StoreOffset::new(
+            Some(ts),
+            Some(INDEX_ENTRY_SIZE),
+            SHARD_TIME,
+        ));
         assert!(cursor.advance(Reverse).unwrap());
         assert_eq!(cursor.get_key(), Some(ts));
         // To different shard
-        cursor.set_offset(StoreOffset::new(Some(ts + SHARD_TIME), Some(0)));
+        cursor.set_offset(StoreOffset::new(Some(ts + SHARD_TIME), Some(0), SHARD_TIME));
         assert!(cursor.advance(Forward).unwrap());
         assert_eq!(cursor.get_key(), Some(ts + SHARD_TIME * 2));
         // Over boundry (offset shouldn't change)
-        cursor.set_offset(StoreOffset::new(Some(ts + SHARD_TIME * 4), Some(0)));
+        cursor.set_offset(StoreOffset::new(
+            Some(ts + SHARD_TIME * 4),
+            Some(0),
+            SHARD_TIME,
+        ));
         assert!(!cursor.advance(Forward).unwrap());
         assert_eq!(
             cursor.get_offset(),
-            StoreOffset::new(Some(ts + SHARD_TIME * 4), Some(0))
+            StoreOffset::new(Some(ts + SHARD_TIME * 4), Some(0), SHARD_TIME)
+        );
+    }
+
+    /// Offsets of a store with shards shorter than DEFAULT_SHARD_TIME.
+    #[test]
+    fn offset_with_custom_shard_time() {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let shard_time = 3600;
+        // 05:00 of some day, which is not a multiple of DEFAULT_SHARD_TIME
+        let ts = 20000 * DEFAULT_SHARD_TIME + 5 * shard_time;
+        let mut writer = StoreWriter::open(
+            &dir,
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(ts),
+            Some(shard_time),
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        for timestamp in [ts + 10, ts + shard_time + 10] {
+            writer
+                .put(
+                    std::time::UNIX_EPOCH + std::time::Duration::from_secs(timestamp),
+                    &DataFrame::default(),
+                    get_logger(),
+                )
+                .expect("Failed to store data");
+        }
+        let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+
+        let offset = StoreOffset::new(Some(ts + 10), Some(0), shard_time);
+        assert_eq!(offset.get_shard(), Some(ts));
+        cursor.set_offset(offset.clone());
+        assert_eq!(cursor.get_key(), Some(ts + 10));
+        assert_eq!(cursor.get_offset(), offset);
+        assert!(cursor.advance(Forward).unwrap());
+        assert_eq!(cursor.get_key(), Some(ts + shard_time + 10));
+        assert_eq!(
+            cursor.get_offset(),
+            StoreOffset::new(Some(ts + shard_time), Some(0), shard_time)
         );
     }
 
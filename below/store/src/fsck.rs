@@ -0,0 +1,807 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offline integrity checking and repair of a store.
+//!
+//! The read side silently skips anything it cannot validate, so a damaged
+//! store usually shows up as missing samples. `check_store` walks every
+//! shard and reports what is wrong with it, and `repair_shard` rewrites the
+//! index of a shard so that it only contains the entries that can actually be
+//! read. Data files are only rewritten when compaction is asked for.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    dict_path, get_shard_time, split_delta, split_subsec, Crc32, IndexEntry, IndexEntryFlags,
+    INDEX_ENTRY_SIZE,
+};
+
+/// A single problem found in a shard. Offsets are in bytes into the index
+/// file for index entries and into the data file for data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// The index file exists but its data file does not
+    MissingDataFile,
+    /// The data file exists but its index file does not
+    MissingIndexFile,
+    /// Index entry whose index_crc does not match its content
+    CorruptIndexEntry { index_offset: u64 },
+    /// Index entry that belongs to a different shard
+    WrongShard { index_offset: u64, timestamp: u64 },
+    /// Index entry pointing past the end of the data file
+    TruncatedData {
+        index_offset: u64,
+        timestamp: u64,
+        data_offset: u64,
+        len: u32,
+    },
+    /// Data whose data_crc does not match
+    CorruptData {
+        index_offset: u64,
+        timestamp: u64,
+        data_offset: u64,
+        len: u32,
+    },
+    /// Data compressed against a dictionary but the shard has no dictionary
+    MissingDictionary { index_offset: u64, timestamp: u64 },
+    /// Delta data whose keyframe is not a valid entry of the shard
+    MissingKeyframe { index_offset: u64, timestamp: u64 },
+    /// Index entry whose timestamp is not after the previous valid entry
+    NonMonotonicTimestamp {
+        index_offset: u64,
+        timestamp: u64,
+        previous: u64,
+    },
+    /// Trailing bytes in the index file that do not form a whole entry
+    TruncatedIndex { len: u64 },
+    /// Data bytes not referenced by any index entry
+    OrphanedData { data_offset: u64, len: u64 },
+}
+
+impl Problem {
+    /// Whether the problem does not affect reading the shard. `repair_shard`
+    /// only removes it when compacting, or when it is at the end of the data
+    /// file.
+    pub fn is_harmless(&self) -> bool {
+        matches!(self, Problem::OrphanedData { .. })
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::MissingDataFile => write!(f, "data file is missing"),
+            Problem::MissingIndexFile => write!(f, "index file is missing"),
+            Problem::CorruptIndexEntry { index_offset } => {
+                write!(f, "corrupted index entry at index offset {:#x}", index_offset)
+            }
+            Problem::WrongShard {
+                index_offset,
+                timestamp,
+            } => write!(
+                f,
+                "entry at index offset {:#x} has timestamp {} outside of shard",
+                index_offset, timestamp
+            ),
+            Problem::TruncatedData {
+                index_offset,
+                timestamp,
+                data_offset,
+                len,
+            } => write!(
+                f,
+                "entry at index offset {:#x} (ts={}) points to {} bytes at data offset {:#x} past the end of the data file",
+                index_offset, timestamp, len, data_offset
+            ),
+            Problem::CorruptData {
+                index_offset,
+                timestamp,
+                data_offset,
+                len,
+            } => write!(
+                f,
+                "entry at index offset {:#x} (ts={}) has corrupted data: {} bytes at data offset {:#x}",
+                index_offset, timestamp, len, data_offset
+            ),
+            Problem::MissingDictionary {
+                index_offset,
+                timestamp,
+            } => write!(
+                f,
+                "entry at index offset {:#x} (ts={}) needs a zstd dictionary but the shard has none",
+                index_offset, timestamp
+            ),
+            Problem::MissingKeyframe {
+                index_offset,
+                timestamp,
+            } => write!(
+                f,
+                "entry at index offset {:#x} (ts={}) is a delta against a missing keyframe",
+                index_offset, timestamp
+            ),
+            Problem::NonMonotonicTimestamp {
+                index_offset,
+                timestamp,
+                previous,
+            } => write!(
+                f,
+                "entry at index offset {:#x} has timestamp {} not after previous timestamp {}",
+                index_offset, timestamp, previous
+            ),
+            Problem::TruncatedIndex { len } => {
+                write!(f, "index file ends with a partial entry of {} bytes", len)
+            }
+            Problem::OrphanedData { data_offset, len } => write!(
+                f,
+                "{} data bytes at data offset {:#x} are not referenced by any index entry",
+                len, data_offset
+            ),
+        }
+    }
+}
+
+/// Result of checking a single shard
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShardReport {
+    pub shard: u64,
+    /// Number of whole index entries, valid or not
+    pub entries: usize,
+    /// Number of entries that can be read back
+    pub valid_entries: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl ShardReport {
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Whether the shard has problems `repair_shard` would fix
+    pub fn needs_repair(&self) -> bool {
+        self.problems.iter().any(|problem| !problem.is_harmless())
+    }
+}
+
+fn read_file(path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read file: {}", path.display())),
+    }
+}
+
+/// Get the shards of all index and data files in `dir`, sorted
+fn get_shards(dir: &Path) -> Result<Vec<u64>> {
+    let mut shards = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?
+    {
+        let entry = entry
+            .with_context(|| format!("Failed to read directory entries in {}", dir.display()))?;
+        let name = entry.file_name();
+        let shard = name.to_str().and_then(|name| {
+            name.strip_prefix("index_")
+                .or_else(|| name.strip_prefix("data_"))
+                .and_then(|shard| shard.parse::<u64>().ok())
+        });
+        if let Some(shard) = shard {
+            shards.push(shard);
+        }
+    }
+    shards.sort_unstable();
+    shards.dedup();
+    Ok(shards)
+}
+
+/// Validate index and data of `shard` and collect the readable entries
+fn check(
+    shard: u64,
+    shard_time: u64,
+    index: Option<&[u8]>,
+    data: Option<&[u8]>,
+    has_dict: bool,
+) -> (ShardReport, Vec<IndexEntry>) {
+    let mut report = ShardReport {
+        shard,
+        ..Default::default()
+    };
+    let mut valid = Vec::new();
+    let (index, data) = match (index, data) {
+        (Some(index), Some(data)) => (index, data),
+        (Some(_), None) => {
+            report.problems.push(Problem::MissingDataFile);
+            return (report, valid);
+        }
+        (None, _) => {
+            report.problems.push(Problem::MissingIndexFile);
+            return (report, valid);
+        }
+    };
+
+    // Data ranges referenced by entries with a valid index_crc
+    let mut referenced = Vec::new();
+    // Timestamp and sub-second nanoseconds of the previous valid entry
+    let mut previous: Option<(u64, u32)> = None;
+    // Timestamps of valid entries that delta entries may refer to
+    let mut keyframes = HashSet::new();
+    let chunks = index.chunks_exact(INDEX_ENTRY_SIZE);
+    let remainder = chunks.remainder().len();
+    for (idx, chunk) in chunks.enumerate() {
+        report.entries += 1;
+        let index_offset = (idx * INDEX_ENTRY_SIZE) as u64;
+        // Safe because any bit pattern is a valid IndexEntry and the read
+        // does not require alignment.
+        let entry = unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const IndexEntry) };
+        if entry.crc32() != entry.index_crc {
+            report
+                .problems
+                .push(Problem::CorruptIndexEntry { index_offset });
+            continue;
+        }
+
+        let data_slice = (entry.offset as usize)
+            .checked_add(entry.len as usize)
+            .and_then(|end| data.get(entry.offset as usize..end));
+        let data_slice = match data_slice {
+            Some(data_slice) => {
+                referenced.push((entry.offset, entry.offset + entry.len as u64));
+                data_slice
+            }
+            None => {
+                referenced.push((entry.offset.min(data.len() as u64), data.len() as u64));
+                report.problems.push(Problem::TruncatedData {
+                    index_offset,
+                    timestamp: entry.timestamp,
+                    data_offset: entry.offset,
+                    len: entry.len,
+                });
+                continue;
+            }
+        };
+        if data_slice.crc32() != entry.data_crc {
+            report.problems.push(Problem::CorruptData {
+                index_offset,
+                timestamp: entry.timestamp,
+                data_offset: entry.offset,
+                len: entry.len,
+            });
+            continue;
+        }
+        if entry.flags.contains(IndexEntryFlags::ZSTD_DICT) && !has_dict {
+            report.problems.push(Problem::MissingDictionary {
+                index_offset,
+                timestamp: entry.timestamp,
+            });
+            continue;
+        }
+        if entry.timestamp - entry.timestamp % shard_time != shard {
+            report.problems.push(Problem::WrongShard {
+                index_offset,
+                timestamp: entry.timestamp,
+            });
+            continue;
+        }
+        let (subsec_nanos, frame_data) = match split_subsec(&entry, data_slice) {
+            Some(split) => split,
+            None => {
+                report.problems.push(Problem::CorruptData {
+                    index_offset,
+                    timestamp: entry.timestamp,
+                    data_offset: entry.offset,
+                    len: entry.len,
+                });
+                continue;
+            }
+        };
+        if let Some((previous, _)) =
+            previous.filter(|previous| (entry.timestamp, subsec_nanos) <= *previous)
+        {
+            report.problems.push(Problem::NonMonotonicTimestamp {
+                index_offset,
+                timestamp: entry.timestamp,
+                previous,
+            });
+            continue;
+        }
+        if entry.flags.contains(IndexEntryFlags::DELTA) {
+            let keyframe_ts = split_delta(frame_data).map(|(keyframe_ts, _)| keyframe_ts);
+            if !keyframe_ts.map_or(false, |ts| keyframes.contains(&ts)) {
+                report.problems.push(Problem::MissingKeyframe {
+                    index_offset,
+                    timestamp: entry.timestamp,
+                });
+                continue;
+            }
+        } else {
+            keyframes.insert(entry.timestamp);
+        }
+
+        previous = Some((entry.timestamp, subsec_nanos));
+        valid.push(entry);
+    }
+    report.valid_entries = valid.len();
+    if remainder != 0 {
+        report.problems.push(Problem::TruncatedIndex {
+            len: remainder as u64,
+        });
+    }
+
+    // Any gap between referenced ranges is orphaned
+    referenced.sort_unstable();
+    let mut covered = 0;
+    for (begin, end) in referenced
+        .into_iter()
+        .chain(std::iter::once((data.len() as u64, data.len() as u64)))
+    {
+        if begin > covered {
+            report.problems.push(Problem::OrphanedData {
+                data_offset: covered,
+                len: begin - covered,
+            });
+        }
+        covered = covered.max(end);
+    }
+
+    (report, valid)
+}
+
+fn shard_paths(dir: &Path, shard: u64) -> (PathBuf, PathBuf) {
+    (
+        dir.join(format!("index_{:011}", shard)),
+        dir.join(format!("data_{:011}", shard)),
+    )
+}
+
+/// Check a single shard of the store at `dir`
+pub fn check_shard<P: AsRef<Path>>(dir: P, shard: u64) -> Result<ShardReport> {
+    let (index_path, data_path) = shard_paths(dir.as_ref(), shard);
+    let index = read_file(&index_path)?;
+    let data = read_file(&data_path)?;
+    let shard_time = get_shard_time(dir.as_ref())?;
+    let has_dict = dict_path(dir.as_ref(), shard).exists();
+    Ok(check(
+        shard,
+        shard_time,
+        index.as_deref(),
+        data.as_deref(),
+        has_dict,
+    )
+    .0)
+}
+
+/// Check every shard of the store at `dir`, oldest first
+pub fn check_store<P: AsRef<Path>>(dir: P) -> Result<Vec<ShardReport>> {
+    get_shards(dir.as_ref())?
+        .into_iter()
+        .map(|shard| check_shard(dir.as_ref(), shard))
+        .collect()
+}
+
+/// Write `bytes` to a file under a name the read side ignores and sync it
+fn write_temp_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(bytes)
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Rewrite the index of `shard` so that it only has its valid entries.
+/// Returns the report of the shard before repair.
+///
+/// Without `compact`, the data file is left as it is apart from truncating
+/// unreferenced bytes at its end, so valid entries keep their offsets and
+/// the repair is a single atomic rename of the index. Data of dropped
+/// entries in the middle of the file stays behind as orphaned data.
+///
+/// With `compact`, the data file is rewritten with only the data of valid
+/// entries, back to back. The data file is renamed into place before the
+/// index; if that is interrupted, the next repair of the shard installs the
+/// pending index first.
+///
+/// An index without data file is removed, but a data file without index is
+/// never touched.
+///
+/// Fails if the shard is being written to, e.g. it is the current shard of
+/// a running `below record`.
+pub fn repair_shard<P: AsRef<Path>>(dir: P, shard: u64, compact: bool) -> Result<ShardReport> {
+    let dir = dir.as_ref();
+    let (index_path, data_path) = shard_paths(dir, shard);
+
+    // Take the same locks as StoreWriter so we never race with it
+    let mut locks = Vec::new();
+    for path in &[&index_path, &data_path] {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to open {}", path.display()));
+            }
+        };
+        nix::fcntl::flock(
+            file.as_raw_fd(),
+            nix::fcntl::FlockArg::LockExclusiveNonblock,
+        )
+        .with_context(|| format!("Shard is in use, failed to lock {}", path.display()))?;
+        locks.push(file);
+    }
+
+    // Names the read side ignores for the files being written
+    let new_index_path = dir.join(format!("fsck_index_{:011}", shard));
+    let new_data_path = dir.join(format!("fsck_data_{:011}", shard));
+    let compacted_index_path = dir.join(format!("fsck_compacted_index_{:011}", shard));
+    // A compacted index without its data was interrupted after the data file
+    // was replaced, and is the only index matching it
+    if compacted_index_path.exists() {
+        if new_data_path.exists() {
+            remove_if_exists(&new_data_path)?;
+            remove_if_exists(&compacted_index_path)?;
+        } else {
+            fs::rename(&compacted_index_path, &index_path)
+                .with_context(|| format!("Failed to replace {}", index_path.display()))?;
+        }
+    }
+
+    let index = read_file(&index_path)?;
+    let data = read_file(&data_path)?;
+    let shard_time = get_shard_time(dir)?;
+    let has_dict = dict_path(dir, shard).exists();
+    let (report, mut valid) = check(
+        shard,
+        shard_time,
+        index.as_deref(),
+        data.as_deref(),
+        has_dict,
+    );
+    if report.is_clean() {
+        return Ok(report);
+    }
+    if index.is_none() {
+        bail!(
+            "Index file is missing, cannot recover {}",
+            data_path.display()
+        );
+    }
+    let data = match data {
+        Some(data) => data,
+        None => {
+            fs::remove_file(&index_path)
+                .with_context(|| format!("Failed to remove {}", index_path.display()))?;
+            return Ok(report);
+        }
+    };
+
+    if compact {
+        let mut new_data = Vec::with_capacity(data.len());
+        for entry in valid.iter_mut() {
+            let begin = entry.offset as usize;
+            new_data.extend_from_slice(&data[begin..begin + entry.len as usize]);
+            entry.offset = (new_data.len() - entry.len as usize) as u64;
+            entry.index_crc = entry.crc32();
+        }
+        write_temp_file(&new_data_path, &new_data)?;
+        write_temp_file(&compacted_index_path, &serialize_index(&valid))?;
+        fs::rename(&new_data_path, &data_path)
+            .with_context(|| format!("Failed to replace {}", data_path.display()))?;
+        fs::rename(&compacted_index_path, &index_path)
+            .with_context(|| format!("Failed to replace {}", index_path.display()))?;
+        return Ok(report);
+    }
+
+    if report.needs_repair() {
+        write_temp_file(&new_index_path, &serialize_index(&valid))?;
+        fs::rename(&new_index_path, &index_path)
+            .with_context(|| format!("Failed to replace {}", index_path.display()))?;
+    }
+    // Only truncate once the index no longer refers to the dropped tail
+    let end = valid
+        .iter()
+        .map(|entry| entry.offset + entry.len as u64)
+        .max()
+        .unwrap_or(0);
+    if end < data.len() as u64 {
+        OpenOptions::new()
+            .write(true)
+            .open(&data_path)
+            .and_then(|file| file.set_len(end).and_then(|_| file.sync_all()))
+            .with_context(|| format!("Failed to truncate {}", data_path.display()))?;
+    }
+
+    Ok(report)
+}
+
+fn serialize_index(entries: &[IndexEntry]) -> Vec<u8> {
+    let mut index = Vec::with_capacity(entries.len() * INDEX_ENTRY_SIZE);
+    for entry in entries {
+        // unsafe to turn this into a slice - we need this to write it though
+        index.extend_from_slice(unsafe {
+            std::slice::from_raw_parts(entry as *const IndexEntry as *const u8, INDEX_ENTRY_SIZE)
+        });
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, StoreCursor};
+    use crate::{CompressionMode, DataFrame, Direction, Format, StoreWriter, DEFAULT_SHARD_TIME};
+    use slog::Drain;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempdir::TempDir;
+
+    fn get_logger() -> slog::Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    /// Write `count` frames 5 seconds apart into `shard` of `dir`
+    fn write_shard(dir: &Path, shard: u64, count: u64) {
+        let mut writer = StoreWriter::new_with_timestamp(
+            dir,
+            UNIX_EPOCH + Duration::from_secs(shard),
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        for i in 0..count {
+            let mut frame = DataFrame::default();
+            frame.sample.cgroup.memory_current = Some(i as i64);
+            writer
+                .put(
+                    UNIX_EPOCH + Duration::from_secs(shard + i * 5),
+                    &frame,
+                    get_logger(),
+                )
+                .expect("Failed to store data");
+        }
+    }
+
+    /// Assert that the store at `dir` contains exactly the frames `expected`
+    /// of `write_shard`
+    fn assert_frames(dir: &Path, shard: u64, expected: &[u64]) {
+        let mut cursor = StoreCursor::new(get_logger(), dir.to_path_buf());
+        for i in expected {
+            let (ts, frame) = cursor
+                .next(Direction::Forward)
+                .expect("Failed to read sample")
+                .expect("Did not find stored sample");
+            assert_eq!(ts, UNIX_EPOCH + Duration::from_secs(shard + i * 5));
+            assert_eq!(frame.sample.cgroup.memory_current, Some(*i as i64));
+        }
+        assert!(cursor.next(Direction::Forward).unwrap().is_none());
+    }
+
+    #[test]
+    fn check_and_repair() {
+        let dir = TempDir::new("below_fsck_test").expect("tempdir failed");
+        let shard = DEFAULT_SHARD_TIME * 20000;
+        write_shard(dir.path(), shard, 3);
+        let reports = check_store(&dir).expect("Failed to check store");
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_clean());
+        assert_eq!(reports[0].valid_entries, 3);
+
+        // Corrupt the second frame, leave some garbage after the last one and
+        // a partially written index entry.
+        let (index_path, data_path) = shard_paths(dir.path(), shard);
+        let mut data = fs::read(&data_path).expect("Failed to read data file");
+        let frame_len = data.len() / 3;
+        data[frame_len] ^= 0xff;
+        data.extend_from_slice(&[1, 2, 3]);
+        fs::write(&data_path, &data).expect("Failed to write data file");
+        let mut index = fs::read(&index_path).expect("Failed to read index file");
+        index.extend_from_slice(&[0; 7]);
+        fs::write(&index_path, &index).expect("Failed to write index file");
+
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert_eq!(report.entries, 3);
+        assert_eq!(report.valid_entries, 2);
+        assert_eq!(
+            report.problems,
+            vec![
+                Problem::CorruptData {
+                    index_offset: INDEX_ENTRY_SIZE as u64,
+                    timestamp: shard + 5,
+                    data_offset: frame_len as u64,
+                    len: frame_len as u32,
+                },
+                Problem::TruncatedIndex { len: 7 },
+                Problem::OrphanedData {
+                    data_offset: frame_len as u64 * 3,
+                    len: 3,
+                },
+            ]
+        );
+        assert!(report.needs_repair());
+
+        assert_eq!(
+            repair_shard(&dir, shard, false).expect("Failed to repair"),
+            report
+        );
+        // Data is never moved, so dropped entries leave orphaned data behind
+        // and only the garbage at the end is truncated
+        assert_eq!(
+            fs::read(&data_path).expect("Failed to read data file"),
+            &data[..frame_len * 3]
+        );
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert!(!report.needs_repair());
+        assert_eq!(report.valid_entries, 2);
+        assert_eq!(
+            report.problems,
+            vec![Problem::OrphanedData {
+                data_offset: frame_len as u64,
+                len: frame_len as u64,
+            }]
+        );
+        assert_frames(dir.path(), shard, &[0, 2]);
+
+        // Compaction drops the orphaned data of the dropped entry
+        assert_eq!(
+            repair_shard(&dir, shard, true).expect("Failed to compact"),
+            report
+        );
+        assert_eq!(
+            fs::metadata(&data_path)
+                .expect("Failed to stat data file")
+                .len(),
+            frame_len as u64 * 2
+        );
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert!(report.is_clean());
+        assert_eq!(report.valid_entries, 2);
+        assert_frames(dir.path(), shard, &[0, 2]);
+    }
+
+    #[test]
+    fn compact_interrupted() {
+        let dir = TempDir::new("below_fsck_test").expect("tempdir failed");
+        let shard = DEFAULT_SHARD_TIME * 20000;
+        write_shard(dir.path(), shard, 3);
+        let (index_path, data_path) = shard_paths(dir.path(), shard);
+        let index = fs::read(&index_path).expect("Failed to read index file");
+        let mut data = fs::read(&data_path).expect("Failed to read data file");
+        data[0] ^= 0xff;
+        fs::write(&data_path, &data).expect("Failed to write data file");
+        repair_shard(&dir, shard, true).expect("Failed to compact");
+        assert_frames(dir.path(), shard, &[1, 2]);
+
+        // Interrupted after the data file was replaced but not the index
+        let compacted_index_path = dir
+            .path()
+            .join(format!("fsck_compacted_index_{:011}", shard));
+        fs::rename(&index_path, &compacted_index_path).expect("Failed to move index file");
+        fs::write(&index_path, &index).expect("Failed to write index file");
+        assert!(check_shard(&dir, shard).unwrap().needs_repair());
+
+        repair_shard(&dir, shard, true).expect("Failed to compact");
+        assert!(!compacted_index_path.exists());
+        assert!(check_shard(&dir, shard).unwrap().is_clean());
+        assert_frames(dir.path(), shard, &[1, 2]);
+    }
+
+    #[test]
+    fn repair_truncated_data() {
+        let dir = TempDir::new("below_fsck_test").expect("tempdir failed");
+        let shard = DEFAULT_SHARD_TIME * 20000;
+        write_shard(dir.path(), shard, 3);
+        let (_, data_path) = shard_paths(dir.path(), shard);
+        let mut data = fs::read(&data_path).expect("Failed to read data file");
+        let frame_len = data.len() / 3;
+        data.truncate(data.len() - 1);
+        fs::write(&data_path, &data).expect("Failed to write data file");
+
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert_eq!(
+            report.problems,
+            vec![Problem::TruncatedData {
+                index_offset: INDEX_ENTRY_SIZE as u64 * 2,
+                timestamp: shard + 10,
+                data_offset: frame_len as u64 * 2,
+                len: frame_len as u32,
+            }]
+        );
+        assert_eq!(
+            repair_shard(&dir, shard, false).expect("Failed to repair"),
+            report
+        );
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert!(!report.needs_repair());
+        assert_eq!(report.entries, 2);
+        assert_frames(dir.path(), shard, &[0, 1]);
+    }
+
+    #[test]
+    fn repair_non_monotonic() {
+        let dir = TempDir::new("below_fsck_test").expect("tempdir failed");
+        let shard = DEFAULT_SHARD_TIME * 20000;
+        write_shard(dir.path(), shard, 3);
+        // Move the last entry back in time to the first one
+        let (index_path, _) = shard_paths(dir.path(), shard);
+        let mut index = fs::read(&index_path).expect("Failed to read index file");
+        let last = &mut index[INDEX_ENTRY_SIZE * 2..];
+        let mut entry = unsafe { std::ptr::read_unaligned(last.as_ptr() as *const IndexEntry) };
+        entry.timestamp = shard;
+        entry.index_crc = entry.crc32();
+        unsafe { std::ptr::write_unaligned(last.as_mut_ptr() as *mut IndexEntry, entry) };
+        fs::write(&index_path, &index).expect("Failed to write index file");
+
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert_eq!(report.valid_entries, 2);
+        assert_eq!(
+            report.problems[0],
+            Problem::NonMonotonicTimestamp {
+                index_offset: INDEX_ENTRY_SIZE as u64 * 2,
+                timestamp: shard,
+                previous: shard + 5,
+            }
+        );
+        assert_eq!(
+            repair_shard(&dir, shard, false).expect("Failed to repair"),
+            report
+        );
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert!(!report.needs_repair());
+        assert_frames(dir.path(), shard, &[0, 1]);
+    }
+
+    #[test]
+    fn repair_missing_files() {
+        let dir = TempDir::new("below_fsck_test").expect("tempdir failed");
+        let shard = DEFAULT_SHARD_TIME * 20000;
+        write_shard(dir.path(), shard, 2);
+        let (index_path, data_path) = shard_paths(dir.path(), shard);
+
+        // Data without index cannot be recovered but is never removed
+        let index = fs::read(&index_path).expect("Failed to read index file");
+        fs::remove_file(&index_path).expect("Failed to remove index file");
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert_eq!(report.problems, vec![Problem::MissingIndexFile]);
+        assert!(repair_shard(&dir, shard, false).is_err());
+        assert!(data_path.exists());
+
+        // An index without data is useless and removed
+        fs::write(&index_path, &index).expect("Failed to write index file");
+        fs::remove_file(&data_path).expect("Failed to remove data file");
+        let report = check_shard(&dir, shard).expect("Failed to check shard");
+        assert_eq!(report.problems, vec![Problem::MissingDataFile]);
+        assert_eq!(
+            repair_shard(&dir, shard, false).expect("Failed to repair"),
+            report
+        );
+        assert!(!index_path.exists());
+        assert!(check_store(&dir).expect("Failed to check store").is_empty());
+    }
+}
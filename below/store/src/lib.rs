@@ -13,18 +13,18 @@
 // limitations under the License.
 
 use std::fs::{self, File, OpenOptions};
-use std::io::{ErrorKind, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 use slog::warn;
 use static_assertions::const_assert;
 
-use crate::cursor::KeyedCursor;
+use crate::cursor::{Cursor, KeyedCursor};
 
 use common::fileutil::get_dir_size;
 use common::open_source_shim;
@@ -33,7 +33,12 @@ use common::util::get_unix_timestamp;
 use model::{self, Model};
 
 pub mod advance;
+pub mod annotation;
 pub mod cursor;
+pub mod fsck;
+pub mod merge;
+pub mod rollup;
+pub mod snapshot;
 #[cfg(test)]
 mod test;
 
@@ -41,6 +46,9 @@ pub type Advance = advance::Advance<DataFrame, Model>;
 
 open_source_shim!();
 
+#[cfg(not(fbcode_build))]
+pub use crate::remote_store::start_server as start_remote_store_server;
+
 /// This data store works as follows:
 ///
 /// Each data item (e.g. DataFrame) is simply appended to a data file.
@@ -57,17 +65,47 @@ open_source_shim!();
 /// Data and Index files are append-only and never modified (only ever
 /// removed).
 ///
-/// Data and Index files are sharded by SHARD_TIME - e.g. any one file
-/// only contains data or index entries whose timestamps are congruent
-/// modulo SHARD_TIME. This allows data and index files to be cleaned
-/// up by just unlinking the files.
+/// Data and Index files are sharded by the shard time of the store -
+/// e.g. any one file only contains data or index entries whose
+/// timestamps are congruent modulo shard time. This allows data and
+/// index files to be cleaned up by just unlinking the files.
+///
+/// The shard time is chosen when the store is created and recorded in
+/// the store's meta file. Stores without one use DEFAULT_SHARD_TIME.
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DataFrame {
     pub sample: model::Sample,
 }
 
-const SHARD_TIME: u64 = 24 * 60 * 60;
+pub const DEFAULT_SHARD_TIME: u64 = 24 * 60 * 60;
+
+/// Name of the file in the store directory holding StoreMeta
+const STORE_META_FILE: &str = "meta";
+
+/// Properties of a store that all readers and writers must agree on
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct StoreMeta {
+    /// Length of each shard in seconds
+    shard_time: u64,
+}
+
+/// Number of frames a shard's zstd dictionary is trained from
+const DICT_TRAINING_FRAMES: usize = 64;
+
+/// Maximum size of a trained zstd dictionary
+const DICT_MAX_SIZE: usize = 110 * 1024;
+
+/// Number of frames from one keyframe to the next, including the keyframe
+const KEYFRAME_INTERVAL: usize = 60;
+
+/// Size of the header of a DELTA data frame, i.e. the timestamp of its
+/// keyframe
+const DELTA_HEADER_SIZE: usize = std::mem::size_of::<u64>();
+
+/// Size of the header of a SUBSEC data frame, i.e. the nanoseconds past the
+/// timestamp of its index entry
+const SUBSEC_HEADER_SIZE: usize = std::mem::size_of::<u32>();
 
 bitflags! {
     struct IndexEntryFlags: u32 {
@@ -76,6 +114,19 @@ bitflags! {
         /// serialization is set to the default (also CBOR in the
         /// case of open source build).
         const CBOR = 0x2;
+        /// If set, data item is compressed (COMPRESSED is also set)
+        /// against the zstd dictionary of its shard.
+        const ZSTD_DICT = 0x4;
+        /// If set, data item is compressed (COMPRESSED is also set)
+        /// against an earlier keyframe of its shard. The data starts with
+        /// the little endian timestamp of the keyframe.
+        const DELTA = 0x8;
+        /// If set, the data item was sampled within the second of its
+        /// timestamp, e.g. during a capture burst. The data starts with the
+        /// little endian nanoseconds past the timestamp, followed by the
+        /// data as described by the other flags. Several entries may then
+        /// share a timestamp.
+        const SUBSEC = 0x10;
     }
 }
 
@@ -114,8 +165,20 @@ pub struct StoreWriter {
     data_len: u64,
     /// Active shard
     shard: u64,
-    /// Individually compress data frames
-    compress: bool,
+    /// Length of each shard in seconds
+    shard_time: u64,
+    /// How data frames are compressed
+    compression_mode: CompressionMode,
+    /// Dictionary of the active shard, if any
+    dict: Option<Vec<u8>>,
+    /// Serialized frames collected to train the dictionary of the active
+    /// shard. Only used with CompressionMode::ZstdDictionary.
+    dict_samples: Vec<Vec<u8>>,
+    /// Timestamp and serialized frame of the last keyframe written to the
+    /// active shard. Only used with CompressionMode::ZstdDelta.
+    keyframe: Option<(u64, Vec<u8>)>,
+    /// Number of frames written since the last keyframe
+    frames_since_keyframe: usize,
     /// Serialization format of data frames
     format: Format,
 }
@@ -142,6 +205,68 @@ fn get_index_files(path: &Path) -> Result<Vec<String>> {
     Ok(entries)
 }
 
+fn read_store_meta(path: &Path) -> Result<Option<StoreMeta>> {
+    let meta_path = path.join(STORE_META_FILE);
+    match fs::read(&meta_path) {
+        Ok(bytes) => serde_cbor::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse store meta: {}", meta_path.display()))
+            .map(Some),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => {
+            Err(e).with_context(|| format!("Failed to read store meta: {}", meta_path.display()))
+        }
+    }
+}
+
+fn write_store_meta(path: &Path, meta: &StoreMeta) -> Result<()> {
+    let meta_path = path.join(STORE_META_FILE);
+    // Write then rename so readers never see a partial file
+    let tmp_path = path.join(format!("{}.tmp", STORE_META_FILE));
+    fs::write(&tmp_path, serde_cbor::to_vec(meta)?)
+        .with_context(|| format!("Failed to write store meta: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &meta_path)
+        .with_context(|| format!("Failed to write store meta: {}", meta_path.display()))
+}
+
+/// Get the shard time of the store at `path`, which is DEFAULT_SHARD_TIME
+/// unless the store recorded another one.
+pub fn get_shard_time<P: AsRef<Path>>(path: P) -> Result<u64> {
+    Ok(read_store_meta(path.as_ref())?.map_or(DEFAULT_SHARD_TIME, |meta| meta.shard_time))
+}
+
+/// Get the shard time of the store at `path` for writing, creating the
+/// store and recording `shard_time` (or DEFAULT_SHARD_TIME) if needed.
+/// Fails if the store already uses a different shard time than requested.
+fn init_shard_time(path: &Path, shard_time: Option<u64>) -> Result<u64> {
+    if shard_time == Some(0) {
+        bail!("Shard time must be greater than 0");
+    }
+    fs::create_dir_all(path)
+        .with_context(|| format!("Failed to create store path: {}", path.display()))?;
+
+    // Stores written before the meta file existed always use the default
+    let recorded = match read_store_meta(path)? {
+        Some(meta) => Some(meta.shard_time),
+        None if !get_index_files(path)?.is_empty() => Some(DEFAULT_SHARD_TIME),
+        None => None,
+    };
+    match (recorded, shard_time) {
+        (Some(recorded), Some(shard_time)) if recorded != shard_time => bail!(
+            "Store at {} uses a shard time of {}s and cannot be changed to {}s",
+            path.display(),
+            recorded,
+            shard_time
+        ),
+        _ => {}
+    }
+    let shard_time = recorded.or(shard_time).unwrap_or(DEFAULT_SHARD_TIME);
+    let meta = StoreMeta { shard_time };
+    if read_store_meta(path)?.as_ref() != Some(&meta) {
+        write_store_meta(path, &meta)?;
+    }
+    Ok(shard_time)
+}
+
 enum SerializedFrame<'a> {
     Bytes(bytes::Bytes),
     Copy(Vec<u8>),
@@ -158,36 +283,148 @@ impl<'a> SerializedFrame<'a> {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Format {
     Thrift,
     Cbor,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CompressionMode {
+    None,
+    /// Each data frame is zstd compressed on its own
+    Zstd,
+    /// Data frames are zstd compressed against a dictionary trained from
+    /// the first frames of each shard. Frames written before the dictionary
+    /// is available are compressed on their own.
+    ZstdDictionary,
+    /// Every KEYFRAME_INTERVAL-th frame of a shard is a keyframe compressed
+    /// on its own. Frames in between only store their difference to the
+    /// previous keyframe.
+    ZstdDelta,
+}
+
+fn dict_path(path: &Path, shard: u64) -> PathBuf {
+    path.join(format!("dict_{:011}", shard))
+}
+
+/// Read the zstd dictionary of `shard`. Returns None if it has none.
+fn read_dict(path: &Path, shard: u64) -> Result<Option<Vec<u8>>> {
+    let dict_path = dict_path(path, shard);
+    match fs::read(&dict_path) {
+        Ok(dict) => Ok(Some(dict)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", dict_path.display())),
+    }
+}
+
+fn compress_with_dict(data: &[u8], dict: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = zstd::stream::write::Encoder::with_dictionary(Vec::new(), 0, dict)?;
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn decompress_with_dict(data: &[u8], dict: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoder = zstd::stream::read::Decoder::with_dictionary(data, dict)?;
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Encode `data` as a DELTA data frame against the serialized keyframe
+/// written at `keyframe_ts`. The keyframe is used as raw content dictionary,
+/// so anything shared with it is stored as a back reference.
+fn compress_delta(data: &[u8], keyframe_ts: u64, keyframe: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut delta = keyframe_ts.to_le_bytes().to_vec();
+    delta.extend_from_slice(&compress_with_dict(data, keyframe)?);
+    Ok(delta)
+}
+
+/// Split a DELTA data frame into the timestamp of its keyframe and the
+/// compressed data. Returns None if it is too short.
+fn split_delta(data: &[u8]) -> Option<(u64, &[u8])> {
+    if data.len() < DELTA_HEADER_SIZE {
+        return None;
+    }
+    let (header, delta) = data.split_at(DELTA_HEADER_SIZE);
+    Some((u64::from_le_bytes(header.try_into().ok()?), delta))
+}
+
+/// Split the data of `index_entry` into the nanoseconds past its timestamp
+/// and the rest of the data. Returns None if a SUBSEC data frame is too
+/// short.
+fn split_subsec<'a>(index_entry: &IndexEntry, data: &'a [u8]) -> Option<(u32, &'a [u8])> {
+    if !index_entry.flags.contains(IndexEntryFlags::SUBSEC) {
+        return Some((0, data));
+    }
+    if data.len() < SUBSEC_HEADER_SIZE {
+        return None;
+    }
+    let (header, data) = data.split_at(SUBSEC_HEADER_SIZE);
+    Some((u32::from_le_bytes(header.try_into().ok()?), data))
+}
+
 impl StoreWriter {
     /// Create a new `StoreWriter` that writes data to `path` directory. Data
     /// serialized with `format`.
     ///
-    /// If `compress` is set, dataframes are zstd compressed.
-    pub fn new<P: AsRef<Path>>(path: P, compress: bool, format: Format) -> Result<Self> {
-        Self::new_with_timestamp(path, SystemTime::now(), compress, format)
+    /// Dataframes are compressed according to `compression_mode`.
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        compression_mode: CompressionMode,
+        format: Format,
+    ) -> Result<Self> {
+        Self::new_with_timestamp(path, SystemTime::now(), compression_mode, format)
+    }
+
+    /// Same as `new`, but a newly created store uses shards of `shard_time`
+    /// seconds. Fails if the existing store at `path` uses a different shard
+    /// time.
+    pub fn new_with_shard_time<P: AsRef<Path>>(
+        path: P,
+        shard_time: u64,
+        compression_mode: CompressionMode,
+        format: Format,
+    ) -> Result<Self> {
+        Self::open(
+            path,
+            SystemTime::now(),
+            Some(shard_time),
+            compression_mode,
+            format,
+        )
     }
 
     pub fn new_with_timestamp<P: AsRef<Path>>(
         path: P,
         timestamp: SystemTime,
-        compress: bool,
+        compression_mode: CompressionMode,
+        format: Format,
+    ) -> Result<Self> {
+        Self::open(path, timestamp, None, compression_mode, format)
+    }
+
+    /// Same as `new_with_timestamp`, but a newly created store uses shards
+    /// of `shard_time` seconds if given. Fails if the existing store at
+    /// `path` uses a different shard time.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        timestamp: SystemTime,
+        shard_time: Option<u64>,
+        compression_mode: CompressionMode,
         format: Format,
     ) -> Result<Self> {
-        let shard = calculate_shard(timestamp);
+        let shard_time = init_shard_time(path.as_ref(), shard_time)?;
+        let shard = calculate_shard(timestamp, shard_time);
 
-        Self::new_with_shard(path, shard, compress, format)
+        Self::new_with_shard(path, shard, shard_time, compression_mode, format)
     }
 
     fn new_with_shard<P: AsRef<Path>>(
         path: P,
         shard: u64,
-        compress: bool,
+        shard_time: u64,
+        compression_mode: CompressionMode,
         format: Format,
     ) -> Result<Self> {
         if !path.as_ref().is_dir() {
@@ -267,17 +504,55 @@ impl StoreWriter {
                 )
             })?
             .len();
+        // Keep using the dictionary of a shard we are appending to
+        let dict = match compression_mode {
+            CompressionMode::ZstdDictionary => read_dict(path.as_ref(), shard)?,
+            _ => None,
+        };
         Ok(StoreWriter {
             dir: path.as_ref().to_path_buf(),
             index,
             data,
             data_len,
             shard,
-            compress,
+            shard_time,
+            compression_mode,
+            dict,
+            dict_samples: Vec::new(),
+            keyframe: None,
+            frames_since_keyframe: 0,
             format,
         })
     }
 
+    /// Collect `frame_bytes` for training the dictionary of the current shard
+    /// and train it once enough frames are collected. The dictionary is
+    /// written to disk before any frame is compressed with it.
+    fn train_dict(&mut self, frame_bytes: &[u8], logger: &slog::Logger) -> Result<()> {
+        self.dict_samples.push(frame_bytes.to_vec());
+        if self.dict_samples.len() < DICT_TRAINING_FRAMES {
+            return Ok(());
+        }
+        let samples = std::mem::take(&mut self.dict_samples);
+        let dict = match zstd::dict::from_samples(samples.as_slice(), DICT_MAX_SIZE) {
+            Ok(dict) => dict,
+            Err(e) => {
+                // Not fatal, try again with the next frames
+                warn!(logger, "Failed to train zstd dictionary: {}", e);
+                return Ok(());
+            }
+        };
+        let dict_path = dict_path(&self.dir, self.shard);
+        // Write then rename so readers never see a partial dictionary
+        let tmp_path = dict_path.with_extension("tmp");
+        fs::write(&tmp_path, &dict)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &dict_path)
+            .with_context(|| format!("Failed to write {}", dict_path.display()))?;
+        self.dict = Some(dict);
+        Ok(())
+    }
+
     /// Store data with corresponding timestamp in current shard. Fails if data
     /// does not belong to current shard. Errors may be returned if file
     /// operations fail,
@@ -287,7 +562,7 @@ impl StoreWriter {
         data: &DataFrame,
         logger: slog::Logger,
     ) -> Result<()> {
-        let shard = calculate_shard(timestamp);
+        let shard = calculate_shard(timestamp, self.shard_time);
         if shard != self.shard {
             panic!("Can't write data to shard as it belongs to different shard")
         }
@@ -295,18 +570,65 @@ impl StoreWriter {
         // most filesystems do not provide ordering guarantees for
         // appends to different files anyways. We just need to handle
         // various failure cases on the read side.
+        let mut flags = IndexEntryFlags::empty();
+        // Set if this frame becomes the new keyframe
+        let mut keyframe = None;
         let serialized = {
             let frame_bytes =
                 serialize_frame(data, self.format).context("Failed to serialize data frame")?;
-            if self.compress {
-                SerializedFrame::Copy(
-                    zstd::block::compress(&frame_bytes, 0)
-                        .context("Failed to compress data serialized data frame")?,
-                )
-            } else {
-                SerializedFrame::Bytes(frame_bytes)
+            if self.compression_mode == CompressionMode::ZstdDictionary && self.dict.is_none() {
+                self.train_dict(&frame_bytes, &logger)?;
+            }
+            match (self.compression_mode, self.dict.as_ref()) {
+                (CompressionMode::None, _) => SerializedFrame::Bytes(frame_bytes),
+                (CompressionMode::ZstdDictionary, Some(dict)) => {
+                    flags |= IndexEntryFlags::COMPRESSED | IndexEntryFlags::ZSTD_DICT;
+                    SerializedFrame::Copy(
+                        compress_with_dict(&frame_bytes, dict)
+                            .context("Failed to compress data serialized data frame")?,
+                    )
+                }
+                (CompressionMode::ZstdDelta, _) => match self.keyframe.as_ref() {
+                    Some((keyframe_ts, keyframe_bytes))
+                        if self.frames_since_keyframe + 1 < KEYFRAME_INTERVAL =>
+                    {
+                        flags |= IndexEntryFlags::COMPRESSED | IndexEntryFlags::DELTA;
+                        SerializedFrame::Copy(
+                            compress_delta(&frame_bytes, *keyframe_ts, keyframe_bytes)
+                                .context("Failed to compress data serialized data frame")?,
+                        )
+                    }
+                    _ => {
+                        flags |= IndexEntryFlags::COMPRESSED;
+                        keyframe = Some(frame_bytes.to_vec());
+                        SerializedFrame::Copy(
+                            zstd::block::compress(&frame_bytes, 0)
+                                .context("Failed to compress data serialized data frame")?,
+                        )
+                    }
+                },
+                _ => {
+                    flags |= IndexEntryFlags::COMPRESSED;
+                    SerializedFrame::Copy(
+                        zstd::block::compress(&frame_bytes, 0)
+                            .context("Failed to compress data serialized data frame")?,
+                    )
+                }
             }
         };
+        // Frames at whole seconds are written as before so older readers
+        // can still read them
+        let subsec_nanos = timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let serialized = if subsec_nanos != 0 {
+            flags |= IndexEntryFlags::SUBSEC;
+            let mut data = subsec_nanos.to_le_bytes().to_vec();
+            data.extend_from_slice(serialized.data());
+            SerializedFrame::Copy(data)
+        } else {
+            serialized
+        };
         // Appends to data file are large and cannot be atomic. We may have
         // partial writes that increases the file size without updating the
         // stored state. Thus always read actual data file length.
@@ -335,10 +657,6 @@ impl StoreWriter {
         self.data_len += serialized.data().len() as u64;
         let data_crc = serialized.data().crc32();
 
-        let mut flags = IndexEntryFlags::empty();
-        if self.compress {
-            flags |= IndexEntryFlags::COMPRESSED;
-        }
         match self.format {
             Format::Thrift => {}
             Format::Cbor => flags |= IndexEntryFlags::CBOR,
@@ -366,6 +684,14 @@ impl StoreWriter {
                 .write_all(entry_slice)
                 .context("Failed to write entry to index file")?;
         }
+        // Only track frames that made it to disk so readers can always find
+        // the keyframe a DELTA frame refers to
+        if let Some(keyframe) = keyframe {
+            self.keyframe = Some((index_entry.timestamp, keyframe));
+            self.frames_since_keyframe = 0;
+        } else {
+            self.frames_since_keyframe += 1;
+        }
         Ok(())
     }
 
@@ -378,11 +704,16 @@ impl StoreWriter {
         data: &DataFrame,
         logger: slog::Logger,
     ) -> Result<bool> {
-        let shard = calculate_shard(timestamp);
+        let shard = calculate_shard(timestamp, self.shard_time);
         if shard != self.shard {
             // We just recreate the StoreWriter since this is a new shard
-            let mut writer =
-                Self::new_with_shard(self.dir.as_path(), shard, self.compress, self.format)?;
+            let mut writer = Self::new_with_shard(
+                self.dir.as_path(),
+                shard,
+                self.shard_time,
+                self.compression_mode,
+                self.format,
+            )?;
             // Set self to new shard only if we succeed in writing the first
             // frame. If we don't do this, we may "forget" returning a true
             // for a new shard where the first write fails.
@@ -454,6 +785,30 @@ impl StoreWriter {
                 }
                 _ => {}
             };
+
+            let dict_path = dict_path(&self.dir, entry_shard);
+
+            match std::fs::remove_file(&dict_path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    return Err(e).context(format!(
+                        "Failed to remove dictionary file: {}",
+                        dict_path.display()
+                    ));
+                }
+                _ => {}
+            };
+
+            let annotation_path = annotation::annotation_path(&self.dir, entry_shard);
+
+            match std::fs::remove_file(&annotation_path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    return Err(e).context(format!(
+                        "Failed to remove annotation file: {}",
+                        annotation_path.display()
+                    ));
+                }
+                _ => {}
+            };
         }
         Ok(false)
     }
@@ -463,7 +818,7 @@ impl StoreWriter {
     /// We do not modify index and data files. We just look for files
     /// which can only contain earlier data and remove them.
     pub fn discard_earlier(&self, timestamp: SystemTime, logger: slog::Logger) -> Result<()> {
-        let shard = calculate_shard(timestamp);
+        let shard = calculate_shard(timestamp, self.shard_time);
         self.discard_until(|shard_timestamp| shard_timestamp >= shard, logger.clone())?;
         Ok(())
     }
@@ -488,7 +843,7 @@ impl StoreWriter {
 }
 
 /// Direction to scan for next sample
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     Forward,
     Reverse,
@@ -510,6 +865,21 @@ impl Direction {
     }
 }
 
+/// Get the timestamp of the last entry of the store at `path`. None if the
+/// store is empty or does not exist.
+fn last_timestamp(path: &Path, logger: &slog::Logger) -> Result<Option<u64>> {
+    if !path.is_dir() {
+        return Ok(None);
+    }
+    let mut cursor = cursor::StoreCursor::new(logger.clone(), path.to_path_buf());
+    while cursor.advance(Direction::Reverse)? {
+        if let Some(ts) = cursor.get_key() {
+            return Ok(Some(ts));
+        }
+    }
+    Ok(None)
+}
+
 pub fn read_next_sample<P: AsRef<Path>>(
     path: P,
     timestamp: SystemTime,
@@ -517,7 +887,20 @@ pub fn read_next_sample<P: AsRef<Path>>(
     logger: slog::Logger,
 ) -> Result<Option<(SystemTime, DataFrame)>> {
     let mut cursor = cursor::StoreCursor::new(logger, path.as_ref().to_path_buf());
-    cursor.get_next(&get_unix_timestamp(timestamp), direction)
+    let mut item = cursor.get_next(&get_unix_timestamp(timestamp), direction)?;
+    // Cursor keys are whole seconds, so skip the frames of the same second
+    // on the wrong side of a sub-second timestamp
+    while let Some((ts, _)) = item.as_ref() {
+        let passed = match direction {
+            Direction::Forward => *ts < timestamp,
+            Direction::Reverse => *ts > timestamp,
+        };
+        if !passed {
+            break;
+        }
+        item = cursor.next(direction)?;
+    }
+    Ok(item)
 }
 
 pub trait Store {
@@ -538,6 +921,8 @@ pub trait Store {
     //   sample
     // * forward search a target that has timestamp later than the last recorded
     //   sample
+    // Samples are not necessarily whole seconds apart, e.g. during a capture
+    // burst, so the timestamp must be compared with sub-second precision.
     fn get_sample_at_timestamp(
         &mut self,
         timestamp: SystemTime,
@@ -590,8 +975,7 @@ impl Store for RemoteStore {
         direction: Direction,
         _logger: slog::Logger,
     ) -> Result<Option<(SystemTime, Self::SampleType)>> {
-        self.store
-            .get_frame(get_unix_timestamp(timestamp), direction)
+        self.store.get_frame(timestamp, direction)
     }
 }
 
@@ -659,10 +1043,10 @@ impl Crc32 for IndexEntry {
 }
 
 // This is the timestamp rounded down to the nearest
-// multiple of SHARD_TIME
-fn calculate_shard(timestamp: SystemTime) -> u64 {
+// multiple of shard_time
+fn calculate_shard(timestamp: SystemTime, shard_time: u64) -> u64 {
     let timestamp_secs = get_unix_timestamp(timestamp);
-    let shard_rem = timestamp_secs % SHARD_TIME;
+    let shard_rem = timestamp_secs % shard_time;
     timestamp_secs - shard_rem
 }
 
@@ -674,6 +1058,9 @@ mod tests {
     use std::time::Duration;
     use tempdir::TempDir;
 
+    // Shard time of all stores created by tests
+    const SHARD_TIME: u64 = DEFAULT_SHARD_TIME;
+
     fn get_logger() -> slog::Logger {
         let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
         slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
@@ -697,14 +1084,28 @@ mod tests {
             paste! {
                 #[test]
                 fn [<$name _compressed_cbor>]() {
-                    $func(true, Format::Cbor);
+                    $func(CompressionMode::Zstd, Format::Cbor);
+                }
+            }
+
+            paste! {
+                #[test]
+                fn [<$name _dict_compressed_cbor>]() {
+                    $func(CompressionMode::ZstdDictionary, Format::Cbor);
+                }
+            }
+
+            paste! {
+                #[test]
+                fn [<$name _delta_compressed_cbor>]() {
+                    $func(CompressionMode::ZstdDelta, Format::Cbor);
                 }
             }
 
             paste! {
                 #[test]
                 fn [<$name _uncompressed_cbor>]() {
-                    $func(false, Format::Cbor);
+                    $func(CompressionMode::None, Format::Cbor);
                 }
             }
 
@@ -712,7 +1113,7 @@ mod tests {
                 #[cfg(fbcode_build)]
                 #[test]
                 fn [<$name _compressed_thrift>]() {
-                    $func(true, Format::Thrift);
+                    $func(CompressionMode::Zstd, Format::Thrift);
                 }
             }
 
@@ -720,7 +1121,7 @@ mod tests {
                 #[cfg(fbcode_build)]
                 #[test]
                 fn [<$name _uncompressed_thrift>]() {
-                    $func(false, Format::Thrift);
+                    $func(CompressionMode::None, Format::Thrift);
                 }
             }
         };
@@ -734,12 +1135,13 @@ mod tests {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
 
-        // States, (compress, format), that we transition between when writing
+        // States, (compression_mode, format), that we transition between when
+        // writing
         let states = vec![
-            (false, Format::Thrift),
-            (false, Format::Cbor),
-            (true, Format::Thrift),
-            (true, Format::Cbor),
+            (CompressionMode::None, Format::Thrift),
+            (CompressionMode::None, Format::Cbor),
+            (CompressionMode::Zstd, Format::Thrift),
+            (CompressionMode::Zstd, Format::Cbor),
         ];
         // State sequence that contains all possible transitions
         let state_sequence = states
@@ -748,9 +1150,9 @@ mod tests {
             .flat_map(|(a, b)| vec![a, b])
             .collect::<Vec<_>>();
 
-        for (i, (compress, format)) in state_sequence.iter().enumerate() {
+        for (i, (compression_mode, format)) in state_sequence.iter().enumerate() {
             let mut writer =
-                StoreWriter::new(&dir, *compress, *format).expect("Failed to create store");
+                StoreWriter::new(&dir, *compression_mode, *format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(i as i64);
 
@@ -760,7 +1162,7 @@ mod tests {
         }
 
         // Test reading all the samples
-        for (i, (_compress, _format)) in state_sequence.iter().enumerate() {
+        for (i, (_compression_mode, _format)) in state_sequence.iter().enumerate() {
             let frame = read_next_sample(
                 &dir,
                 ts + Duration::from_secs(i as u64),
@@ -775,18 +1177,18 @@ mod tests {
     }
 
     store_test!(create_writer, _create_writer);
-    fn _create_writer(compress: bool, format: Format) {
+    fn _create_writer(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
-        StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+        StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
     }
 
     store_test!(simple_put_read, _simple_put_read);
-    fn _simple_put_read(compress: bool, format: Format) {
+    fn _simple_put_read(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -802,19 +1204,76 @@ mod tests {
         assert_eq!(frame.1.sample.cgroup.memory_current, Some(333));
     }
 
+    store_test!(subsec_put_read, _subsec_put_read);
+    fn _subsec_put_read(compression_mode: CompressionMode, format: Format) {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
+        // Regular frames around a burst within the second of the second frame
+        let timestamps: Vec<SystemTime> = [0, 1000, 1200, 1400, 1600, 3000]
+            .iter()
+            .map(|ms| ts + Duration::from_millis(*ms))
+            .collect();
+        {
+            let mut writer = StoreWriter::new_with_timestamp(&dir, ts, compression_mode, format)
+                .expect("Failed to create store");
+            for (i, timestamp) in timestamps.iter().enumerate() {
+                let mut frame = DataFrame::default();
+                frame.sample.cgroup.memory_current = Some(i as i64);
+                writer
+                    .put(*timestamp, &frame, get_logger())
+                    .expect("Failed to store data");
+            }
+        }
+
+        let read = |timestamp: SystemTime, direction: Direction| {
+            read_next_sample(&dir, timestamp, direction, get_logger())
+                .expect("Failed to read sample")
+                .map(|(ts, frame)| (ts, frame.sample.cgroup.memory_current.unwrap()))
+        };
+        // Every frame is found at its precise timestamp in both directions
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            for direction in [Direction::Forward, Direction::Reverse] {
+                assert_eq!(read(*timestamp, direction), Some((*timestamp, i as i64)));
+            }
+        }
+        // Frames of the same second on the wrong side are skipped
+        assert_eq!(
+            read(ts + Duration::from_millis(1300), Direction::Forward),
+            Some((timestamps[3], 3))
+        );
+        assert_eq!(
+            read(ts + Duration::from_millis(1300), Direction::Reverse),
+            Some((timestamps[2], 2))
+        );
+        assert_eq!(
+            read(ts + Duration::from_millis(1700), Direction::Forward),
+            Some((timestamps[5], 5))
+        );
+        assert_eq!(
+            read(ts + Duration::from_millis(2500), Direction::Reverse),
+            Some((timestamps[4], 4))
+        );
+        assert_eq!(
+            read(ts + Duration::from_millis(500), Direction::Forward),
+            Some((timestamps[1], 1))
+        );
+    }
+
     store_test!(put_new_shard, _put_new_shard);
-    fn _put_new_shard(compress: bool, format: Format) {
+    fn _put_new_shard(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let now = SystemTime::now();
         // Ensure that the follow writes (within 60s) are to the same shard
-        let ts = if calculate_shard(now) == calculate_shard(now + Duration::from_secs(60)) {
+        let ts = if calculate_shard(now, SHARD_TIME)
+            == calculate_shard(now + Duration::from_secs(60), SHARD_TIME)
+        {
             now
         } else {
             now + Duration::from_secs(60)
         };
 
         {
-            let mut writer = StoreWriter::new_with_timestamp(&dir, ts, compress, format)
+            let mut writer = StoreWriter::new_with_timestamp(&dir, ts, compression_mode, format)
                 .expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(111);
@@ -849,7 +1308,7 @@ mod tests {
             let mut writer = StoreWriter::new_with_timestamp(
                 &dir,
                 ts + Duration::from_secs(SHARD_TIME + 1),
-                compress,
+                compression_mode,
                 format,
             )
             .expect("Failed to create store");
@@ -909,13 +1368,13 @@ mod tests {
     }
 
     store_test!(put_read_corrupt_data, _put_read_corrupt_data);
-    fn _put_read_corrupt_data(compress: bool, format: Format) {
+    fn _put_read_corrupt_data(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
         let ts_next = ts + Duration::from_secs(1);
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -963,12 +1422,12 @@ mod tests {
         read_past_the_end_returns_none,
         _read_past_the_end_returns_none
     );
-    fn _read_past_the_end_returns_none(compress: bool, format: Format) {
+    fn _read_past_the_end_returns_none(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -988,12 +1447,12 @@ mod tests {
     }
 
     store_test!(read_iterates_appropriately, _read_iterates_appropriately);
-    fn _read_iterates_appropriately(compress: bool, format: Format) {
+    fn _read_iterates_appropriately(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1023,12 +1482,12 @@ mod tests {
         put_and_read_work_across_shards,
         _put_and_read_work_across_shards
     );
-    fn _put_and_read_work_across_shards(compress: bool, format: Format) {
+    fn _put_and_read_work_across_shards(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1055,12 +1514,12 @@ mod tests {
     }
 
     store_test!(read_reverse, _read_reverse);
-    fn _read_reverse(compress: bool, format: Format) {
+    fn _read_reverse(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1077,12 +1536,12 @@ mod tests {
     }
 
     store_test!(read_reverse_across_shards, _read_reverse_across_shards);
-    fn _read_reverse_across_shards(compress: bool, format: Format) {
+    fn _read_reverse_across_shards(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1109,12 +1568,12 @@ mod tests {
     }
 
     store_test!(discard_earlier, _discard_earlier);
-    fn _discard_earlier(compress: bool, format: Format) {
+    fn _discard_earlier(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1154,12 +1613,15 @@ mod tests {
     }
 
     store_test!(try_discard_until_size, _try_discard_until_size);
-    fn _try_discard_until_size(compress: bool, format: Format) {
+    fn _try_discard_until_size(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let dir_path_buf = dir.path().to_path_buf();
         let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
         let mut shard_sizes = Vec::new();
-        let mut writer = StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+        let mut writer =
+            StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
+        // Size of the store before any sample is written, i.e. its meta file
+        let initial_size = get_dir_size(dir_path_buf.clone());
 
         // Write n samples from timestamp 1 seconds apart, returning size
         // increase of the store directory.
@@ -1191,7 +1653,7 @@ mod tests {
         for i in 0..num_shards {
             shard_sizes.push(write(ts + Duration::from_secs(SHARD_TIME * i), i + 1));
         }
-        let total_size = shard_sizes.iter().sum::<u64>();
+        let total_size = initial_size + shard_sizes.iter().sum::<u64>();
 
         {
             // Nothing is discarded
@@ -1273,10 +1735,10 @@ mod tests {
     }
 
     store_test!(flock_protects, _flock_protects);
-    fn _flock_protects(compress: bool, format: Format) {
+    fn _flock_protects(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
-        let shard = calculate_shard(ts);
+        let shard = calculate_shard(ts, SHARD_TIME);
         let mut index_path = dir.path().to_path_buf();
         index_path.push(format!("index_{:011}", shard));
         let index = OpenOptions::new()
@@ -1290,19 +1752,20 @@ mod tests {
         )
         .expect("Failed to acquire flock on index file");
 
-        StoreWriter::new(&dir, compress, format).expect_err("Did not conflict on index lock");
+        StoreWriter::new(&dir, compression_mode, format)
+            .expect_err("Did not conflict on index lock");
     }
 
     store_test!(
         writing_to_already_written_index_works,
         _writing_to_already_written_index_works
     );
-    fn _writing_to_already_written_index_works(compress: bool, format: Format) {
+    fn _writing_to_already_written_index_works(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1312,7 +1775,7 @@ mod tests {
         }
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(666);
             writer
@@ -1342,12 +1805,12 @@ mod tests {
         read_skips_over_corrupt_index_entry,
         _read_skips_over_corrupt_index_entry
     );
-    fn _read_skips_over_corrupt_index_entry(compress: bool, format: Format) {
+    fn _read_skips_over_corrupt_index_entry(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1357,7 +1820,7 @@ mod tests {
         }
         // Append garbage to the index entry
         {
-            let shard = calculate_shard(ts);
+            let shard = calculate_shard(ts, SHARD_TIME);
             let mut index_path = dir.path().to_path_buf();
             index_path.push(format!("index_{:011}", shard));
             let mut index = OpenOptions::new()
@@ -1371,7 +1834,7 @@ mod tests {
         }
         {
             let mut writer =
-                StoreWriter::new(&dir, compress, format).expect("Failed to create store");
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(666);
             writer
@@ -1392,14 +1855,14 @@ mod tests {
     }
 
     store_test!(writer_creates_directory, _writer_creates_directory);
-    fn _writer_creates_directory(compress: bool, format: Format) {
+    fn _writer_creates_directory(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let mut subdir = dir.path().to_path_buf();
         subdir.push("foo");
         let ts = SystemTime::now();
         {
-            let mut writer =
-                StoreWriter::new(&subdir, compress, format).expect("Failed to create store");
+            let mut writer = StoreWriter::new(&subdir, compression_mode, format)
+                .expect("Failed to create store");
             let mut frame = DataFrame::default();
             frame.sample.cgroup.memory_current = Some(333);
 
@@ -1414,4 +1877,129 @@ mod tests {
         assert_ts!(frame.0, ts);
         assert_eq!(frame.1.sample.cgroup.memory_current, Some(333));
     }
+
+    #[cfg(not(fbcode_build))]
+    store_test!(remote_store_roundtrip, _remote_store_roundtrip);
+    #[cfg(not(fbcode_build))]
+    fn _remote_store_roundtrip(compression_mode: CompressionMode, format: Format) {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
+        {
+            let mut writer =
+                StoreWriter::new(&dir, compression_mode, format).expect("Failed to create store");
+            for i in 0..3 {
+                let mut frame = DataFrame::default();
+                frame.sample.cgroup.memory_current = Some(i);
+                writer
+                    .put(ts + Duration::from_secs(i as u64), &frame, get_logger())
+                    .expect("Failed to store data");
+            }
+        }
+
+        let listener =
+            std::net::TcpListener::bind(("127.0.0.1", 0)).expect("Failed to bind listener");
+        let port = listener.local_addr().expect("No local addr").port();
+        let store_dir = dir.path().to_path_buf();
+        std::thread::spawn(move || remote_store::serve(listener, store_dir, get_logger()));
+
+        let mut store =
+            RemoteStore::new("127.0.0.1".to_owned(), Some(port)).expect("Failed to connect");
+        let frame = store
+            .get_sample_at_timestamp(
+                ts + Duration::from_millis(500),
+                Direction::Forward,
+                get_logger(),
+            )
+            .expect("Failed to read sample")
+            .expect("Did not find stored sample");
+        assert_ts!(frame.0, ts + Duration::from_secs(1));
+        assert_eq!(frame.1.sample.cgroup.memory_current, Some(1));
+
+        // The same connection keeps serving requests
+        let frame = store
+            .get_sample_at_timestamp(
+                ts + Duration::from_secs(10),
+                Direction::Reverse,
+                get_logger(),
+            )
+            .expect("Failed to read sample")
+            .expect("Did not find stored sample");
+        assert_ts!(frame.0, ts + Duration::from_secs(2));
+        assert_eq!(frame.1.sample.cgroup.memory_current, Some(2));
+
+        assert!(store
+            .get_sample_at_timestamp(
+                ts + Duration::from_secs(10),
+                Direction::Forward,
+                get_logger(),
+            )
+            .expect("Failed to read sample")
+            .is_none());
+    }
+
+    store_test!(custom_shard_time, _custom_shard_time);
+    fn _custom_shard_time(compression_mode: CompressionMode, format: Format) {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let shard_time = 60 * 60;
+        let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
+        {
+            let mut writer =
+                StoreWriter::new_with_shard_time(&dir, shard_time, compression_mode, format)
+                    .expect("Failed to create store");
+            for i in 0..3 {
+                let mut frame = DataFrame::default();
+                frame.sample.cgroup.memory_current = Some(i as i64);
+                writer
+                    .put(
+                        ts + Duration::from_secs(shard_time * i),
+                        &frame,
+                        get_logger(),
+                    )
+                    .expect("Failed to store data");
+            }
+        }
+        assert_eq!(
+            get_shard_time(&dir).expect("Failed to get shard time"),
+            shard_time
+        );
+        // One shard per hour
+        assert_eq!(
+            get_index_files(dir.path())
+                .expect("Failed to get index files")
+                .len(),
+            4
+        );
+
+        // Opening the store again keeps its shard time
+        assert!(
+            StoreWriter::new_with_shard_time(&dir, SHARD_TIME, compression_mode, format).is_err()
+        );
+        StoreWriter::new(&dir, compression_mode, format).expect("Failed to open store");
+        assert_eq!(
+            get_shard_time(&dir).expect("Failed to get shard time"),
+            shard_time
+        );
+
+        for i in 0..3 {
+            let frame = read_next_sample(
+                &dir,
+                ts + Duration::from_secs(shard_time * i),
+                Direction::Forward,
+                get_logger(),
+            )
+            .expect("Failed to read sample")
+            .expect("Did not find stored sample");
+            assert_ts!(frame.0, ts + Duration::from_secs(shard_time * i));
+            assert_eq!(frame.1.sample.cgroup.memory_current, Some(i as i64));
+        }
+        let frame = read_next_sample(
+            &dir,
+            ts + Duration::from_secs(shard_time * 3),
+            Direction::Reverse,
+            get_logger(),
+        )
+        .expect("Failed to read sample")
+        .expect("Did not find stored sample");
+        assert_ts!(frame.0, ts + Duration::from_secs(shard_time * 2));
+    }
 }
@@ -0,0 +1,460 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Copying frames between stores: merging several stores into one, cutting
+//! a time window out of a store and splitting a store into time windows.
+//!
+//! Frames are decoded and written again with a `StoreWriter`, so the
+//! resulting stores have consistent shards and index CRCs regardless of how
+//! the source stores were written.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use slog::Logger;
+
+use common::util::get_unix_timestamp;
+
+use crate::cursor::{Cursor, KeyedCursor, StoreCursor};
+use crate::{last_timestamp, CompressionMode, DataFrame, Direction, Format, StoreWriter};
+
+/// How stores created by this module are written
+#[derive(Clone, Copy, Debug)]
+pub struct WriteOptions {
+    /// Shard time of a newly created store, DEFAULT_SHARD_TIME if None
+    pub shard_time: Option<u64>,
+    pub compression_mode: CompressionMode,
+    pub format: Format,
+}
+
+/// Number of frames handled while copying
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CopyStats {
+    /// Frames written to the destination store
+    pub copied: usize,
+    /// Frames dropped because an earlier source store has a frame with the
+    /// same timestamp
+    pub duplicates: usize,
+}
+
+/// Fail if `frame` is not of `hostname`, which is set to the host of the
+/// first frame checked
+fn check_host(hostname: &mut Option<String>, ts: SystemTime, frame: &DataFrame) -> Result<()> {
+    let frame_hostname = &frame.sample.system.hostname;
+    match hostname.as_ref() {
+        Some(hostname) if hostname != frame_hostname => bail!(
+            "Frame at {} is from host {} but other frames are from host {}",
+            get_unix_timestamp(ts),
+            frame_hostname,
+            hostname
+        ),
+        Some(_) => {}
+        None => *hostname = Some(frame_hostname.clone()),
+    }
+    Ok(())
+}
+
+/// Frames of several stores in timestamp order
+struct MergedCursor {
+    cursors: Vec<StoreCursor>,
+    /// Next frame of each cursor
+    heads: Vec<Option<(SystemTime, DataFrame)>>,
+    /// Whether frames of different hosts are rejected
+    check_hosts: bool,
+    /// Host of the frames returned so far
+    hostname: Option<String>,
+}
+
+impl MergedCursor {
+    fn new<P: AsRef<Path>>(
+        srcs: &[P],
+        begin: u64,
+        allow_mixed_hosts: bool,
+        logger: &Logger,
+    ) -> Result<Self> {
+        let mut cursors = Vec::new();
+        let mut heads = Vec::new();
+        for src in srcs {
+            if !src.as_ref().is_dir() {
+                bail!("No store at {}", src.as_ref().display());
+            }
+            let mut cursor = StoreCursor::new(logger.clone(), src.as_ref().to_path_buf());
+            heads.push(
+                cursor
+                    .get_next(&begin, Direction::Forward)
+                    .with_context(|| format!("Failed to read {}", src.as_ref().display()))?,
+            );
+            cursors.push(cursor);
+        }
+        // A single store may legitimately see its host renamed
+        let check_hosts = !allow_mixed_hosts && srcs.len() > 1;
+        // Fail before anything is written if the stores start with
+        // different hosts
+        let mut hostname = None;
+        if check_hosts {
+            for (ts, frame) in heads.iter().flatten() {
+                check_host(&mut hostname, *ts, frame)?;
+            }
+        }
+        Ok(Self {
+            cursors,
+            heads,
+            check_hosts,
+            hostname,
+        })
+    }
+
+    /// Get the timestamp and store index of the next frame
+    fn peek(&self) -> Option<(SystemTime, usize)> {
+        self.heads
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, head)| head.as_ref().map(|(ts, _)| (*ts, idx)))
+            // Ties go to the first store
+            .min()
+    }
+
+    /// Get the next frame. Frames of other stores with the same timestamp
+    /// are skipped and counted as duplicates. Fails on a frame of another
+    /// host unless mixed hosts are allowed.
+    fn next(&mut self, stats: &mut CopyStats) -> Result<Option<(SystemTime, DataFrame)>> {
+        let next_idx = match self.peek() {
+            Some((_, next_idx)) => next_idx,
+            None => return Ok(None),
+        };
+        let next = self.advance(next_idx)?;
+        if let Some((ts, frame)) = next.as_ref() {
+            if self.check_hosts {
+                check_host(&mut self.hostname, *ts, frame)?;
+            }
+            for idx in 0..self.heads.len() {
+                while self.heads[idx].as_ref().map(|(head_ts, _)| head_ts) == Some(ts) {
+                    self.advance(idx)?;
+                    stats.duplicates += 1;
+                }
+            }
+        }
+        Ok(next)
+    }
+
+    /// Move cursor `idx` forward and return its previous head
+    fn advance(&mut self, idx: usize) -> Result<Option<(SystemTime, DataFrame)>> {
+        let head = self.cursors[idx].next(Direction::Forward)?;
+        Ok(std::mem::replace(&mut self.heads[idx], head))
+    }
+}
+
+/// Copy frames from `cursor` to the store at `dest` until `end` (inclusive).
+/// The store is only created once there is a frame to write.
+fn copy(
+    cursor: &mut MergedCursor,
+    dest: &Path,
+    end: Option<u64>,
+    options: &WriteOptions,
+    stats: &mut CopyStats,
+    logger: &Logger,
+) -> Result<()> {
+    let mut writer: Option<StoreWriter> = None;
+    // Peek first so frames after `end` are left for the next copy
+    while let Some((ts, _)) = cursor.peek() {
+        if end.map_or(false, |end| get_unix_timestamp(ts) > end) {
+            break;
+        }
+        let (ts, frame) = match cursor.next(stats)? {
+            Some(next) => next,
+            None => break,
+        };
+        let writer = match writer.as_mut() {
+            Some(writer) => writer,
+            None => writer.insert(StoreWriter::open(
+                dest,
+                ts,
+                options.shard_time,
+                options.compression_mode,
+                options.format,
+            )?),
+        };
+        writer
+            .put(ts, &frame, logger.clone())
+            .with_context(|| format!("Failed to write to {}", dest.display()))?;
+        stats.copied += 1;
+    }
+    Ok(())
+}
+
+/// Merge the frames between `begin` and `end` (inclusive) of the stores at
+/// `srcs` into the store at `dest`. If several stores have a frame with the
+/// same timestamp, only the one of the first such store is kept. If `dest`
+/// already has data, only frames after its last frame are copied. Fails if
+/// the stores are of different hosts, unless `allow_mixed_hosts` is set.
+pub fn merge<P: AsRef<Path>>(
+    srcs: &[P],
+    dest: &Path,
+    begin: SystemTime,
+    end: Option<SystemTime>,
+    allow_mixed_hosts: bool,
+    options: &WriteOptions,
+    logger: &Logger,
+) -> Result<CopyStats> {
+    let mut begin = get_unix_timestamp(begin);
+    if let Some(last) = last_timestamp(dest, logger)? {
+        begin = begin.max(last + 1);
+    }
+    let mut cursor = MergedCursor::new(srcs, begin, allow_mixed_hosts, logger)?;
+    let mut stats = CopyStats::default();
+    copy(
+        &mut cursor,
+        dest,
+        end.map(get_unix_timestamp),
+        options,
+        &mut stats,
+        logger,
+    )?;
+    Ok(stats)
+}
+
+/// Split the frames between `begin` and `end` (inclusive) of the store at
+/// `src` into one store per `every` seconds, in subdirectories of `dest`
+/// named after the start of their window. Windows are aligned to multiples
+/// of `every` since the epoch. Returns the created stores.
+pub fn split<P: AsRef<Path>>(
+    src: P,
+    dest: &Path,
+    every: Duration,
+    begin: SystemTime,
+    end: Option<SystemTime>,
+    options: &WriteOptions,
+    logger: &Logger,
+) -> Result<Vec<(PathBuf, CopyStats)>> {
+    let every = every.as_secs();
+    if every == 0 {
+        bail!("Split interval must be at least one second");
+    }
+    let end = end.map(get_unix_timestamp);
+    let mut cursor = MergedCursor::new(&[src], get_unix_timestamp(begin), false, logger)?;
+    let mut res = Vec::new();
+    // Each window picks up where the previous one stopped
+    while let Some(ts) = cursor.peek().map(|(ts, _)| get_unix_timestamp(ts)) {
+        if end.map_or(false, |end| ts > end) {
+            break;
+        }
+        let window = ts - ts % every;
+        let window_end = window + every - 1;
+        let window_dest = dest.join(format!("{:011}", window));
+        if last_timestamp(&window_dest, logger)?.is_some() {
+            bail!("Store at {} already has data", window_dest.display());
+        }
+        let mut stats = CopyStats::default();
+        copy(
+            &mut cursor,
+            &window_dest,
+            Some(end.map_or(window_end, |end| end.min(window_end))),
+            options,
+            &mut stats,
+            logger,
+        )?;
+        res.push((window_dest, stats));
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_shard_time, read_next_sample};
+    use slog::Drain;
+    use std::time::UNIX_EPOCH;
+    use tempdir::TempDir;
+
+    fn get_logger() -> Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    const OPTIONS: WriteOptions = WriteOptions {
+        shard_time: Some(3600),
+        compression_mode: CompressionMode::None,
+        format: Format::Cbor,
+    };
+
+    /// Write a frame of host `hostname` with memory_current set to `value`
+    /// at each of `timestamps`.
+    fn write_host_store(dir: &Path, hostname: &str, timestamps: &[u64], value: i64) {
+        let mut writer = StoreWriter::open(
+            dir,
+            UNIX_EPOCH + Duration::from_secs(timestamps[0]),
+            Some(3600),
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        for ts in timestamps {
+            let mut frame = DataFrame::default();
+            frame.sample.system.hostname = hostname.to_owned();
+            frame.sample.cgroup.memory_current = Some(value);
+            writer
+                .put(UNIX_EPOCH + Duration::from_secs(*ts), &frame, get_logger())
+                .expect("Failed to store data");
+        }
+    }
+
+    fn write_store(dir: &Path, timestamps: &[u64], value: i64) {
+        write_host_store(dir, "host.example.com", timestamps, value)
+    }
+
+    /// Read timestamps and memory_current of all frames of the store at `dir`
+    fn read_store(dir: &Path) -> Vec<(u64, i64)> {
+        let mut res = Vec::new();
+        let mut ts = UNIX_EPOCH;
+        while let Some((frame_ts, frame)) =
+            read_next_sample(dir, ts, Direction::Forward, get_logger())
+                .expect("Failed to read sample")
+        {
+            res.push((
+                get_unix_timestamp(frame_ts),
+                frame.sample.cgroup.memory_current.unwrap(),
+            ));
+            ts = frame_ts + Duration::from_secs(1);
+        }
+        res
+    }
+
+    #[test]
+    fn merge_interleaves_stores() {
+        let dir = TempDir::new("below_merge_test").expect("tempdir failed");
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        let dest = dir.path().join("dest");
+        write_store(&a, &[3600, 3610, 7200], 1);
+        write_store(&b, &[3605, 3610, 7205], 2);
+
+        let stats = merge(
+            &[&a, &b],
+            &dest,
+            UNIX_EPOCH,
+            None,
+            false,
+            &OPTIONS,
+            &get_logger(),
+        )
+        .expect("Failed to merge");
+        assert_eq!(
+            stats,
+            CopyStats {
+                copied: 5,
+                duplicates: 1
+            }
+        );
+        assert_eq!(
+            read_store(&dest),
+            vec![(3600, 1), (3605, 2), (3610, 1), (7200, 1), (7205, 2)]
+        );
+        assert_eq!(get_shard_time(&dest).unwrap(), 3600);
+
+        // Merging again does not copy anything twice
+        let stats = merge(
+            &[&a, &b],
+            &dest,
+            UNIX_EPOCH,
+            None,
+            false,
+            &OPTIONS,
+            &get_logger(),
+        )
+        .expect("Failed to merge");
+        assert_eq!(stats, CopyStats::default());
+    }
+
+    #[test]
+    fn merge_rejects_mixed_hosts() {
+        let dir = TempDir::new("below_merge_test").expect("tempdir failed");
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        let dest = dir.path().join("dest");
+        write_host_store(&a, "a.example.com", &[3600, 3610], 1);
+        write_host_store(&b, "b.example.com", &[3605], 2);
+
+        assert!(merge(
+            &[&a, &b],
+            &dest,
+            UNIX_EPOCH,
+            None,
+            false,
+            &OPTIONS,
+            &get_logger(),
+        )
+        .is_err());
+        // Nothing is written
+        assert!(!dest.exists());
+
+        let other = dir.path().join("other");
+        let stats = merge(
+            &[&a, &b],
+            &other,
+            UNIX_EPOCH,
+            None,
+            true,
+            &OPTIONS,
+            &get_logger(),
+        )
+        .expect("Failed to merge");
+        assert_eq!(stats.copied, 3);
+        assert_eq!(read_store(&other), vec![(3600, 1), (3605, 2), (3610, 1)]);
+    }
+
+    #[test]
+    fn slice_and_split() {
+        let dir = TempDir::new("below_merge_test").expect("tempdir failed");
+        let src = dir.path().join("src");
+        write_store(&src, &[3600, 3700, 7200, 7300, 10800], 1);
+
+        let slice = dir.path().join("slice");
+        let stats = merge(
+            &[&src],
+            &slice,
+            UNIX_EPOCH + Duration::from_secs(3700),
+            Some(UNIX_EPOCH + Duration::from_secs(7300)),
+            false,
+            &OPTIONS,
+            &get_logger(),
+        )
+        .expect("Failed to slice");
+        assert_eq!(stats.copied, 3);
+        assert_eq!(read_store(&slice), vec![(3700, 1), (7200, 1), (7300, 1)]);
+
+        let split_dir = dir.path().join("split");
+        let stores = split(
+            &src,
+            &split_dir,
+            Duration::from_secs(3600),
+            UNIX_EPOCH,
+            Some(UNIX_EPOCH + Duration::from_secs(7200)),
+            &OPTIONS,
+            &get_logger(),
+        )
+        .expect("Failed to split");
+        assert_eq!(
+            stores
+                .iter()
+                .map(|(path, stats)| (path.clone(), stats.copied))
+                .collect::<Vec<_>>(),
+            vec![
+                (split_dir.join("00000003600"), 2),
+                (split_dir.join("00000007200"), 1),
+            ]
+        );
+        assert_eq!(read_store(&split_dir.join("00000007200")), vec![(7200, 1)]);
+    }
+}
@@ -12,24 +12,189 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::SystemTime;
+//! A minimal TCP protocol for reading frames out of a remote store.
+//!
+//! Every message on the wire is a big endian u32 length followed by that many
+//! bytes of CBOR. The client sends a `Request` and the server answers with a
+//! `Response`. A single connection can serve any number of requests.
+//! Timestamps are sent as seconds and nanoseconds since the epoch, so frames
+//! of a capture burst are not lost.
 
-use anyhow::{bail, Result};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slog::{error, warn};
 
 use crate::{DataFrame, Direction};
 
-pub struct RemoteStore {}
+/// Port used by both the server and the client when none is specified
+pub const DEFAULT_PORT: u16 = 4242;
+
+/// Refuse messages larger than this to avoid allocating unbounded buffers
+/// on garbage input.
+const MAX_MESSAGE_SIZE: u32 = 1 << 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    timestamp: u64,
+    subsec_nanos: u32,
+    direction: Direction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Response {
+    /// Seconds and nanoseconds of the timestamp and the frame
+    Frame(Option<(u64, u32, DataFrame)>),
+    Error(String),
+}
+
+fn split_timestamp(timestamp: SystemTime) -> (u64, u32) {
+    let since_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+    (since_epoch.as_secs(), since_epoch.subsec_nanos())
+}
+
+fn join_timestamp(secs: u64, subsec_nanos: u32) -> SystemTime {
+    UNIX_EPOCH + Duration::new(secs, subsec_nanos)
+}
+
+fn write_message<T: Serialize>(stream: &mut TcpStream, message: &T) -> Result<()> {
+    let bytes = serde_cbor::to_vec(message)?;
+    if bytes.len() > MAX_MESSAGE_SIZE as usize {
+        bail!("Message of {} bytes is too large to send", bytes.len());
+    }
+    stream.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    stream.write_all(&bytes)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Returns None if the other end closed the connection before a new message
+fn read_message<T: DeserializeOwned>(stream: &mut TcpStream) -> Result<Option<T>> {
+    let mut len_buf = [0u8; 4];
+    match stream.read_exact(&mut len_buf) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len_buf);
+    if len > MAX_MESSAGE_SIZE {
+        bail!("Message of {} bytes exceeds size limit", len);
+    }
+    let mut buf = vec![0u8; len as usize];
+    stream
+        .read_exact(&mut buf)
+        .context("Connection closed in the middle of a message")?;
+    Ok(Some(serde_cbor::from_slice(&buf)?))
+}
+
+pub struct RemoteStore {
+    stream: TcpStream,
+}
 
 impl RemoteStore {
-    pub fn new(_host: String, _port: Option<u16>) -> Result<RemoteStore> {
-        bail!("Remote client not supported")
+    pub fn new(host: String, port: Option<u16>) -> Result<RemoteStore> {
+        let port = port.unwrap_or(DEFAULT_PORT);
+        let stream = TcpStream::connect((host.as_str(), port))
+            .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+        stream.set_nodelay(true)?;
+        Ok(RemoteStore { stream })
     }
 
     pub fn get_frame(
         &mut self,
-        _timestamp: u64,
-        _direction: Direction,
+        timestamp: SystemTime,
+        direction: Direction,
     ) -> Result<Option<(SystemTime, DataFrame)>> {
-        bail!("Remote client not supported")
+        let (timestamp, subsec_nanos) = split_timestamp(timestamp);
+        write_message(
+            &mut self.stream,
+            &Request {
+                timestamp,
+                subsec_nanos,
+                direction,
+            },
+        )
+        .context("Failed to send request to remote store")?;
+        match read_message(&mut self.stream).context("Failed to read remote store response")? {
+            Some(Response::Frame(frame)) => {
+                Ok(frame.map(|(ts, subsec_nanos, df)| (join_timestamp(ts, subsec_nanos), df)))
+            }
+            Some(Response::Error(e)) => Err(anyhow!("Remote store error: {}", e)),
+            None => bail!("Remote store closed the connection"),
+        }
     }
 }
+
+fn handle_connection(
+    mut stream: TcpStream,
+    store_dir: PathBuf,
+    logger: slog::Logger,
+) -> Result<()> {
+    stream.set_nodelay(true)?;
+    while let Some(request) = read_message::<Request>(&mut stream)? {
+        let response = match crate::read_next_sample(
+            &store_dir,
+            join_timestamp(request.timestamp, request.subsec_nanos),
+            request.direction,
+            logger.clone(),
+        ) {
+            Ok(Some((ts, df))) => {
+                let (ts, subsec_nanos) = split_timestamp(ts);
+                Response::Frame(Some((ts, subsec_nanos, df)))
+            }
+            Ok(None) => Response::Frame(None),
+            Err(e) => Response::Error(format!("{:#}", e)),
+        };
+        write_message(&mut stream, &response)?;
+    }
+    Ok(())
+}
+
+/// Accept connections on `listener` forever, serving frames from the store
+/// at `store_dir`. Each connection is handled on its own thread.
+pub fn serve(listener: TcpListener, store_dir: PathBuf, logger: slog::Logger) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let store_dir = store_dir.clone();
+                let logger = logger.clone();
+                thread::spawn(move || {
+                    let peer = stream
+                        .peer_addr()
+                        .map_or("unknown".to_owned(), |a| a.to_string());
+                    if let Err(e) = handle_connection(stream, store_dir, logger.clone()) {
+                        warn!(
+                            logger,
+                            "Remote store connection from {} failed: {:#}", peer, e
+                        );
+                    }
+                });
+            }
+            Err(e) => error!(logger, "Failed to accept remote store connection: {:#}", e),
+        }
+    }
+}
+
+/// Bind the remote store server to `address` and `port` (or `DEFAULT_PORT`)
+/// and serve `store_dir` from a background thread.
+pub fn start_server(
+    logger: slog::Logger,
+    store_dir: PathBuf,
+    address: IpAddr,
+    port: Option<u16>,
+) -> Result<()> {
+    let port = port.unwrap_or(DEFAULT_PORT);
+    let listener = TcpListener::bind((address, port)).with_context(|| {
+        format!(
+            "Failed to bind remote store server to {}",
+            SocketAddr::from((address, port))
+        )
+    })?;
+    thread::spawn(move || serve(listener, store_dir, logger));
+    Ok(())
+}
@@ -0,0 +1,202 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Downsampling of old data into a coarser store.
+//!
+//! A rollup store keeps a single frame per interval, e.g. one per minute.
+//! Counters in a sample are cumulative, so the last frame of an interval
+//! already accounts for everything that happened up to its end and
+//! `Model::new` computes correct (averaged) rates between two consecutive
+//! rolled up frames. Processes that exit within an interval would be lost
+//! that way, so the last sample of each process seen in the interval is
+//! carried over into the rolled up frame.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use slog::Logger;
+
+use common::util::get_unix_timestamp;
+
+use crate::cursor::{Cursor, KeyedCursor, StoreCursor};
+use crate::{last_timestamp, DataFrame, Direction, StoreWriter};
+
+/// Merge `next` into `merged`, an earlier frame of the same interval.
+fn merge_frames(merged: DataFrame, mut next: DataFrame) -> DataFrame {
+    let mut processes = merged.sample.processes;
+    // Entries of `next` overwrite those of the same pid
+    processes.append(&mut next.sample.processes);
+    next.sample.processes = processes;
+    next
+}
+
+/// Roll up frames of the store at `src_dir` into `dest`, keeping one frame
+/// per `interval`. Only intervals that end before `until` are rolled up and
+/// intervals already in `dest` are skipped, so this can be called
+/// repeatedly as time goes on. Returns the number of frames written.
+pub fn rollup<P: AsRef<Path>>(
+    src_dir: P,
+    dest: &mut StoreWriter,
+    until: SystemTime,
+    interval: Duration,
+    logger: &Logger,
+) -> Result<usize> {
+    let interval = interval.as_secs();
+    if interval == 0 {
+        bail!("Rollup interval must be at least one second");
+    }
+    let until = get_unix_timestamp(until);
+    let until = until - until % interval;
+    let begin = match last_timestamp(&dest.dir, logger)? {
+        Some(ts) => ts - ts % interval + interval,
+        None => 0,
+    };
+    if begin >= until {
+        return Ok(0);
+    }
+
+    let mut cursor = StoreCursor::new(logger.clone(), src_dir.as_ref().to_path_buf());
+    // Start of the interval being merged, timestamp of its last frame and
+    // the merged frame
+    let mut pending: Option<(u64, SystemTime, DataFrame)> = None;
+    let mut written = 0;
+    let mut item = cursor.get_next(&begin, Direction::Forward)?;
+    while let Some((ts, frame)) = item {
+        let timestamp = get_unix_timestamp(ts);
+        if timestamp >= until {
+            break;
+        }
+        let start = timestamp - timestamp % interval;
+        pending = match pending.take() {
+            Some((pending_start, _, merged)) if pending_start == start => {
+                Some((start, ts, merge_frames(merged, frame)))
+            }
+            Some((_, pending_ts, merged)) => {
+                dest.put(pending_ts, &merged, logger.clone())
+                    .context("Failed to write rolled up frame")?;
+                written += 1;
+                Some((start, ts, frame))
+            }
+            None => Some((start, ts, frame)),
+        };
+        item = cursor.next(Direction::Forward)?;
+    }
+    // The last interval is complete as it ends before `until`
+    if let Some((_, pending_ts, merged)) = pending {
+        dest.put(pending_ts, &merged, logger.clone())
+            .context("Failed to write rolled up frame")?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_next_sample, CompressionMode, Format};
+    use slog::Drain;
+    use std::time::UNIX_EPOCH;
+    use tempdir::TempDir;
+
+    fn get_logger() -> Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    #[test]
+    fn rollup_keeps_last_frame_per_interval() {
+        let src = TempDir::new("below_rollup_src").expect("tempdir failed");
+        let dest = TempDir::new("below_rollup_dest").expect("tempdir failed");
+        let begin = 1_000_000 * 60;
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                &src,
+                UNIX_EPOCH + Duration::from_secs(begin),
+                CompressionMode::None,
+                Format::Cbor,
+            )
+            .expect("Failed to create store");
+            // Three minutes of 5s samples. Pid 1 exits after the first 30s.
+            for i in 0..36 {
+                let mut frame = DataFrame::default();
+                frame.sample.cgroup.memory_current = Some(i);
+                frame.sample.processes.insert(2, Default::default());
+                if i < 6 {
+                    frame.sample.processes.insert(1, Default::default());
+                }
+                writer
+                    .put(
+                        UNIX_EPOCH + Duration::from_secs(begin + i as u64 * 5),
+                        &frame,
+                        get_logger(),
+                    )
+                    .expect("Failed to store data");
+            }
+        }
+
+        let mut writer = StoreWriter::new_with_timestamp(
+            &dest,
+            UNIX_EPOCH + Duration::from_secs(begin),
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        // The last minute is incomplete
+        let until = UNIX_EPOCH + Duration::from_secs(begin + 150);
+        let interval = Duration::from_secs(60);
+        assert_eq!(
+            rollup(&src, &mut writer, until, interval, &get_logger()).expect("Failed to roll up"),
+            2
+        );
+        assert_eq!(
+            rollup(&src, &mut writer, until, interval, &get_logger()).expect("Failed to roll up"),
+            0
+        );
+
+        let (ts, frame) = read_next_sample(
+            &dest,
+            UNIX_EPOCH + Duration::from_secs(begin),
+            Direction::Forward,
+            get_logger(),
+        )
+        .expect("Failed to read sample")
+        .expect("Did not find rolled up sample");
+        assert_eq!(ts, UNIX_EPOCH + Duration::from_secs(begin + 55));
+        assert_eq!(frame.sample.cgroup.memory_current, Some(11));
+        assert_eq!(
+            frame.sample.processes.keys().collect::<Vec<_>>(),
+            vec![&1, &2]
+        );
+
+        let (ts, frame) = read_next_sample(
+            &dest,
+            ts + Duration::from_secs(1),
+            Direction::Forward,
+            get_logger(),
+        )
+        .expect("Failed to read sample")
+        .expect("Did not find rolled up sample");
+        assert_eq!(ts, UNIX_EPOCH + Duration::from_secs(begin + 115));
+        assert_eq!(frame.sample.cgroup.memory_current, Some(23));
+        assert_eq!(frame.sample.processes.keys().collect::<Vec<_>>(), vec![&2]);
+
+        // Later calls pick up where the last one stopped
+        let until = UNIX_EPOCH + Duration::from_secs(begin + 180);
+        assert_eq!(
+            rollup(&src, &mut writer, until, interval, &get_logger()).expect("Failed to roll up"),
+            1
+        );
+    }
+}
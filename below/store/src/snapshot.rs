@@ -0,0 +1,623 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Single file snapshots of a time range of a store, e.g. to attach the data
+//! around an incident to a bug report.
+//!
+//! A snapshot starts with SNAPSHOT_MAGIC, followed by the length of the CBOR
+//! encoded SnapshotHeader as u32 and the header itself. The rest of the file
+//! are frames in increasing timestamp order. Each frame is its timestamp as
+//! u64 seconds and u32 nanoseconds, the length and crc32 of its data as u32
+//! and the data, i.e. the data frame serialized in the format of the header
+//! and compressed as the header says. All integers are little endian.
+//!
+//! Version 1 snapshots only had whole seconds, i.e. no nanoseconds in the
+//! frame header.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use slog::{warn, Logger};
+use zstd::stream::decode_all;
+
+use common::util::get_unix_timestamp;
+
+use crate::cursor::{Cursor, KeyedCursor, StoreCursor};
+use crate::{
+    deserialize_frame, serialize_frame, CompressionMode, Crc32, DataFrame, Direction, Format, Store,
+};
+
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"BELOWSNP";
+/// Version of the snapshot format written by this module
+pub const SNAPSHOT_VERSION: u32 = 2;
+/// Size of timestamp, length and crc preceding the data of each frame
+const FRAME_HEADER_SIZE: usize = 20;
+/// Same as FRAME_HEADER_SIZE for version 1 snapshots without nanoseconds
+const V1_FRAME_HEADER_SIZE: usize = 16;
+
+/// Describes where the frames of a snapshot come from and how they are
+/// encoded
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotHeader {
+    pub version: u32,
+    pub hostname: String,
+    pub kernel_version: Option<String>,
+    pub below_version: String,
+    pub format: Format,
+    /// Either None or Zstd, frames are compressed on their own
+    pub compression: CompressionMode,
+}
+
+/// Writes a snapshot file frame by frame
+pub struct SnapshotWriter {
+    file: BufWriter<File>,
+    format: Format,
+    compression: CompressionMode,
+    last_timestamp: Option<Duration>,
+}
+
+impl SnapshotWriter {
+    /// Create a new snapshot file at `path` and write `header` to it. Fails
+    /// if the file already exists.
+    pub fn create(path: &Path, header: &SnapshotHeader) -> Result<Self> {
+        match header.compression {
+            CompressionMode::None | CompressionMode::Zstd => {}
+            mode => bail!("Compression mode {:?} is not supported for snapshots", mode),
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .with_context(|| format!("Failed to create snapshot {}", path.display()))?;
+        let mut file = BufWriter::new(file);
+        let header_bytes =
+            serde_cbor::to_vec(header).context("Failed to serialize snapshot header")?;
+        file.write_all(SNAPSHOT_MAGIC)?;
+        file.write_all(&(header_bytes.len() as u32).to_le_bytes())?;
+        file.write_all(&header_bytes)?;
+        Ok(Self {
+            file,
+            format: header.format,
+            compression: header.compression,
+            last_timestamp: None,
+        })
+    }
+
+    /// Append a frame. Frames must be put in increasing timestamp order.
+    pub fn put(&mut self, timestamp: SystemTime, data: &DataFrame) -> Result<()> {
+        let timestamp = timestamp
+            .duration_since(UNIX_EPOCH)
+            .context("Snapshot frames must not be older than the epoch")?;
+        if self.last_timestamp.map_or(false, |last| timestamp <= last) {
+            bail!("Snapshot frames must be put in increasing timestamp order");
+        }
+        let frame_bytes =
+            serialize_frame(data, self.format).context("Failed to serialize data frame")?;
+        let frame_bytes = match self.compression {
+            CompressionMode::Zstd => zstd::block::compress(&frame_bytes, 0)
+                .context("Failed to compress data serialized data frame")?,
+            _ => frame_bytes.to_vec(),
+        };
+        self.file.write_all(&timestamp.as_secs().to_le_bytes())?;
+        self.file
+            .write_all(&timestamp.subsec_nanos().to_le_bytes())?;
+        self.file
+            .write_all(&(frame_bytes.len() as u32).to_le_bytes())?;
+        self.file.write_all(&frame_bytes.crc32().to_le_bytes())?;
+        self.file.write_all(&frame_bytes)?;
+        self.last_timestamp = Some(timestamp);
+        Ok(())
+    }
+
+    /// Flush all frames to the file
+    pub fn finish(mut self) -> Result<()> {
+        self.file.flush().context("Failed to write snapshot")
+    }
+}
+
+/// Location of a frame within a snapshot
+struct SnapshotFrame {
+    /// Since the epoch
+    timestamp: Duration,
+    offset: usize,
+    len: usize,
+    crc: u32,
+}
+
+/// A snapshot file opened for reading. The whole file is kept in memory.
+pub struct SnapshotStore {
+    header: SnapshotHeader,
+    data: Vec<u8>,
+    /// Frames in increasing timestamp order
+    frames: Vec<SnapshotFrame>,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+impl SnapshotStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if !data.starts_with(SNAPSHOT_MAGIC) {
+            bail!("{} is not a below snapshot", path.display());
+        }
+        let mut offset = SNAPSHOT_MAGIC.len();
+        let header_len = read_u32(&data, offset).context("Truncated snapshot header")? as usize;
+        offset += 4;
+        let header: SnapshotHeader = serde_cbor::from_slice(
+            data.get(offset..offset + header_len)
+                .context("Truncated snapshot header")?,
+        )
+        .context("Failed to parse snapshot header")?;
+        if header.version > SNAPSHOT_VERSION {
+            bail!(
+                "Snapshot version {} is newer than supported version {}",
+                header.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        offset += header_len;
+        let frame_header_size = if header.version < 2 {
+            V1_FRAME_HEADER_SIZE
+        } else {
+            FRAME_HEADER_SIZE
+        };
+
+        let mut frames = Vec::new();
+        while offset < data.len() {
+            // A truncated last frame is ignored, e.g. of an interrupted copy
+            let secs = read_u64(&data, offset);
+            let (nanos, len, crc) = if header.version < 2 {
+                (
+                    Some(0),
+                    read_u32(&data, offset + 8),
+                    read_u32(&data, offset + 12),
+                )
+            } else {
+                (
+                    read_u32(&data, offset + 8),
+                    read_u32(&data, offset + 12),
+                    read_u32(&data, offset + 16),
+                )
+            };
+            let (timestamp, len, crc) = match (secs, nanos, len, crc) {
+                (Some(secs), Some(nanos), Some(len), Some(crc)) if nanos < 1_000_000_000 => {
+                    (Duration::new(secs, nanos), len as usize, crc)
+                }
+                _ => break,
+            };
+            offset += frame_header_size;
+            if offset + len > data.len() {
+                break;
+            }
+            if frames
+                .last()
+                .map_or(false, |last: &SnapshotFrame| timestamp <= last.timestamp)
+            {
+                bail!("Snapshot frames are out of order at offset {}", offset);
+            }
+            frames.push(SnapshotFrame {
+                timestamp,
+                offset,
+                len,
+                crc,
+            });
+            offset += len;
+        }
+
+        Ok(Self {
+            header,
+            data,
+            frames,
+        })
+    }
+
+    pub fn header(&self) -> &SnapshotHeader {
+        &self.header
+    }
+
+    /// Timestamps of the first and last frame. None if the snapshot is
+    /// empty.
+    pub fn time_range(&self) -> Option<(SystemTime, SystemTime)> {
+        let to_time = |frame: &SnapshotFrame| UNIX_EPOCH + frame.timestamp;
+        Some((to_time(self.frames.first()?), to_time(self.frames.last()?)))
+    }
+
+    /// Decode frame `idx`. Returns None if it is corrupt.
+    fn get_frame(&self, idx: usize, logger: &Logger) -> Option<(SystemTime, DataFrame)> {
+        let frame = &self.frames[idx];
+        let data = &self.data[frame.offset..frame.offset + frame.len];
+        if data.crc32() != frame.crc {
+            warn!(
+                logger,
+                "Snapshot frame at {:?} has a bad crc, skipping", frame.timestamp
+            );
+            return None;
+        }
+        let decompressed;
+        let data = match self.header.compression {
+            CompressionMode::None => data,
+            _ => match decode_all(data) {
+                Ok(data) => {
+                    decompressed = data;
+                    decompressed.as_slice()
+                }
+                Err(e) => {
+                    warn!(logger, "Failed to decompress snapshot frame: {}", e);
+                    return None;
+                }
+            },
+        };
+        match deserialize_frame(data, self.header.format) {
+            Ok(df) => Some((UNIX_EPOCH + frame.timestamp, df)),
+            Err(e) => {
+                warn!(logger, "Failed to deserialize data frame: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Store for SnapshotStore {
+    type SampleType = DataFrame;
+
+    fn get_sample_at_timestamp(
+        &mut self,
+        timestamp: SystemTime,
+        direction: Direction,
+        logger: slog::Logger,
+    ) -> Result<Option<(SystemTime, Self::SampleType)>> {
+        let timestamp = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+        // Corrupt frames are skipped in search direction
+        match direction {
+            Direction::Forward => {
+                let first = self.frames.partition_point(|f| f.timestamp < timestamp);
+                Ok((first..self.frames.len()).find_map(|idx| self.get_frame(idx, &logger)))
+            }
+            Direction::Reverse => {
+                let end = self.frames.partition_point(|f| f.timestamp <= timestamp);
+                Ok((0..end).rev().find_map(|idx| self.get_frame(idx, &logger)))
+            }
+        }
+    }
+}
+
+/// Write the frames between `begin` and `end` (inclusive) of the store at
+/// `src` to a new snapshot file at `dest`. Host information in the header is
+/// taken from the first frame. Returns the number of frames written.
+pub fn snapshot<P: AsRef<Path>>(
+    src: P,
+    dest: &Path,
+    begin: SystemTime,
+    end: SystemTime,
+    compression: CompressionMode,
+    format: Format,
+    logger: &Logger,
+) -> Result<usize> {
+    let src = src.as_ref();
+    if !src.is_dir() {
+        bail!("No store at {}", src.display());
+    }
+    let end = get_unix_timestamp(end);
+    let mut cursor = StoreCursor::new(logger.clone(), src.to_path_buf());
+    let mut writer: Option<SnapshotWriter> = None;
+    let mut written = 0;
+    let mut item = cursor
+        .get_next(&get_unix_timestamp(begin), Direction::Forward)
+        .with_context(|| format!("Failed to read {}", src.display()))?;
+    while let Some((ts, frame)) = item {
+        if get_unix_timestamp(ts) > end {
+            break;
+        }
+        let writer = match writer.as_mut() {
+            Some(writer) => writer,
+            None => writer.insert(SnapshotWriter::create(
+                dest,
+                &SnapshotHeader {
+                    version: SNAPSHOT_VERSION,
+                    hostname: frame.sample.system.hostname.clone(),
+                    kernel_version: frame.sample.system.kernel_version.clone(),
+                    below_version: env!("CARGO_PKG_VERSION").to_owned(),
+                    format,
+                    compression,
+                },
+            )?),
+        };
+        writer.put(ts, &frame)?;
+        written += 1;
+        item = cursor.next(Direction::Forward)?;
+    }
+    match writer {
+        Some(writer) => writer.finish()?,
+        None => bail!("No data in the requested time range"),
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StoreWriter;
+    use slog::Drain;
+    use tempdir::TempDir;
+
+    fn get_logger() -> Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    fn write_store(dir: &Path, timestamps: &[u64]) {
+        let mut writer = StoreWriter::new_with_timestamp(
+            dir,
+            UNIX_EPOCH + Duration::from_secs(timestamps[0]),
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        for ts in timestamps {
+            let mut frame = DataFrame::default();
+            frame.sample.system.hostname = "host.example.com".to_owned();
+            frame.sample.cgroup.memory_current = Some(*ts as i64);
+            writer
+                .put(UNIX_EPOCH + Duration::from_secs(*ts), &frame, get_logger())
+                .expect("Failed to store data");
+        }
+    }
+
+    fn get_memory_current(
+        store: &mut SnapshotStore,
+        ts: u64,
+        direction: Direction,
+    ) -> Option<(u64, i64)> {
+        store
+            .get_sample_at_timestamp(
+                UNIX_EPOCH + Duration::from_secs(ts),
+                direction,
+                get_logger(),
+            )
+            .expect("Failed to read snapshot")
+            .map(|(ts, frame)| {
+                (
+                    get_unix_timestamp(ts),
+                    frame.sample.cgroup.memory_current.unwrap(),
+                )
+            })
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        for compression in [CompressionMode::None, CompressionMode::Zstd] {
+            let dir = TempDir::new("below_snapshot_test").expect("tempdir failed");
+            let src = dir.path().join("store");
+            write_store(&src, &[100, 105, 110, 115, 120]);
+            let path = dir.path().join("snapshot.below");
+            let written = snapshot(
+                &src,
+                &path,
+                UNIX_EPOCH + Duration::from_secs(102),
+                UNIX_EPOCH + Duration::from_secs(115),
+                compression,
+                Format::Cbor,
+                &get_logger(),
+            )
+            .expect("Failed to write snapshot");
+            assert_eq!(written, 3);
+
+            let mut store = SnapshotStore::open(&path).expect("Failed to open snapshot");
+            assert_eq!(store.header().hostname, "host.example.com");
+            assert_eq!(store.header().compression, compression);
+            assert_eq!(
+                store.time_range(),
+                Some((
+                    UNIX_EPOCH + Duration::from_secs(105),
+                    UNIX_EPOCH + Duration::from_secs(115)
+                ))
+            );
+            assert_eq!(
+                get_memory_current(&mut store, 0, Direction::Forward),
+                Some((105, 105))
+            );
+            assert_eq!(
+                get_memory_current(&mut store, 112, Direction::Forward),
+                Some((115, 115))
+            );
+            assert_eq!(
+                get_memory_current(&mut store, 116, Direction::Forward),
+                None
+            );
+            assert_eq!(
+                get_memory_current(&mut store, 112, Direction::Reverse),
+                Some((110, 110))
+            );
+            assert_eq!(
+                get_memory_current(&mut store, 104, Direction::Reverse),
+                None
+            );
+
+            // Snapshots are never overwritten
+            assert!(snapshot(
+                &src,
+                &path,
+                UNIX_EPOCH,
+                UNIX_EPOCH + Duration::from_secs(200),
+                compression,
+                Format::Cbor,
+                &get_logger(),
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn snapshot_skips_corrupt_frame() {
+        let dir = TempDir::new("below_snapshot_test").expect("tempdir failed");
+        let src = dir.path().join("store");
+        write_store(&src, &[100, 105, 110]);
+        let path = dir.path().join("snapshot.below");
+        snapshot(
+            &src,
+            &path,
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::from_secs(200),
+            CompressionMode::None,
+            Format::Cbor,
+            &get_logger(),
+        )
+        .expect("Failed to write snapshot");
+
+        let mut store = SnapshotStore::open(&path).expect("Failed to open snapshot");
+        let offset = store.frames[1].offset;
+        let mut data = fs::read(&path).expect("Failed to read snapshot");
+        data[offset] ^= 0xFF;
+        // Also truncate the last frame
+        data.truncate(data.len() - 1);
+        fs::write(&path, &data).expect("Failed to write snapshot");
+
+        store = SnapshotStore::open(&path).expect("Failed to open snapshot");
+        assert_eq!(store.frames.len(), 2);
+        assert_eq!(
+            get_memory_current(&mut store, 101, Direction::Forward),
+            None
+        );
+        assert_eq!(
+            get_memory_current(&mut store, 109, Direction::Reverse),
+            Some((100, 100))
+        );
+
+        fs::write(&path, b"not a snapshot").expect("Failed to write file");
+        assert!(SnapshotStore::open(&path).is_err());
+    }
+
+    #[test]
+    fn snapshot_keeps_burst_frames() {
+        let dir = TempDir::new("below_snapshot_test").expect("tempdir failed");
+        let src = dir.path().join("store");
+        let timestamps = [
+            Duration::from_secs(100),
+            Duration::from_millis(100_200),
+            Duration::from_millis(100_400),
+            Duration::from_secs(101),
+        ];
+        let mut writer = StoreWriter::new_with_timestamp(
+            &src,
+            UNIX_EPOCH + timestamps[0],
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        for (i, ts) in timestamps.iter().enumerate() {
+            let mut frame = DataFrame::default();
+            frame.sample.cgroup.memory_current = Some(i as i64);
+            writer
+                .put(UNIX_EPOCH + *ts, &frame, get_logger())
+                .expect("Failed to store data");
+        }
+        let path = dir.path().join("snapshot.below");
+        let written = snapshot(
+            &src,
+            &path,
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::from_secs(200),
+            CompressionMode::None,
+            Format::Cbor,
+            &get_logger(),
+        )
+        .expect("Failed to write snapshot");
+        assert_eq!(written, 4);
+
+        let mut store = SnapshotStore::open(&path).expect("Failed to open snapshot");
+        assert_eq!(store.header().version, SNAPSHOT_VERSION);
+        for (i, ts) in timestamps.iter().enumerate() {
+            let (found, frame) = store
+                .get_sample_at_timestamp(UNIX_EPOCH + *ts, Direction::Forward, get_logger())
+                .expect("Failed to read snapshot")
+                .expect("Missing frame");
+            assert_eq!(found, UNIX_EPOCH + *ts);
+            assert_eq!(frame.sample.cgroup.memory_current, Some(i as i64));
+        }
+        // Searches from within a burst find the adjacent frames
+        let between = UNIX_EPOCH + Duration::from_millis(100_300);
+        assert_eq!(
+            store
+                .get_sample_at_timestamp(between, Direction::Forward, get_logger())
+                .expect("Failed to read snapshot")
+                .map(|(ts, _)| ts),
+            Some(UNIX_EPOCH + timestamps[2])
+        );
+        assert_eq!(
+            store
+                .get_sample_at_timestamp(between, Direction::Reverse, get_logger())
+                .expect("Failed to read snapshot")
+                .map(|(ts, _)| ts),
+            Some(UNIX_EPOCH + timestamps[1])
+        );
+    }
+
+    #[test]
+    fn snapshot_reads_v1() {
+        let dir = TempDir::new("below_snapshot_test").expect("tempdir failed");
+        let path = dir.path().join("snapshot.below");
+        let header = SnapshotHeader {
+            version: 1,
+            hostname: "host.example.com".to_owned(),
+            kernel_version: None,
+            below_version: "0.0.0".to_owned(),
+            format: Format::Cbor,
+            compression: CompressionMode::None,
+        };
+        let header_bytes = serde_cbor::to_vec(&header).expect("Failed to serialize header");
+        let mut data = SNAPSHOT_MAGIC.to_vec();
+        data.extend_from_slice(&(header_bytes.len() as u32).to_le_bytes());
+        data.extend_from_slice(&header_bytes);
+        for ts in [100u64, 105] {
+            let mut frame = DataFrame::default();
+            frame.sample.cgroup.memory_current = Some(ts as i64);
+            let frame_bytes = serialize_frame(&frame, Format::Cbor).expect("Failed to serialize");
+            data.extend_from_slice(&ts.to_le_bytes());
+            data.extend_from_slice(&(frame_bytes.len() as u32).to_le_bytes());
+            data.extend_from_slice(&frame_bytes.crc32().to_le_bytes());
+            data.extend_from_slice(&frame_bytes);
+        }
+        fs::write(&path, &data).expect("Failed to write snapshot");
+
+        let mut store = SnapshotStore::open(&path).expect("Failed to open snapshot");
+        assert_eq!(store.header().version, 1);
+        assert_eq!(
+            store.time_range(),
+            Some((
+                UNIX_EPOCH + Duration::from_secs(100),
+                UNIX_EPOCH + Duration::from_secs(105)
+            ))
+        );
+        assert_eq!(
+            get_memory_current(&mut store, 101, Direction::Forward),
+            Some((105, 105))
+        );
+        assert_eq!(
+            get_memory_current(&mut store, 104, Direction::Reverse),
+            Some((100, 100))
+        );
+    }
+}
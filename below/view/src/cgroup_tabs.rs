@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 use crate::cgroup_view::CgroupState;
 use crate::render::ViewItem;
 use crate::stats_view::StateCommon;
 
-use model::{sort_queriables, CgroupModel, SingleCgroupModel};
+use base_render::RenderConfigBuilder;
+use model::{sort_queriables, BTreeMapFieldId, CgroupModel, SingleCgroupModel};
 
 use cursive::utils::markup::StyledString;
 
@@ -38,6 +39,69 @@ impl CgroupTab {
         Self { view_items }
     }
 
+    /// The cpuset of each cgroup and its anon and file memory on each NUMA
+    /// node of `model`. Columns depend on the nodes, so unlike the other
+    /// tabs this one is built from the model.
+    pub fn numa(model: &CgroupModel) -> Self {
+        use model::CgroupMemoryNumaModelFieldId::{Anon, File};
+        use model::SingleCgroupModelFieldId::{CpusetCpus, CpusetMems, MemNuma};
+
+        fn collect_nodes(model: &CgroupModel, nodes: &mut BTreeSet<u32>) {
+            // The root cgroup has no memory.numa_stat
+            if let Some(numa) = model.data.memory_numa.as_ref() {
+                nodes.extend(numa.keys().cloned());
+            }
+            for child in model.children.iter() {
+                collect_nodes(child, nodes);
+            }
+        }
+
+        let mut nodes = BTreeSet::new();
+        collect_nodes(model, &mut nodes);
+        let mut view_items = vec![
+            ViewItem::from_default(CpusetCpus),
+            ViewItem::from_default(CpusetMems),
+        ];
+        for node in nodes {
+            for (field_id, name) in [(Anon, "Anon"), (File, "File")] {
+                view_items.push(
+                    ViewItem::from_default(MemNuma(BTreeMapFieldId {
+                        key: Some(node),
+                        subquery_id: field_id,
+                    }))
+                    .update(RenderConfigBuilder::new().title(format!("N{} {}", node, name))),
+                );
+            }
+        }
+        Self::new(view_items)
+    }
+
+    /// Tab with one column per value read from the extra_cgroup_files of
+    /// below.conf, over every cgroup of the model.
+    pub fn custom(model: &CgroupModel) -> Self {
+        use model::CgroupExtraModelFieldId;
+        use model::SingleCgroupModelFieldId::Extra;
+
+        fn collect_keys(model: &CgroupModel, keys: &mut BTreeSet<String>) {
+            if let Some(extra) = model.data.extra.as_ref() {
+                keys.extend(extra.0.keys().cloned());
+            }
+            for child in model.children.iter() {
+                collect_keys(child, keys);
+            }
+        }
+
+        let mut keys = BTreeSet::new();
+        collect_keys(model, &mut keys);
+        Self::new(
+            keys.into_iter()
+                .map(|key| {
+                    ViewItem::from_default(Extra(CgroupExtraModelFieldId { key: Some(key) }))
+                })
+                .collect(),
+        )
+    }
+
     fn get_line(
         &self,
         model: &SingleCgroupModel,
@@ -196,24 +260,37 @@ pub mod default_tabs {
 
     use base_render::RenderConfigBuilder as Rc;
     use common::util::get_prefix;
+    use model::CgroupCpuModelFieldId::Weight as CpuWeight;
     use model::CgroupCpuModelFieldId::{
-        NrPeriodsPerSec, NrThrottledPerSec, SystemPct, ThrottledPct, UsagePct, UserPct,
+        MaxUsec, NrPeriodsPerSec, NrThrottledPerSec, SystemPct, ThrottledPct, UsagePct,
+        UsagePctOfMax, UserPct,
     };
+    use model::CgroupIoModelFieldId::Weight as IoWeight;
     use model::CgroupIoModelFieldId::{
-        DbytesPerSec, DiosPerSec, RbytesPerSec, RiosPerSec, RwbytesPerSec, WbytesPerSec, WiosPerSec,
+        DbytesPerSec, DiosPerSec, RbytesPctOfMax, RbytesPerSec, RiosPerSec, RwbytesPerSec,
+        WbytesPctOfMax, WbytesPerSec, WiosPerSec,
     };
     use model::CgroupMemoryModelFieldId::{
         ActiveAnon, ActiveFile, Anon, AnonThp, EventsHigh, EventsLow, EventsMax, EventsOom,
-        EventsOomKill, File, FileDirty, FileMapped, FileWriteback, InactiveAnon, InactiveFile,
-        KernelStack, Pgactivate, Pgdeactivate, Pgfault, Pglazyfree, Pglazyfreed, Pgmajfault,
-        Pgrefill, Pgscan, Pgsteal, Shmem, Slab, SlabReclaimable, SlabUnreclaimable, Sock, Swap,
-        ThpCollapseAlloc, ThpFaultAlloc, Total, Unevictable, WorkingsetActivate,
-        WorkingsetNodereclaim, WorkingsetRefault,
+        EventsOomKill, File, FileDirty, FileMapped, FileThp, FileWriteback, InactiveAnon,
+        InactiveFile, KernelStack, MemoryHigh, MemoryLow, MemoryMax, MemoryMin, MemorySwapMax,
+        Percpu, Pgactivate, Pgdeactivate, Pgfault, Pglazyfree, Pglazyfreed, Pgmajfault, Pgrefill,
+        Pgscan, PgscanDirect, PgscanKswapd, Pgsteal, SecPagetables, Shmem, ShmemThp, Slab,
+        SlabReclaimable, SlabUnreclaimable, Sock, Swap, SwapPctOfMax, ThpCollapseAlloc,
+        ThpFaultAlloc, Total, TotalPctOfMax, Unevictable, WorkingsetActivate,
+        WorkingsetNodereclaim, WorkingsetRefault, WorkingsetRefaultAnon, WorkingsetRefaultFile,
+        Zswap, Zswapped,
+    };
+    use model::CgroupPidsModelFieldId::CurrentPctOfMax;
+    use model::CgroupPidsModelFieldId::{
+        Current as PidsCurrent, EventsMax as PidsEventsMax, Max as PidsMax,
     };
     use model::CgroupPressureModelFieldId::{
         CpuSomePct, IoFullPct, IoSomePct, MemoryFullPct, MemorySomePct,
     };
-    use model::SingleCgroupModelFieldId::{Cpu, Io, Mem, Name, Pressure};
+    use model::SingleCgroupModelFieldId::{
+        Cpu, Frozen, Io, Mem, Name, NrDescendants, NrDyingDescendants, Pids, Populated, Pressure,
+    };
 
     use once_cell::sync::Lazy;
 
@@ -240,18 +317,23 @@ pub mod default_tabs {
     pub static CGROUP_CPU_TAB: Lazy<CgroupTab> = Lazy::new(|| {
         CgroupTab::new(vec![
             ViewItem::from_default(Cpu(UsagePct)),
+            ViewItem::from_default(Cpu(UsagePctOfMax)),
             ViewItem::from_default(Cpu(UserPct)),
             ViewItem::from_default(Cpu(SystemPct)),
             ViewItem::from_default(Cpu(NrPeriodsPerSec)),
             ViewItem::from_default(Cpu(NrThrottledPerSec)),
             ViewItem::from_default(Cpu(ThrottledPct)),
+            ViewItem::from_default(Cpu(MaxUsec)),
+            ViewItem::from_default(Cpu(CpuWeight)),
         ])
     });
 
     pub static CGROUP_MEM_TAB: Lazy<CgroupTab> = Lazy::new(|| {
         CgroupTab::new(vec![
             ViewItem::from_default(Mem(Total)),
+            ViewItem::from_default(Mem(TotalPctOfMax)),
             ViewItem::from_default(Mem(Swap)),
+            ViewItem::from_default(Mem(SwapPctOfMax)),
             ViewItem::from_default(Mem(Anon)),
             ViewItem::from_default(Mem(File)),
             ViewItem::from_default(Mem(KernelStack)),
@@ -269,13 +351,23 @@ pub mod default_tabs {
             ViewItem::from_default(Mem(Unevictable)),
             ViewItem::from_default(Mem(SlabReclaimable)),
             ViewItem::from_default(Mem(SlabUnreclaimable)),
+            ViewItem::from_default(Mem(Percpu)),
+            ViewItem::from_default(Mem(SecPagetables)),
+            ViewItem::from_default(Mem(Zswap)),
+            ViewItem::from_default(Mem(Zswapped)),
+            ViewItem::from_default(Mem(FileThp)),
+            ViewItem::from_default(Mem(ShmemThp)),
             ViewItem::from_default(Mem(Pgfault)),
             ViewItem::from_default(Mem(Pgmajfault)),
             ViewItem::from_default(Mem(WorkingsetRefault)),
+            ViewItem::from_default(Mem(WorkingsetRefaultAnon)),
+            ViewItem::from_default(Mem(WorkingsetRefaultFile)),
             ViewItem::from_default(Mem(WorkingsetActivate)),
             ViewItem::from_default(Mem(WorkingsetNodereclaim)),
             ViewItem::from_default(Mem(Pgrefill)),
             ViewItem::from_default(Mem(Pgscan)),
+            ViewItem::from_default(Mem(PgscanDirect)),
+            ViewItem::from_default(Mem(PgscanKswapd)),
             ViewItem::from_default(Mem(Pgsteal)),
             ViewItem::from_default(Mem(Pgactivate)),
             ViewItem::from_default(Mem(Pgdeactivate)),
@@ -288,18 +380,26 @@ pub mod default_tabs {
             ViewItem::from_default(Mem(EventsMax)),
             ViewItem::from_default(Mem(EventsOom)),
             ViewItem::from_default(Mem(EventsOomKill)),
+            ViewItem::from_default(Mem(MemoryMax)),
+            ViewItem::from_default(Mem(MemoryHigh)),
+            ViewItem::from_default(Mem(MemoryLow)),
+            ViewItem::from_default(Mem(MemoryMin)),
+            ViewItem::from_default(Mem(MemorySwapMax)),
         ])
     });
 
     pub static CGROUP_IO_TAB: Lazy<CgroupTab> = Lazy::new(|| {
         CgroupTab::new(vec![
             ViewItem::from_default(Io(RbytesPerSec)),
+            ViewItem::from_default(Io(RbytesPctOfMax)),
             ViewItem::from_default(Io(WbytesPerSec)),
+            ViewItem::from_default(Io(WbytesPctOfMax)),
             ViewItem::from_default(Io(DbytesPerSec)),
             ViewItem::from_default(Io(RiosPerSec)),
             ViewItem::from_default(Io(WiosPerSec)),
             ViewItem::from_default(Io(DiosPerSec)),
             ViewItem::from_default(Io(RwbytesPerSec)),
+            ViewItem::from_default(Io(IoWeight)),
         ])
     });
 
@@ -312,4 +412,17 @@ pub mod default_tabs {
             ViewItem::from_default(Pressure(IoFullPct)),
         ])
     });
+
+    pub static CGROUP_PIDS_TAB: Lazy<CgroupTab> = Lazy::new(|| {
+        CgroupTab::new(vec![
+            ViewItem::from_default(Pids(PidsCurrent)),
+            ViewItem::from_default(Pids(CurrentPctOfMax)),
+            ViewItem::from_default(Pids(PidsMax)),
+            ViewItem::from_default(Pids(PidsEventsMax)),
+            ViewItem::from_default(NrDescendants),
+            ViewItem::from_default(NrDyingDescendants),
+            ViewItem::from_default(Populated),
+            ViewItem::from_default(Frozen),
+        ])
+    });
 }
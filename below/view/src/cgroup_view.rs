@@ -23,7 +23,8 @@ use cursive::Cursive;
 
 use crate::cgroup_tabs::{
     default_tabs::{
-        CGROUP_CPU_TAB, CGROUP_GENERAL_TAB, CGROUP_IO_TAB, CGROUP_MEM_TAB, CGROUP_PRESSURE_TAB,
+        CGROUP_CPU_TAB, CGROUP_GENERAL_TAB, CGROUP_IO_TAB, CGROUP_MEM_TAB, CGROUP_PIDS_TAB,
+        CGROUP_PRESSURE_TAB,
     },
     CgroupTab,
 };
@@ -71,17 +72,26 @@ impl StateCommon for CgroupState {
     }
 
     fn set_sort_tag_from_tab_idx(&mut self, tab: &str, idx: usize, reverse: &mut bool) -> bool {
+        let dynamic_tab;
         let sort_order = match idx {
             0 => Self::TagType::Name,
-            _ => self
-                .sort_tags
-                .get(tab)
-                .unwrap_or_else(|| panic!("Fail to find tab: {}", tab))
-                .view_items
-                .get(idx - 1)
-                .expect("Out of title scope")
-                .field_id
-                .to_owned(),
+            _ => match self.sort_tags.get(tab) {
+                Some(tab) => *tab,
+                None if tab == "NUMA" => {
+                    dynamic_tab = CgroupTab::numa(&self.get_model());
+                    &dynamic_tab
+                }
+                None if tab == "Custom" => {
+                    dynamic_tab = CgroupTab::custom(&self.get_model());
+                    &dynamic_tab
+                }
+                None => panic!("Fail to find tab: {}", tab),
+            }
+            .view_items
+            .get(idx - 1)
+            .expect("Out of title scope")
+            .field_id
+            .to_owned(),
         };
 
         self.set_sort_tag(sort_order, reverse)
@@ -110,6 +120,7 @@ impl StateCommon for CgroupState {
         sort_tags.insert("Mem".into(), &*CGROUP_MEM_TAB);
         sort_tags.insert("I/O".into(), &*CGROUP_IO_TAB);
         sort_tags.insert("Pressure".into(), &*CGROUP_PRESSURE_TAB);
+        sort_tags.insert("Pids".into(), &*CGROUP_PIDS_TAB);
         Self {
             collapsed_cgroups: Rc::new(RefCell::new(HashSet::new())),
             current_selected_cgroup: "<root>".into(),
@@ -138,8 +149,12 @@ impl CgroupState {
 }
 
 // TODO: Make CgroupView a collection of CgroupTab
-pub struct CgroupView {
-    tab: &'static CgroupTab,
+pub enum CgroupView {
+    Tab(&'static CgroupTab),
+    /// The NUMA tab, whose columns depend on the nodes of the model
+    Numa(Rc<RefCell<CgroupModel>>),
+    /// The Custom tab, whose columns depend on the extra cgroup files collected
+    Custom(Rc<RefCell<CgroupModel>>),
 }
 
 impl CgroupView {
@@ -197,41 +212,25 @@ impl CgroupView {
             "Mem".into(),
             "I/O".into(),
             "Pressure".into(),
+            "Pids".into(),
+            "NUMA".into(),
+            "Custom".into(),
         ];
         let mut tabs_map: HashMap<String, CgroupView> = HashMap::new();
-        tabs_map.insert(
-            "General".into(),
-            CgroupView {
-                tab: &*CGROUP_GENERAL_TAB,
-            },
-        );
-        tabs_map.insert(
-            "CPU".into(),
-            CgroupView {
-                tab: &*CGROUP_CPU_TAB,
-            },
-        );
-        tabs_map.insert(
-            "Mem".into(),
-            CgroupView {
-                tab: &*CGROUP_MEM_TAB,
-            },
-        );
-        tabs_map.insert(
-            "I/O".into(),
-            CgroupView {
-                tab: &*CGROUP_IO_TAB,
-            },
-        );
-        tabs_map.insert(
-            "Pressure".into(),
-            CgroupView {
-                tab: &*CGROUP_PRESSURE_TAB,
-            },
-        );
+        tabs_map.insert("General".into(), CgroupView::Tab(&*CGROUP_GENERAL_TAB));
+        tabs_map.insert("CPU".into(), CgroupView::Tab(&*CGROUP_CPU_TAB));
+        tabs_map.insert("Mem".into(), CgroupView::Tab(&*CGROUP_MEM_TAB));
+        tabs_map.insert("I/O".into(), CgroupView::Tab(&*CGROUP_IO_TAB));
+        tabs_map.insert("Pressure".into(), CgroupView::Tab(&*CGROUP_PRESSURE_TAB));
+        tabs_map.insert("Pids".into(), CgroupView::Tab(&*CGROUP_PIDS_TAB));
         let user_data = c
             .user_data::<ViewState>()
             .expect("No data stored in Cursive Object!");
+        tabs_map.insert("NUMA".into(), CgroupView::Numa(user_data.cgroup.clone()));
+        tabs_map.insert(
+            "Custom".into(),
+            CgroupView::Custom(user_data.cgroup.clone()),
+        );
         StatsView::new(
             "Cgroup",
             tabs,
@@ -301,7 +300,11 @@ impl ViewBridge for CgroupView {
         "cgroup_view"
     }
     fn get_title_vec(&self) -> Vec<String> {
-        self.tab.get_title_vec()
+        match self {
+            Self::Tab(tab) => tab.get_title_vec(),
+            Self::Numa(model) => CgroupTab::numa(&model.borrow()).get_title_vec(),
+            Self::Custom(model) => CgroupTab::custom(&model.borrow()).get_title_vec(),
+        }
     }
 
     fn get_rows(
@@ -309,6 +312,10 @@ impl ViewBridge for CgroupView {
         state: &Self::StateType,
         offset: Option<usize>,
     ) -> Vec<(StyledString, String)> {
-        self.tab.get_rows(state, offset)
+        match self {
+            Self::Tab(tab) => tab.get_rows(state, offset),
+            Self::Numa(_) => CgroupTab::numa(&state.get_model()).get_rows(state, offset),
+            Self::Custom(_) => CgroupTab::custom(&state.get_model()).get_rows(state, offset),
+        }
     }
 }
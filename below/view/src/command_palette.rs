@@ -50,6 +50,7 @@ pub struct CommandPalette {
     content: String,
     filter: Option<String>,
     fold: bool,
+    threads: bool,
     mode: CPMode,
     cmd_view: RefCell<EditView>,
     cmd_controllers: Rc<RefCell<HashMap<&'static str, Controllers>>>,
@@ -75,6 +76,12 @@ impl View for CommandPalette {
             printer.print((max_x, 0), text);
         }
 
+        if self.threads {
+            let text = "| Threads |";
+            max_x -= text.len();
+            printer.print((max_x, 0), text);
+        }
+
         match self.mode {
             CPMode::Command => {
                 printer.print((0, 1), ":");
@@ -128,6 +135,7 @@ impl CommandPalette {
             content: content.into(),
             filter: None,
             fold: false,
+            threads: false,
             mode: CPMode::Info,
             cmd_view: RefCell::new(
                 EditView::new()
@@ -265,6 +273,10 @@ impl CommandPalette {
         self.fold = !self.fold;
     }
 
+    pub fn toggle_threads(&mut self) {
+        self.threads = !self.threads;
+    }
+
     fn print_info(&self, printer: &Printer, pos: Vec2, idx: usize) {
         if idx + printer.size.x > self.content.len() {
             printer.print(pos, &self.content[idx..]);
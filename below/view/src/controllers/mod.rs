@@ -116,6 +116,12 @@ make_controllers!(
     NSample: NextSample,
     PSample: PrevSample,
     Pause: PauseImpl,
+    NHost: NextHost,
+    PHost: PrevHost,
+    Mark: MarkSample,
+    Diff: DiffPopup,
+    NAnnotation: NextAnnotation,
+    PAnnotation: PrevAnnotation,
     Quit: QuitImpl,
     Help: HelpMenu,
     Process: ProcessView,
@@ -123,6 +129,7 @@ make_controllers!(
     System: SystemView,
     Zoom: ZoomView,
     Fold: FoldProcessView,
+    Threads: ThreadsProcessView,
     NextPage: NextPageImpl,
     PrevPage: PrevPageImpl,
     Url: URLPopup,
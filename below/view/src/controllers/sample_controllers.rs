@@ -14,7 +14,12 @@
 
 use super::*;
 
+use std::time::{Duration, UNIX_EPOCH};
+
 use crate::{jump_popup, ViewMode};
+use cursive::view::Scrollable;
+use cursive::views::{OnEventView, Panel, ResizedView, TextView};
+use model::{DiffSortKey, ModelDiff};
 use store::Direction;
 
 // Jump forward
@@ -147,3 +152,212 @@ make_event_controller!(
         crate::status_bar::refresh(c);
     }
 );
+
+/// Show the next (or previous) host in the cgroup and process views while
+/// replaying several hosts
+fn switch_host(c: &mut Cursive, forward: bool) {
+    let (nr_hosts, current_host) = {
+        let view_state = c.user_data::<ViewState>().expect("user data not set");
+        (view_state.hosts.len(), view_state.current_host)
+    };
+    if nr_hosts == 0 {
+        view_warn!(c, "Only a single host is being replayed");
+        return;
+    }
+    let idx = if forward {
+        (current_host + 1) % nr_hosts
+    } else {
+        (current_host + nr_hosts - 1) % nr_hosts
+    };
+    let view_state = c.user_data::<ViewState>().expect("user data not set");
+    if !view_state.switch_host(idx) {
+        let name = view_state.hosts[idx].name.clone();
+        view_warn!(c, "No data available for host {}", name);
+        return;
+    }
+    refresh(c);
+}
+
+// Next host
+make_event_controller!(
+    NextHost,
+    "next_host",
+    "nh",
+    Event::Char(']'),
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        switch_host(c, true);
+    }
+);
+
+// Previous host
+make_event_controller!(
+    PrevHost,
+    "prev_host",
+    "ph",
+    Event::Char('['),
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        switch_host(c, false);
+    }
+);
+
+// Mark the current sample as one end of the diff window
+make_event_controller!(
+    MarkSample,
+    "mark",
+    "mk",
+    Event::Char('M'),
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        let view_state = c.user_data::<ViewState>().expect("user data not set");
+        view_state.diff_mark = Some(view_state.timestamp);
+    }
+);
+
+/// Show how cgroups and processes changed between the marked sample and
+/// the current one, sorted by `sort`
+fn diff_popup(c: &mut Cursive, sort: DiffSortKey) {
+    let (mode, mark, timestamp) = {
+        let view_state = c.user_data::<ViewState>().expect("user data not set");
+        (
+            view_state.mode.clone(),
+            view_state.diff_mark,
+            view_state.timestamp,
+        )
+    };
+    let adv = match mode {
+        ViewMode::Pause(adv) | ViewMode::Replay(adv) => adv,
+        ViewMode::Live(_) => {
+            view_warn!(c, "Diff is only available in replay and live-paused mode");
+            return;
+        }
+    };
+    let mark = match mark {
+        Some(mark) => mark,
+        None => {
+            view_warn!(c, "Mark a sample first");
+            return;
+        }
+    };
+    let (begin, end) = if mark < timestamp {
+        (mark, timestamp)
+    } else {
+        (timestamp, mark)
+    };
+    let (begin_model, window_model) = match adv.borrow_mut().get_window_models(begin, end) {
+        Some(models) => models,
+        None => {
+            view_warn!(c, "Marked sample is the current sample");
+            return;
+        }
+    };
+    let mut diff = ModelDiff::new(&begin_model, &window_model);
+    diff.sort(sort);
+
+    let title = format!(
+        "Diff {} - {}",
+        common::util::systemtime_to_datetime(diff.begin),
+        common::util::systemtime_to_datetime(diff.end)
+    );
+    c.add_fullscreen_layer(ResizedView::with_full_screen(
+        OnEventView::new(
+            Panel::new(
+                TextView::new(base_render::diff::render_model_diff(&diff, None)).scrollable(),
+            )
+            .title(title),
+        )
+        .on_event(EventTrigger::from('q').or('D'), |c| {
+            c.pop_layer();
+        }),
+    ));
+}
+
+// Diff between the marked sample and the current one
+make_event_controller!(
+    DiffPopup,
+    "diff",
+    "df",
+    Event::Char('D'),
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let sort = match cmd_vec.get(1).map(|key| key.parse::<DiffSortKey>()) {
+            Some(Ok(sort)) => sort,
+            Some(Err(e)) => {
+                view_warn!(c, "{}", e);
+                return;
+            }
+            None => DiffSortKey::Mem,
+        };
+        diff_popup(c, sort);
+    }
+);
+
+/// Jump to the sample of the next (or previous) annotation
+fn jump_to_annotation(c: &mut Cursive, forward: bool) {
+    let (mode, target) = {
+        let view_state = c.user_data::<ViewState>().expect("user data not set");
+        let current = view_state.current_annotation_range();
+        let target = if forward {
+            view_state.annotations[current.end..].first()
+        } else {
+            view_state.annotations[..current.start].last()
+        };
+        (
+            view_state.mode.clone(),
+            target.map(|a| UNIX_EPOCH + Duration::from_secs(a.timestamp)),
+        )
+    };
+    let adv = match mode {
+        ViewMode::Pause(adv) | ViewMode::Replay(adv) => adv,
+        ViewMode::Live(_) => {
+            view_warn!(
+                c,
+                "Jumping to annotations is only available in replay and live-paused mode"
+            );
+            return;
+        }
+    };
+    let target = match target {
+        Some(target) => target,
+        None => {
+            view_warn!(
+                c,
+                "No {} annotation",
+                if forward { "next" } else { "previous" }
+            );
+            return;
+        }
+    };
+    let model = adv.borrow_mut().jump_sample_to(target);
+    if let Some(model) = model {
+        c.user_data::<ViewState>()
+            .expect("user data not set")
+            .update(model);
+    }
+    refresh(c);
+}
+
+// Next annotation
+make_event_controller!(
+    NextAnnotation,
+    "next_annotation",
+    "na",
+    Event::Char('>'),
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        jump_to_annotation(c, true);
+    }
+);
+
+// Previous annotation
+make_event_controller!(
+    PrevAnnotation,
+    "prev_annotation",
+    "pa",
+    Event::Char('<'),
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        jump_to_annotation(c, false);
+    }
+);
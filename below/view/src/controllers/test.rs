@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use super::*;
+use crate::cgroup_view::CgroupView;
 use crate::fake_view::FakeView;
 use crate::View;
 
@@ -342,3 +343,182 @@ next_col = 'd'
         Some(&Controllers::Cgroup)
     );
 }
+
+/// Write a frame of host `hostname` at each of `timestamps` into a new store
+/// at `dir`
+fn write_host_store(dir: &std::path::Path, hostname: &str, timestamps: &[u64]) {
+    use std::time::{Duration, UNIX_EPOCH};
+    use store::{CompressionMode, DataFrame, Format, StoreWriter};
+
+    let mut writer = StoreWriter::new_with_timestamp(
+        dir,
+        UNIX_EPOCH + Duration::from_secs(timestamps[0]),
+        CompressionMode::None,
+        Format::Cbor,
+    )
+    .expect("Failed to create store");
+    for ts in timestamps {
+        let mut frame = DataFrame::default();
+        frame.sample.system.hostname = hostname.to_owned();
+        writer
+            .put(
+                UNIX_EPOCH + Duration::from_secs(*ts),
+                &frame,
+                common::logutil::get_logger(),
+            )
+            .expect("Failed to store data");
+    }
+}
+
+/// Replay the stores at `dirs` from `begin` with `dirs[current_host]` as
+/// the current host, like `below replay` with several hosts
+fn new_multi_host_view(
+    dirs: &[std::path::PathBuf],
+    begin: u64,
+    current_host: usize,
+) -> cursive::CursiveRunnable {
+    use crate::{status_bar, system_view, MainViewState, ViewMode};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let begin = UNIX_EPOCH + Duration::from_secs(begin);
+    let hosts = dirs
+        .iter()
+        .map(|dir| {
+            let mut advance = store::advance::new_advance_local(
+                common::logutil::get_logger(),
+                dir.clone(),
+                begin,
+            );
+            advance.initialize();
+            (dir.display().to_string(), Rc::new(RefCell::new(advance)))
+        })
+        .collect::<Vec<_>>();
+    let advance = hosts[current_host].1.clone();
+    let model = advance
+        .borrow_mut()
+        .jump_sample_to(begin)
+        .expect("No data for current host");
+
+    let mut c = cursive::CursiveRunnable::dummy();
+    c.set_user_data(ViewState::new_with_advance(
+        MainViewState::Cgroup,
+        model,
+        ViewMode::Replay(advance),
+    ));
+    c.user_data::<ViewState>()
+        .expect("No data stored in Cursive object!")
+        .set_hosts(hosts, current_host);
+    let status_bar = status_bar::new(&mut c);
+    c.add_layer(status_bar);
+    let system_view = system_view::new(&mut c);
+    c.add_layer(system_view);
+    let cgroup_view = CgroupView::new(&mut c);
+    c.add_layer(cgroup_view);
+    c
+}
+
+/// Timestamp and hostname shown by the view, and timestamps of the other
+/// hosts (None for the current one)
+fn host_state(c: &mut Cursive) -> (u64, String, Vec<Option<u64>>) {
+    use common::util::get_unix_timestamp;
+
+    let view_state = c.user_data::<ViewState>().expect("user data not set");
+    (
+        get_unix_timestamp(view_state.timestamp),
+        view_state.system.borrow().hostname.clone(),
+        view_state
+            .hosts
+            .iter()
+            .enumerate()
+            .map(|(idx, host)| match &host.system {
+                Some((ts, _)) if idx != view_state.current_host => Some(get_unix_timestamp(*ts)),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+#[test]
+fn test_multi_host_lockstep() {
+    use crate::ViewMode;
+    use store::Direction;
+
+    let tempdir = TempDir::new("below_view_test").expect("Failed to create temp dir");
+    let dirs = vec![tempdir.path().join("a"), tempdir.path().join("b")];
+    write_host_store(&dirs[0], "a", &[1000, 1005, 1010]);
+    write_host_store(&dirs[1], "b", &[1002, 1007]);
+    let mut c = new_multi_host_view(&dirs, 1000, 0);
+    assert_eq!(
+        host_state(&mut c),
+        (1000, "a".to_owned(), vec![None, Some(1002)])
+    );
+
+    // Other hosts follow the current one, showing their latest sample at or
+    // before the current timestamp
+    let view_state = c.user_data::<ViewState>().expect("user data not set");
+    let model = match &view_state.mode {
+        ViewMode::Replay(advance) => advance.borrow_mut().advance(Direction::Forward),
+        _ => panic!("Expected replay mode"),
+    }
+    .expect("No next sample");
+    view_state.update(model);
+    assert_eq!(
+        host_state(&mut c),
+        (1005, "a".to_owned(), vec![None, Some(1002)])
+    );
+}
+
+#[test]
+fn test_multi_host_switch() {
+    let tempdir = TempDir::new("below_view_test").expect("Failed to create temp dir");
+    let dirs = vec![tempdir.path().join("a"), tempdir.path().join("b")];
+    write_host_store(&dirs[0], "a", &[1000, 1005, 1010]);
+    write_host_store(&dirs[1], "b", &[1002, 1007]);
+    let mut c = new_multi_host_view(&dirs, 1005, 0);
+    assert_eq!(
+        host_state(&mut c),
+        (1005, "a".to_owned(), vec![None, Some(1002)])
+    );
+
+    // Switching keeps the timestamp and shows the host's sample at or
+    // before it
+    Controllers::NHost.callback::<CgroupView>(&mut c, &[]);
+    assert_eq!(c.user_data::<ViewState>().unwrap().current_host, 1);
+    assert_eq!(
+        host_state(&mut c),
+        (1005, "b".to_owned(), vec![Some(1005), None])
+    );
+
+    // Wraps around in both directions without drifting
+    Controllers::NHost.callback::<CgroupView>(&mut c, &[]);
+    assert_eq!(c.user_data::<ViewState>().unwrap().current_host, 0);
+    assert_eq!(
+        host_state(&mut c),
+        (1005, "a".to_owned(), vec![None, Some(1002)])
+    );
+    Controllers::PHost.callback::<CgroupView>(&mut c, &[]);
+    assert_eq!(c.user_data::<ViewState>().unwrap().current_host, 1);
+    assert_eq!(
+        host_state(&mut c),
+        (1005, "b".to_owned(), vec![Some(1005), None])
+    );
+}
+
+#[test]
+fn test_multi_host_without_data() {
+    let tempdir = TempDir::new("below_view_test").expect("Failed to create temp dir");
+    // The first host has no data at all, e.g. a fresh store
+    let dirs = vec![tempdir.path().join("a"), tempdir.path().join("b")];
+    write_host_store(&dirs[1], "b", &[1000, 1005]);
+    let mut c = new_multi_host_view(&dirs, 1000, 1);
+    assert_eq!(host_state(&mut c), (1000, "b".to_owned(), vec![None, None]));
+
+    // Switching to it keeps the current host
+    let view_state = c.user_data::<ViewState>().expect("user data not set");
+    assert!(!view_state.switch_host(0));
+    Controllers::PHost.callback::<CgroupView>(&mut c, &[]);
+    assert_eq!(c.user_data::<ViewState>().unwrap().current_host, 1);
+    assert_eq!(host_state(&mut c), (1000, "b".to_owned(), vec![None, None]));
+}
@@ -360,6 +360,32 @@ make_event_controller!(
     }
 );
 
+// Expand processes in process view into their threads
+make_event_controller!(
+    ThreadsProcessView,
+    "threads",
+    "",
+    Event::Char('H'),
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        let current_state = c
+            .user_data::<ViewState>()
+            .expect("No data stored in Cursive object!")
+            .main_view_state
+            .clone();
+
+        // NB: scope the borrowing to refresh() doesn't re-borrow and panic
+        if current_state == MainViewState::Process {
+            let mut process_view = crate::process_view::ProcessView::get_process_view(c);
+            process_view.get_cmd_palette().toggle_threads();
+            process_view.state.borrow_mut().toggle_threads();
+        }
+
+        // Redraw screen now so we don't have to wait until next tick
+        refresh(c)
+    }
+);
+
 // utl function to parse page length
 fn parse_page_length(cmd_vec: &[&str]) -> Result<usize, String> {
     static DEFAULT_PAGE_LENGTH: usize = 15;
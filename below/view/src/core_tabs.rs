@@ -18,7 +18,8 @@ use crate::stats_view::StateCommon;
 use base_render::{get_fixed_width, RenderConfigBuilder as Rc};
 use common::util::get_prefix;
 use model::system::{
-    MemoryModelFieldId, SingleCpuModelFieldId, SingleDiskModelFieldId, VmModelFieldId,
+    MemoryModelFieldId, SingleCpuModelFieldId, SingleDiskModelFieldId, SingleNodeModelFieldId,
+    VmModelFieldId,
 };
 use model::EnumIter;
 
@@ -198,3 +199,44 @@ impl CoreTab for CoreDisk {
             .collect()
     }
 }
+
+#[derive(Default, Clone)]
+pub struct CoreNuma;
+
+impl CoreTab for CoreNuma {
+    fn get_title_vec(&self) -> Vec<String> {
+        SingleNodeModelFieldId::unit_variant_iter()
+            .map(|field_id| ViewItem::from_default(field_id).config.render_title())
+            .collect()
+    }
+
+    fn get_rows(&self, state: &CoreState, offset: Option<usize>) -> Vec<(StyledString, String)> {
+        state
+            .get_model()
+            .nodes
+            .values()
+            .filter(|snm| {
+                if let Some(f) = &state.filter {
+                    snm.idx.to_string().starts_with(f)
+                } else {
+                    true
+                }
+            })
+            .map(|snm| {
+                (
+                    std::iter::once(SingleNodeModelFieldId::Idx)
+                        .chain(
+                            SingleNodeModelFieldId::unit_variant_iter()
+                                .skip(offset.unwrap_or(0) + 1),
+                        )
+                        .fold(StyledString::new(), |mut line, field_id| {
+                            line.append(ViewItem::from_default(field_id).render(snm));
+                            line.append_plain(" ");
+                            line
+                        }),
+                    "".to_owned(),
+                )
+            })
+            .collect()
+    }
+}
@@ -67,6 +67,7 @@ pub enum CoreView {
     Mem(CoreMem),
     Vm(CoreVm),
     Disk(CoreDisk),
+    Numa(CoreNuma),
 }
 
 impl CoreView {
@@ -89,12 +90,19 @@ impl CoreView {
             }
         });
 
-        let tabs = vec!["CPU".into(), "Mem".into(), "Vm".into(), "Disk".into()];
+        let tabs = vec![
+            "CPU".into(),
+            "Mem".into(),
+            "Vm".into(),
+            "Disk".into(),
+            "NUMA".into(),
+        ];
         let mut tabs_map: HashMap<String, CoreView> = HashMap::new();
         tabs_map.insert("CPU".into(), CoreView::Cpu(Default::default()));
         tabs_map.insert("Mem".into(), CoreView::Mem(Default::default()));
         tabs_map.insert("Vm".into(), CoreView::Vm(Default::default()));
         tabs_map.insert("Disk".into(), CoreView::Disk(Default::default()));
+        tabs_map.insert("NUMA".into(), CoreView::Numa(Default::default()));
         let user_data = c
             .user_data::<ViewState>()
             .expect("No data stored in Cursive Object!");
@@ -125,6 +133,7 @@ impl CoreView {
             Self::Mem(inner) => Box::new(inner.clone()),
             Self::Vm(inner) => Box::new(inner.clone()),
             Self::Disk(inner) => Box::new(inner.clone()),
+            Self::Numa(inner) => Box::new(inner.clone()),
         }
     }
 }
@@ -12,14 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::render::{HasViewStyle, ViewStyle, CPU_HIGHLIGHT, MEM_HIGHLIGHT, PRESSURE_HIGHLIGHT};
+use crate::render::{
+    HasViewStyle, ViewStyle, CPU_HIGHLIGHT, LIMIT_HIGHLIGHT, MEM_HIGHLIGHT, PRESSURE_HIGHLIGHT,
+};
 
 impl HasViewStyle for model::SingleCgroupModel {
     fn get_view_style(field_id: &Self::FieldId) -> Option<ViewStyle> {
-        use model::SingleCgroupModelFieldId::{Cpu, Pressure};
+        use model::SingleCgroupModelFieldId::{Cpu, Io, IoDetails, Mem, Pids, Pressure};
         match field_id {
             Cpu(field_id) => model::CgroupCpuModel::get_view_style(field_id),
+            Mem(field_id) => model::CgroupMemoryModel::get_view_style(field_id),
+            Io(field_id) => model::CgroupIoModel::get_view_style(field_id),
+            IoDetails(field_id) => model::CgroupIoModel::get_view_style(&field_id.subquery_id),
             Pressure(field_id) => model::CgroupPressureModel::get_view_style(field_id),
+            Pids(field_id) => model::CgroupPidsModel::get_view_style(field_id),
             _ => None,
         }
     }
@@ -27,9 +33,40 @@ impl HasViewStyle for model::SingleCgroupModel {
 
 impl HasViewStyle for model::CgroupCpuModel {
     fn get_view_style(field_id: &Self::FieldId) -> Option<ViewStyle> {
-        use model::CgroupCpuModelFieldId::{SystemPct, UsagePct, UserPct};
+        use model::CgroupCpuModelFieldId::{SystemPct, UsagePct, UsagePctOfMax, UserPct};
         match field_id {
             UsagePct | UserPct | SystemPct => Some(CPU_HIGHLIGHT.clone()),
+            UsagePctOfMax => Some(LIMIT_HIGHLIGHT.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl HasViewStyle for model::CgroupMemoryModel {
+    fn get_view_style(field_id: &Self::FieldId) -> Option<ViewStyle> {
+        use model::CgroupMemoryModelFieldId::{SwapPctOfMax, TotalPctOfMax};
+        match field_id {
+            TotalPctOfMax | SwapPctOfMax => Some(LIMIT_HIGHLIGHT.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl HasViewStyle for model::CgroupIoModel {
+    fn get_view_style(field_id: &Self::FieldId) -> Option<ViewStyle> {
+        use model::CgroupIoModelFieldId::{RbytesPctOfMax, WbytesPctOfMax};
+        match field_id {
+            RbytesPctOfMax | WbytesPctOfMax => Some(LIMIT_HIGHLIGHT.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl HasViewStyle for model::CgroupPidsModel {
+    fn get_view_style(field_id: &Self::FieldId) -> Option<ViewStyle> {
+        use model::CgroupPidsModelFieldId::CurrentPctOfMax;
+        match field_id {
+            CurrentPctOfMax => Some(LIMIT_HIGHLIGHT.clone()),
             _ => None,
         }
     }
@@ -88,3 +125,4 @@ impl HasViewStyle for model::SingleCpuModel {}
 impl HasViewStyle for model::VmModel {}
 
 impl HasViewStyle for model::SingleDiskModel {}
+impl HasViewStyle for model::SingleNodeModel {}
@@ -74,6 +74,24 @@ fn get_description(controller: &Controllers) -> &'static str {
         Controllers::Pause => {
             "pause/resume the live mode. While pausing, use the above commands to go forwards or backwards in time"
         }
+        Controllers::NHost => {
+            "Show next host in cgroup and process view (replay of several hosts only)."
+        }
+        Controllers::PHost => {
+            "Show previous host in cgroup and process view (replay of several hosts only)."
+        }
+        Controllers::Mark => {
+            "Mark the current sample as one end of the diff window (replay and live-paused mode)."
+        }
+        Controllers::Diff => {
+            "Show changes of cgroups and processes between the marked and the current sample, biggest first (replay and live-paused mode)."
+        }
+        Controllers::NAnnotation => {
+            "Jump to the sample of the next annotation (replay and live-paused mode)."
+        }
+        Controllers::PAnnotation => {
+            "Jump to the sample of the previous annotation (replay and live-paused mode)."
+        }
         Controllers::Quit => "Quit.",
         Controllers::Help => "Toggle help menu.",
         Controllers::Process => "Show process view.",
@@ -81,6 +99,9 @@ fn get_description(controller: &Controllers) -> &'static str {
         Controllers::System => "Show system core view.",
         Controllers::Zoom => "Zoom into process view filtered by selected cgroup.",
         Controllers::Fold => "Fold processes (post filter) and display aggregated values.",
+        Controllers::Threads => {
+            "Expand/collapse the threads of every process (process view only, threads are collected with --collect-threads)."
+        }
         Controllers::NextPage => "scroll down 15 lines primary display.",
         Controllers::PrevPage => "scroll up 15 lines primary display.",
         Controllers::Url => "Show Corresponding Below Web URL",
@@ -94,6 +115,7 @@ fn get_args(controller: &Controllers) -> &'static str {
         Controllers::Filter => "Name",
         Controllers::JForward => "Time",
         Controllers::JBackward => "Time",
+        Controllers::Diff => "SortKey",
         _ => "-",
     }
 }
@@ -150,11 +172,18 @@ fn fill_controllers(
         cmd_map.get(&Controllers::NSample).unwrap().to_string(),
         cmd_map.get(&Controllers::PSample).unwrap().to_string(),
         cmd_map.get(&Controllers::Pause).unwrap().to_string(),
+        cmd_map.get(&Controllers::NHost).unwrap().to_string(),
+        cmd_map.get(&Controllers::PHost).unwrap().to_string(),
+        cmd_map.get(&Controllers::Mark).unwrap().to_string(),
+        cmd_map.get(&Controllers::Diff).unwrap().to_string(),
+        cmd_map.get(&Controllers::NAnnotation).unwrap().to_string(),
+        cmd_map.get(&Controllers::PAnnotation).unwrap().to_string(),
         cmd_map.get(&Controllers::SortCol).unwrap().to_string(),
         cmd_map.get(&Controllers::Filter).unwrap().to_string(),
         cmd_map.get(&Controllers::CFilter).unwrap().to_string(),
         cmd_map.get(&Controllers::Zoom).unwrap().to_string(),
         cmd_map.get(&Controllers::Fold).unwrap().to_string(),
+        cmd_map.get(&Controllers::Threads).unwrap().to_string(),
         cmd_map.get(&Controllers::Process).unwrap().to_string(),
         cmd_map.get(&Controllers::Cgroup).unwrap().to_string(),
         cmd_map.get(&Controllers::System).unwrap().to_string(),
@@ -28,6 +28,7 @@
 /// ```
 /// * Status Bar: Displays datetime, elapsed time, hostname, and below version.
 /// * System View: Displays overall system stats including cpu, mem, io, iface, transport, and network.
+///   When several hosts are replayed at once, it displays a summary row per host instead.
 /// * Stats View: Display the detailed stats. Please check the stats view section for more details.
 ///
 /// ### Stats View
@@ -52,6 +53,7 @@
 ///   or `S` to sort in ascending or descending order.
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
 use std::time::{Duration, SystemTime};
 
@@ -71,8 +73,10 @@ use common::logutil::get_last_log_to_display;
 use common::open_source_shim;
 use common::util::{
     get_belowrc_cmd_section_key, get_belowrc_filename, get_belowrc_view_section_key,
+    get_unix_timestamp,
 };
 use model::{CgroupModel, Model, NetworkModel, ProcessModel, SystemModel};
+use store::annotation::Annotation;
 use store::Advance;
 extern crate render as base_render;
 
@@ -178,6 +182,16 @@ fn refresh(c: &mut Cursive) {
     }
 }
 
+/// A host replayed together with others. All hosts follow the timestamp of
+/// the current one, whose Advance is the one of ViewMode::Replay.
+pub struct ReplayHost {
+    pub name: String,
+    pub advance: Rc<RefCell<Advance>>,
+    /// Timestamp and system model of the sample closest to the current
+    /// timestamp. Not kept up to date for the current host.
+    pub system: Option<(SystemTime, SystemModel)>,
+}
+
 pub struct ViewState {
     pub time_elapsed: Duration,
     /// Keep track of the lowest seen `time_elapsed` so that view can highlight abnormal
@@ -193,19 +207,87 @@ pub struct ViewState {
     pub mode: ViewMode,
     pub event_controllers: Rc<RefCell<HashMap<Event, controllers::Controllers>>>,
     pub cmd_controllers: Rc<RefCell<HashMap<&'static str, controllers::Controllers>>>,
+    /// All replayed hosts if there is more than one, empty otherwise
+    pub hosts: Vec<ReplayHost>,
+    /// Index into `hosts` of the host the cgroup and process views show
+    pub current_host: usize,
+    /// Timestamp marked as the other end of the window shown by the diff
+    /// popup
+    pub diff_mark: Option<SystemTime>,
+    /// Annotations of the replayed store sorted by timestamp
+    pub annotations: Vec<Annotation>,
 }
 
 impl ViewState {
     pub fn update(&mut self, model: Model) {
+        let timestamp = model.timestamp;
+        self.update_at(model, timestamp);
+    }
+
+    /// Show `model` with the timeline at `timestamp`, which differs from the
+    /// model's if the current host has no sample at `timestamp`
+    fn update_at(&mut self, model: Model, timestamp: SystemTime) {
         self.time_elapsed = model.time_elapsed;
         if model.time_elapsed.as_secs() != 0 && model.time_elapsed < self.lowest_time_elapsed {
             self.lowest_time_elapsed = model.time_elapsed;
         }
-        self.timestamp = model.timestamp;
+        self.timestamp = timestamp;
         self.system.replace(model.system);
         self.cgroup.replace(model.cgroup);
         self.process.replace(model.process);
         self.network.replace(model.network);
+        self.sync_hosts();
+    }
+
+    /// Move all hosts other than the current one to their latest sample at
+    /// or before the current timestamp
+    fn sync_hosts(&mut self) {
+        let timestamp = self.timestamp;
+        let current_host = self.current_host;
+        for (idx, host) in self.hosts.iter_mut().enumerate() {
+            // The current host's Advance may be borrowed by the caller
+            if idx == current_host {
+                continue;
+            }
+            host.system = host
+                .advance
+                .borrow_mut()
+                .jump_sample_at_or_before(timestamp)
+                .map(|model| (model.timestamp, model.system));
+        }
+    }
+
+    /// Replay `hosts` in lockstep with `hosts[current_host]` as the current
+    /// host, whose Advance must be the one of the current ViewMode::Replay
+    pub fn set_hosts(&mut self, hosts: Vec<(String, Rc<RefCell<Advance>>)>, current_host: usize) {
+        self.hosts = hosts
+            .into_iter()
+            .map(|(name, advance)| ReplayHost {
+                name,
+                advance,
+                system: None,
+            })
+            .collect();
+        self.current_host = current_host;
+        self.sync_hosts();
+    }
+
+    /// Make `hosts[idx]` the current host, showing its latest sample at or
+    /// before the current timestamp. The timestamp does not change. Returns
+    /// false if it has no data.
+    pub fn switch_host(&mut self, idx: usize) -> bool {
+        let advance = self.hosts[idx].advance.clone();
+        let model = match advance
+            .borrow_mut()
+            .jump_sample_at_or_before(self.timestamp)
+        {
+            Some(model) => model,
+            None => return false,
+        };
+        self.current_host = idx;
+        self.mode = ViewMode::Replay(advance);
+        self.update_at(model, self.timestamp);
+        true
     }
 
     pub fn new_with_advance(main_view_state: MainViewState, model: Model, mode: ViewMode) -> Self {
@@ -221,9 +303,31 @@ impl ViewState {
             mode,
             event_controllers: Rc::new(RefCell::new(HashMap::new())),
             cmd_controllers: Rc::new(RefCell::new(controllers::make_cmd_controller_map())),
+            hosts: Vec::new(),
+            current_host: 0,
+            diff_mark: None,
+            annotations: Vec::new(),
         }
     }
 
+    /// Range of `annotations` since the sample before the current one, i.e.
+    /// the ones whose timestamp jumps to the current sample
+    pub fn current_annotation_range(&self) -> Range<usize> {
+        let timestamp = get_unix_timestamp(self.timestamp);
+        let elapsed = self.time_elapsed.as_secs();
+        let begin = if elapsed == 0 {
+            self.annotations
+                .partition_point(|a| a.timestamp < timestamp)
+        } else {
+            self.annotations
+                .partition_point(|a| a.timestamp <= timestamp.saturating_sub(elapsed))
+        };
+        let end = self
+            .annotations
+            .partition_point(|a| a.timestamp <= timestamp);
+        begin..end
+    }
+
     pub fn view_mode_str(&self) -> &'static str {
         match self.mode {
             ViewMode::Live(_) => "live",
@@ -259,6 +363,32 @@ impl View {
         View { inner }
     }
 
+    /// Replay several hosts in lockstep. `hosts` are the names and Advances
+    /// of all hosts and `model` is the initial model of
+    /// `hosts[current_host]`, e.g. the first one with data.
+    pub fn new_with_hosts(
+        model: model::Model,
+        hosts: Vec<(String, Rc<RefCell<Advance>>)>,
+        current_host: usize,
+    ) -> View {
+        let mut view =
+            Self::new_with_advance(model, ViewMode::Replay(hosts[current_host].1.clone()));
+        view.inner
+            .user_data::<ViewState>()
+            .expect("No data stored in Cursive object!")
+            .set_hosts(hosts, current_host);
+        view
+    }
+
+    /// Annotations to show in the status bar. They must be sorted by
+    /// timestamp.
+    pub fn set_annotations(&mut self, annotations: Vec<Annotation>) {
+        self.inner
+            .user_data::<ViewState>()
+            .expect("No data stored in Cursive object!")
+            .annotations = annotations;
+    }
+
     pub fn cb_sink(&mut self) -> &::cursive::CbSink {
         self.inner.set_fps(4);
         self.inner.cb_sink()
@@ -15,7 +15,7 @@
 use crate::process_view::ProcessState;
 use crate::render::ViewItem;
 use crate::stats_view::StateCommon;
-use model::SingleProcessModel;
+use model::{Queriable, SingleProcessModel};
 
 use cursive::utils::markup::StyledString;
 use itertools::Itertools;
@@ -38,10 +38,19 @@ impl ProcessTab {
     fn get_process_field_line(
         &self,
         model: &SingleProcessModel,
+        thread: bool,
         offset: Option<usize>,
     ) -> StyledString {
         let mut line = StyledString::new();
-        line.append(default_tabs::COMM_VIEW_ITEM.render(model));
+        if thread {
+            let item = &*default_tabs::THREAD_COMM_VIEW_ITEM;
+            line.append(
+                item.config
+                    .render_indented(model.query(&item.field_id), /* depth */ 1),
+            );
+        } else {
+            line.append(default_tabs::COMM_VIEW_ITEM.render(model));
+        }
         line.append_plain(" ");
 
         for item in std::iter::once(&*default_tabs::CGROUP_VIEW_ITEM)
@@ -111,11 +120,26 @@ impl ProcessTab {
                     it.next().cloned()
                 }
             })
-            .map(|spm| {
-                (
-                    self.get_process_field_line(&spm, offset),
-                    spm.pid.unwrap_or(0).to_string(),
-                )
+            .flat_map(|spm| {
+                let pid = spm.pid.unwrap_or(0);
+                let mut rows = vec![(
+                    self.get_process_field_line(&spm, false, offset),
+                    pid.to_string(),
+                )];
+                // Threads follow their process, sorted the same way
+                if let (true, Some(threads)) = (state.threads, spm.threads.as_ref()) {
+                    let mut threads: Vec<&SingleProcessModel> = threads.values().collect();
+                    if let Some(sort_order) = state.sort_order.as_ref() {
+                        model::sort_queriables(&mut threads, sort_order, state.reverse);
+                    }
+                    rows.extend(threads.into_iter().map(|thread| {
+                        (
+                            self.get_process_field_line(thread, true, offset),
+                            format!("{}/{}", pid, thread.pid.unwrap_or(0)),
+                        )
+                    }));
+                }
+                rows
             })
             .collect()
     }
@@ -134,9 +158,13 @@ pub mod default_tabs {
         Cgroup, Cmdline, Comm, Cpu, Io, Mem, Pid, Ppid, State, UptimeSecs,
     };
 
+    use base_render::RenderConfigBuilder;
     use once_cell::sync::Lazy;
 
     pub static COMM_VIEW_ITEM: Lazy<ProcessViewItem> = Lazy::new(|| ViewItem::from_default(Comm));
+    pub static THREAD_COMM_VIEW_ITEM: Lazy<ProcessViewItem> = Lazy::new(|| {
+        ViewItem::from_default(Comm).update(RenderConfigBuilder::new().indented_prefix(" └ "))
+    });
     pub static CGROUP_VIEW_ITEM: Lazy<ProcessViewItem> =
         Lazy::new(|| ViewItem::from_default(Cgroup));
 
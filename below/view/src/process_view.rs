@@ -46,6 +46,7 @@ pub struct ProcessState {
     pub sort_tags: HashMap<String, &'static ProcessTab>,
     pub reverse: bool,
     pub fold: bool,
+    pub threads: bool,
     pub model: Rc<RefCell<ProcessModel>>,
 }
 
@@ -116,6 +117,7 @@ impl StateCommon for ProcessState {
             sort_tags,
             reverse: false,
             fold: false,
+            threads: false,
             model,
         }
     }
@@ -134,6 +136,10 @@ impl ProcessState {
         self.fold = !self.fold;
     }
 
+    pub fn toggle_threads(&mut self) {
+        self.threads = !self.threads;
+    }
+
     pub fn handle_state_for_entering_zoom(&mut self, current_selection: String) {
         self.cgroup_filter = Some(current_selection);
         std::mem::swap(&mut self.filter_cache_for_zoom, &mut self.filter);
@@ -147,6 +153,17 @@ impl ProcessState {
     }
 }
 
+/// Rows of threads are keyed by <pid>/<tid>, see ProcessTab::get_rows
+fn get_cmdline(model: &ProcessModel, selection: &str) -> String {
+    let pid = selection.split('/').next().unwrap_or(selection);
+    model
+        .processes
+        .get(&pid.parse::<i32>().unwrap_or(0))
+        .map_or("?".to_string(), |spm| {
+            spm.cmdline.clone().unwrap_or_else(|| "?".to_string())
+        })
+}
+
 pub struct ProcessView {
     tab: &'static ProcessTab,
 }
@@ -156,15 +173,7 @@ impl ProcessView {
         let mut list = SelectView::<String>::new();
         list.set_on_select(|c, pid: &String| {
             c.call_on_name(Self::get_view_name(), |view: &mut ViewType| {
-                let cmdline = view
-                    .state
-                    .borrow()
-                    .get_model()
-                    .processes
-                    .get(&pid.parse::<i32>().unwrap_or(0))
-                    .map_or("?".to_string(), |spm| {
-                        spm.cmdline.clone().unwrap_or_else(|| "?".to_string())
-                    });
+                let cmdline = get_cmdline(&view.state.borrow().get_model(), pid);
                 view.get_cmd_palette().set_info(cmdline);
             });
         });
@@ -271,15 +280,7 @@ impl ProcessView {
             view.get_detail_view().selection(),
         ) {
             (false, Some(selection)) => {
-                let cmdline = view
-                    .state
-                    .borrow()
-                    .get_model()
-                    .processes
-                    .get(&selection.parse::<i32>().unwrap_or(0))
-                    .map_or("?".to_string(), |spm| {
-                        spm.cmdline.clone().unwrap_or_else(|| "?".to_string())
-                    });
+                let cmdline = get_cmdline(&view.state.borrow().get_model(), &selection);
                 cmd_palette.set_info(cmdline)
             }
             _ => {}
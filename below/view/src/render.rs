@@ -29,6 +29,8 @@ pub enum ViewStyle {
 pub const PRESSURE_HIGHLIGHT: ViewStyle = ViewStyle::HighlightAbove(Field::F64(40.0));
 pub const CPU_HIGHLIGHT: ViewStyle = ViewStyle::HighlightAbove(Field::F64(100.0));
 pub const MEM_HIGHLIGHT: ViewStyle = ViewStyle::HighlightBelow(Field::U64(2 << 20));
+/// For usage as a percentage of a cgroup limit
+pub const LIMIT_HIGHLIGHT: ViewStyle = ViewStyle::HighlightAbove(Field::F64(90.0));
 
 #[derive(Clone, Default)]
 pub struct ViewConfig {
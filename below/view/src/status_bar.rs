@@ -29,14 +29,23 @@ fn get_content(c: &mut Cursive) -> impl Into<StyledString> {
         .user_data::<ViewState>()
         .expect("No data stored in Cursive object!");
     let datetime = DateTime::<Local>::from(view_state.timestamp);
+    // Samples of a capture burst are less than a second apart
+    let format = if datetime.timestamp_subsec_millis() != 0 {
+        "%m/%d/%Y %H:%M:%S%.3f UTC%:z"
+    } else {
+        "%m/%d/%Y %H:%M:%S UTC%:z"
+    };
     let mut header_str = StyledString::plain(format!(
         "{}{}",
-        datetime.format("%m/%d/%Y %H:%M:%S UTC%:z").to_string(),
+        datetime.format(format).to_string(),
         get_spacing()
     ));
 
     header_str.append_plain("Elapsed: ");
-    let elapsed_rendered = format!("{}s", view_state.time_elapsed.as_secs(),);
+    let elapsed_rendered = match view_state.time_elapsed.as_millis() {
+        millis @ 1..=999 => format!("{}ms", millis),
+        _ => format!("{}s", view_state.time_elapsed.as_secs()),
+    };
     let lowest = view_state.lowest_time_elapsed.as_secs();
     let this = view_state.time_elapsed.as_secs();
     // 1 second jitter happens pretty often due to integer rounding
@@ -59,6 +68,27 @@ fn get_content(c: &mut Cursive) -> impl Into<StyledString> {
     header_str.append_plain(crate::get_version_str());
     header_str.append_plain(get_spacing());
     header_str.append_plain(view_state.view_mode_str());
+    if !view_state.hosts.is_empty() {
+        header_str.append_plain(format!(
+            " (host {}/{})",
+            view_state.current_host + 1,
+            view_state.hosts.len()
+        ));
+    }
+
+    let annotations = &view_state.annotations[view_state.current_annotation_range()];
+    if let Some(last) = annotations.last() {
+        header_str.append_plain(get_spacing());
+        let more = if annotations.len() > 1 {
+            format!(" (+{} more)", annotations.len() - 1)
+        } else {
+            String::new()
+        };
+        header_str.append_styled(
+            format!("[{}] {}{}", last.source, last.text, more),
+            cursive::theme::Color::Light(cursive::theme::BaseColor::Yellow),
+        );
+    }
 
     header_str
 }
@@ -19,7 +19,9 @@ use crate::ViewState;
 
 mod render_impl {
     use std::collections::BTreeMap;
+    use std::time::SystemTime;
 
+    use chrono::{DateTime, Local};
     use cursive::utils::markup::StyledString;
     use once_cell::sync::Lazy;
 
@@ -63,6 +65,19 @@ mod render_impl {
         ]
     });
 
+    static SYS_HOST_ITEMS: Lazy<Vec<SystemViewItem>> = Lazy::new(|| {
+        use model::MemoryModelFieldId::{Anon, Free};
+        use model::SingleCpuModelFieldId::UsagePct;
+        use model::SystemModelFieldId::{Cpu, Mem, Vm};
+        use model::VmModelFieldId::PswpoutPerSec;
+        vec![
+            ViewItem::from_default(Cpu(UsagePct)),
+            ViewItem::from_default(Mem(Free)),
+            ViewItem::from_default(Mem(Anon)),
+            ViewItem::from_default(Vm(PswpoutPerSec)),
+        ]
+    });
+
     const ROW_NAME_WIDTH: usize = 15;
     const ROW_FIELD_NAME_WIDTH: usize = 9;
     const ROW_FIELD_WIDTH: usize = 21;
@@ -130,6 +145,41 @@ mod render_impl {
         )
     }
 
+    /// Summary row of one of several replayed hosts. `sample` is the time
+    /// and model of the host's sample, None if it has no data.
+    pub fn render_host_row(
+        name: &str,
+        current: bool,
+        sample: Option<(SystemTime, &SystemModel)>,
+    ) -> StyledString {
+        let name = format!("{}{}", if current { "> " } else { "  " }, name);
+        match sample {
+            Some((timestamp, model)) => {
+                let mut row = StyledString::new();
+                row.append(base_render::get_fixed_width(&name, ROW_NAME_WIDTH));
+                row.append(base_render::get_fixed_width("Time", ROW_FIELD_NAME_WIDTH));
+                row.append(base_render::get_fixed_width(
+                    &DateTime::<Local>::from(timestamp)
+                        .format("%H:%M:%S")
+                        .to_string(),
+                    ROW_FIELD_WIDTH,
+                ));
+                for item in SYS_HOST_ITEMS.iter().cloned() {
+                    let title = item.config.render_config.get_title();
+                    row.append(base_render::get_fixed_width(title, ROW_FIELD_NAME_WIDTH));
+                    row.append(item.update(Rc::new().width(ROW_FIELD_WIDTH)).render(model));
+                }
+                row
+            }
+            None => {
+                let mut row = StyledString::new();
+                row.append(base_render::get_fixed_width(&name, ROW_NAME_WIDTH));
+                row.append_plain("No data");
+                row
+            }
+        }
+    }
+
     pub fn render_iface_row(ifaces: &BTreeMap<String, SingleNetModel>) -> StyledString {
         use model::SingleNetModelFieldId::RxBytesPerSec;
         use model::SingleNetModelFieldId::TxBytesPerSec;
@@ -147,6 +197,26 @@ fn fill_content(c: &mut Cursive, v: &mut LinearLayout) {
         .user_data::<ViewState>()
         .expect("No data stored in Cursive object!");
 
+    if !view_state.hosts.is_empty() {
+        let system_model = view_state.system.borrow();
+        let mut view = LinearLayout::vertical();
+        for (idx, host) in view_state.hosts.iter().enumerate() {
+            let current = idx == view_state.current_host;
+            let sample = if current {
+                Some((view_state.timestamp, &*system_model))
+            } else {
+                host.system
+                    .as_ref()
+                    .map(|(timestamp, system)| (*timestamp, system))
+            };
+            view.add_child(TextView::new(render_impl::render_host_row(
+                &host.name, current, sample,
+            )));
+        }
+        *v = view;
+        return;
+    }
+
     let system_model = view_state.system.borrow();
     let network_model = view_state.network.borrow();
     let cpu_row = render_impl::render_cpu_row(&system_model);